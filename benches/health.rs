@@ -41,6 +41,6 @@ pub fn health_check_benchmark(c: &mut Criterion) {
     });
 
     c.bench_with_input(BenchmarkId::new(BENCHMARK_NAME, "health"), &(), |b, _| {
-        b.to_async(&rt).iter(|| check_health());
+        b.to_async(&rt).iter(check_health);
     });
 }
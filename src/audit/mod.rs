@@ -0,0 +1,181 @@
+mod record;
+mod sink;
+
+use crate::configuration::audit::AuditConfiguration;
+use std::cell::RefCell;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{sync_channel, SyncSender, TrySendError};
+use std::sync::Arc;
+use tracing::{error, info, warn};
+
+pub use record::{ciphertext_key_version, AuditRecord, Operation, Outcome};
+pub use sink::Sink;
+
+/// Records waiting to be written, once full new records are dropped rather than buffered without limit.
+const MAX_PENDING_RECORDS: usize = 10_000;
+
+tokio::task_local! {
+    static VAULT_REQUEST_IDS: RefCell<Vec<String>>;
+}
+
+/// Runs `future`, collecting the ids of the Vault requests it makes, see [vault_request].
+pub async fn collect_vault_requests<F: Future>(future: F) -> (F::Output, Vec<String>) {
+    VAULT_REQUEST_IDS
+        .scope(RefCell::new(vec![]), async {
+            let output = future.await;
+            (output, VAULT_REQUEST_IDS.with(|ids| ids.take()))
+        })
+        .await
+}
+
+/// Records the id Vault assigned to a request, when made within [collect_vault_requests].
+pub fn vault_request(id: &str) {
+    if !id.is_empty() {
+        let _ = VAULT_REQUEST_IDS.try_with(|ids| ids.borrow_mut().push(id.to_string()));
+    }
+}
+
+/// Writes [AuditRecord]s as JSON lines on a dedicated thread, so requests never wait on the sink. When the sink
+/// falls behind (ex: a stalled disk) records are dropped and counted, rather than held in memory.
+pub struct Auditor {
+    sender: Option<SyncSender<String>>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl Auditor {
+    pub fn new(config: &AuditConfiguration) -> std::io::Result<Self> {
+        let Some(sink) = &config.sink else {
+            return Ok(Self::disabled());
+        };
+        let mut sink = Sink::open(sink, config)?;
+        let (sender, receiver) = sync_channel::<String>(MAX_PENDING_RECORDS);
+        let dropped = Arc::new(AtomicU64::new(0));
+        let unreported = dropped.clone();
+        std::thread::Builder::new()
+            .name("audit".to_string())
+            .spawn(move || {
+                let mut reported = 0;
+                for line in receiver {
+                    if let Err(error) = sink.write_line(&line) {
+                        error!("Failed to write audit record: {}", error);
+                    }
+                    let dropped = unreported.load(Ordering::Relaxed);
+                    if dropped > reported {
+                        warn!(
+                            "Dropped {} audit record(s) while the audit sink was falling behind",
+                            dropped - reported
+                        );
+                        reported = dropped;
+                    }
+                }
+            })?;
+        info!("Auditing KMS requests to {:?}", config.sink);
+        Ok(Self {
+            sender: Some(sender),
+            dropped,
+        })
+    }
+
+    pub fn disabled() -> Self {
+        Self {
+            sender: None,
+            dropped: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Records dropped because the sink was falling behind, since the auditor was created.
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    pub fn record(&self, record: &AuditRecord) {
+        let Some(sender) = &self.sender else {
+            return;
+        };
+        match serde_json::to_string(record) {
+            Ok(line) => match sender.try_send(line) {
+                Ok(()) => {}
+                Err(TrySendError::Full(_)) => {
+                    if self.dropped.fetch_add(1, Ordering::Relaxed) == 0 {
+                        warn!("The audit sink is falling behind, dropping audit records");
+                    }
+                }
+                Err(TrySendError::Disconnected(_)) => {
+                    error!("Failed to write audit record, the audit sink has stopped")
+                }
+            },
+            Err(error) => error!("Failed to serialize audit record: {}", error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod auditor {
+    use super::*;
+    use crate::configuration::audit::AuditSink;
+    use pretty_assertions::assert_eq;
+
+    #[tokio::test]
+    async fn collects_the_ids_of_vault_requests() {
+        let ((), ids) = collect_vault_requests(async {
+            vault_request("first");
+            tokio::task::yield_now().await;
+            vault_request("");
+            vault_request("second");
+        })
+        .await;
+        assert_eq!(ids, vec!["first".to_string(), "second".to_string()]);
+    }
+
+    #[test]
+    fn ignores_vault_requests_outside_of_a_collection() {
+        vault_request("ignored");
+    }
+
+    #[test]
+    fn writes_records_as_json_lines() {
+        let path = std::env::temp_dir().join(format!(
+            "vault-kms-provider-auditor-{}.log",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+        let auditor = Auditor::new(&AuditConfiguration::new(
+            Some(AuditSink::File(path.clone())),
+            None,
+            None,
+        ))
+        .unwrap();
+        auditor.record(&AuditRecord::new(Operation::Encrypt, Some("a".to_string())));
+        auditor.record(&AuditRecord::new(Operation::Decrypt, Some("b".to_string())));
+        drop(auditor);
+        let mut lines = vec![];
+        for _ in 0..100 {
+            lines = std::fs::read_to_string(&path)
+                .unwrap()
+                .lines()
+                .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+                .collect::<Vec<_>>();
+            if lines.len() == 2 {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        assert_eq!(lines[0]["uid"], "a");
+        assert_eq!(lines[1]["operation"], "decrypt");
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn drops_records_once_the_sink_falls_behind() {
+        let (sender, _receiver) = sync_channel(1);
+        let auditor = Auditor {
+            sender: Some(sender),
+            dropped: Arc::new(AtomicU64::new(0)),
+        };
+        for uid in ["a", "b", "c"] {
+            auditor.record(&AuditRecord::new(Operation::Encrypt, Some(uid.to_string())));
+        }
+        assert_eq!(auditor.dropped(), 2);
+    }
+}
@@ -0,0 +1,120 @@
+use serde::Serialize;
+use std::time::Duration;
+use tonic::Status;
+
+const CIPHERTEXT_PREFIX: &str = "vault:v";
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Operation {
+    Encrypt,
+    Decrypt,
+    Status,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Outcome {
+    Success,
+    Failure,
+}
+
+/// A single audited KMS request. Only sizes are recorded, never plaintext or ciphertext.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct AuditRecord {
+    pub timestamp: String,
+    pub uid: Option<String>,
+    pub operation: Operation,
+    pub key_id: Option<String>,
+    pub key_version: Option<String>,
+    pub plaintext_size: Option<usize>,
+    pub ciphertext_size: Option<usize>,
+    pub latency_ms: f64,
+    pub outcome: Outcome,
+    /// gRPC status code returned to the caller.
+    pub code: String,
+    pub vault_request_ids: Vec<String>,
+}
+
+impl AuditRecord {
+    pub fn new(operation: Operation, uid: Option<String>) -> Self {
+        Self {
+            timestamp: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            uid: uid.filter(|uid| !uid.is_empty()),
+            operation,
+            key_id: None,
+            key_version: None,
+            plaintext_size: None,
+            ciphertext_size: None,
+            latency_ms: 0.0,
+            outcome: Outcome::Success,
+            code: format!("{:?}", tonic::Code::Ok),
+            vault_request_ids: vec![],
+        }
+    }
+
+    pub fn finish<T>(
+        &mut self,
+        result: &Result<T, Status>,
+        latency: Duration,
+        vault_request_ids: Vec<String>,
+    ) {
+        self.latency_ms = latency.as_secs_f64() * 1000.0;
+        self.vault_request_ids = vault_request_ids;
+        if let Err(status) = result {
+            self.outcome = Outcome::Failure;
+            self.code = format!("{:?}", status.code());
+        }
+    }
+}
+
+/// Key version a Vault ciphertext (`vault:v<version>:...`) was encrypted with.
+pub fn ciphertext_key_version(ciphertext: &[u8]) -> Option<String> {
+    let version = ciphertext.strip_prefix(CIPHERTEXT_PREFIX.as_bytes())?;
+    let end = version.iter().position(|byte| *byte == b':')?;
+    let version = std::str::from_utf8(&version[..end]).ok()?;
+    (!version.is_empty() && version.bytes().all(|byte| byte.is_ascii_digit()))
+        .then(|| version.to_string())
+}
+
+#[cfg(test)]
+mod audit_record {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tonic::Code;
+
+    #[test]
+    fn reads_the_key_version_from_a_ciphertext() {
+        assert_eq!(
+            ciphertext_key_version(b"vault:v12:c2VjcmV0"),
+            Some("12".to_string())
+        );
+        assert_eq!(ciphertext_key_version(b"vault:vx:c2VjcmV0"), None);
+        assert_eq!(ciphertext_key_version(b"c2VjcmV0"), None);
+    }
+
+    #[test]
+    fn records_the_status_code_of_failed_requests() {
+        let mut record = AuditRecord::new(Operation::Decrypt, Some("uid".to_string()));
+        record.finish::<()>(
+            &Err(Status::unavailable("Vault is sealed")),
+            Duration::from_millis(5),
+            vec!["request".to_string()],
+        );
+        assert_eq!(record.outcome, Outcome::Failure);
+        assert_eq!(record.code, format!("{:?}", Code::Unavailable));
+        assert_eq!(record.latency_ms, 5.0);
+        assert_eq!(record.vault_request_ids, vec!["request".to_string()]);
+    }
+
+    #[test]
+    fn serializes_to_a_single_json_line() {
+        let record = AuditRecord::new(Operation::Status, Some(String::new()));
+        let line = serde_json::to_string(&record).unwrap();
+        assert!(!line.contains('\n'));
+        let value: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(value["operation"], "status");
+        assert_eq!(value["outcome"], "success");
+        assert_eq!(value["uid"], serde_json::Value::Null);
+    }
+}
@@ -0,0 +1,33 @@
+use crate::configuration::audit::{AuditConfiguration, AuditSink};
+use crate::utilities::rotating_file::RotatingFile;
+use std::io::Write;
+
+/// Destination of audit records, each written as a single line.
+pub enum Sink {
+    Stdout,
+    File(RotatingFile),
+}
+
+impl Sink {
+    pub fn open(sink: &AuditSink, config: &AuditConfiguration) -> std::io::Result<Self> {
+        match sink {
+            AuditSink::Stdout => Ok(Self::Stdout),
+            AuditSink::File(path) => Ok(Self::File(RotatingFile::open(
+                path,
+                config.max_file_size,
+                config.max_files,
+            )?)),
+        }
+    }
+
+    pub fn write_line(&mut self, line: &str) -> std::io::Result<()> {
+        match self {
+            Self::Stdout => {
+                let mut stdout = std::io::stdout().lock();
+                writeln!(stdout, "{}", line)?;
+                stdout.flush()
+            }
+            Self::File(file) => file.write_line(line),
+        }
+    }
+}
@@ -1,11 +1,65 @@
-use bytes::Bytes;
-use http::{Response, StatusCode};
-use http_body_util::Full;
-use std::convert::Infallible;
-
-pub async fn health_check() -> Result<Response<Full<Bytes>>, Infallible> {
-    Ok(Response::builder()
-        .status(StatusCode::OK)
-        .body(Full::new(Bytes::from("OK")))
-        .expect("Unable to build request"))
+use crate::checks::report::Check;
+use crate::utilities::heartbeat::{Heartbeats, TaskStatus};
+
+/// Checks that the process is alive, failing these gets the container restarted.
+pub fn liveness_checks(heartbeats: &Heartbeats) -> Vec<Check> {
+    let mut checks = vec![Check::ok("ping")];
+    checks.extend(
+        heartbeats
+            .statuses()
+            .into_iter()
+            .map(|(name, status)| match status {
+                TaskStatus::Alive => Check::ok(name),
+                TaskStatus::Stalled(since) => Check::failed(
+                    name,
+                    format!("No progress reported for {}s", since.as_secs()),
+                ),
+                TaskStatus::Stopped => Check::failed(name, "Task has stopped"),
+            }),
+    );
+    checks
+}
+
+#[cfg(test)]
+mod health {
+    use super::liveness_checks;
+    use crate::checks::report::Check;
+    use crate::utilities::heartbeat::Heartbeats;
+    use pretty_assertions::assert_eq;
+    use std::time::Duration;
+
+    #[test]
+    fn passes_if_every_task_is_alive() {
+        let heartbeats = Heartbeats::new(Duration::from_secs(60));
+        let _heartbeat = heartbeats.register("grpc-server");
+        assert_eq!(
+            liveness_checks(&heartbeats),
+            vec![Check::ok("ping"), Check::ok("grpc-server")]
+        );
+    }
+
+    #[test]
+    fn fails_if_a_task_has_stopped() {
+        let heartbeats = Heartbeats::new(Duration::from_secs(60));
+        let _heartbeat = heartbeats.register("grpc-server");
+        drop(heartbeats.register("credentials-watcher"));
+        assert_eq!(
+            liveness_checks(&heartbeats),
+            vec![
+                Check::ok("ping"),
+                Check::ok("grpc-server"),
+                Check::failed("credentials-watcher", "Task has stopped")
+            ]
+        );
+    }
+
+    #[test]
+    fn fails_if_a_task_has_stalled() {
+        let heartbeats = Heartbeats::new(Duration::from_millis(10));
+        let _heartbeat = heartbeats.register("grpc-server");
+        std::thread::sleep(Duration::from_millis(20));
+        let checks = liveness_checks(&heartbeats);
+        assert_eq!(checks[1].name, "grpc-server");
+        assert!(!checks[1].is_ok());
+    }
 }
@@ -0,0 +1,231 @@
+use crate::checks::{is_authorized, unauthorized, Peer};
+use crate::utilities::logging::{LogFilter, LogFilterError};
+use crate::utilities::source::Source;
+use bytes::Bytes;
+use http::{header, Method, Request, Response, StatusCode};
+use http_body_util::{BodyExt, Full};
+use hyper::body::Body;
+use std::convert::Infallible;
+use tracing::{info, warn};
+
+fn response(status: StatusCode, message: impl Into<Bytes>) -> Response<Full<Bytes>> {
+    Response::builder()
+        .status(status)
+        .body(Full::new(message.into()))
+        .expect("Unable to build response")
+}
+
+/// `GET` returns the current filter directive of the logs, `PUT` replaces it with the request body,
+/// ex: `info,lib::vault=debug`.
+pub async fn log_level<B>(
+    request: Request<B>,
+    peer: &Peer,
+    filter: Option<&LogFilter>,
+    admin_token: Option<&Source>,
+) -> Result<Response<Full<Bytes>>, Infallible>
+where
+    B: Body,
+    B::Error: std::fmt::Display,
+{
+    if !is_authorized(&request, peer, admin_token) {
+        warn!("Rejected log level request from {}", peer);
+        return Ok(unauthorized());
+    }
+    let Some(filter) = filter else {
+        return Ok(response(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "The log level cannot be changed at runtime",
+        ));
+    };
+    let current = match filter.directive() {
+        Ok(directive) => directive,
+        Err(error) => {
+            return Ok(response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                error.to_string(),
+            ))
+        }
+    };
+    match *request.method() {
+        Method::GET => Ok(response(StatusCode::OK, current)),
+        Method::PUT => {
+            let body = match request.into_body().collect().await {
+                Ok(body) => body.to_bytes(),
+                Err(error) => return Ok(response(StatusCode::BAD_REQUEST, error.to_string())),
+            };
+            let directive = String::from_utf8_lossy(&body).trim().to_string();
+            match filter.set_directive(&directive) {
+                Ok(()) => {
+                    info!(
+                        "Log level changed from \"{}\" to \"{}\" by {}",
+                        current, directive, peer
+                    );
+                    Ok(response(
+                        StatusCode::OK,
+                        filter.directive().unwrap_or(directive),
+                    ))
+                }
+                Err(error @ LogFilterError::Invalid(_)) => {
+                    Ok(response(StatusCode::BAD_REQUEST, error.to_string()))
+                }
+                Err(error) => Ok(response(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    error.to_string(),
+                )),
+            }
+        }
+        _ => Ok(Response::builder()
+            .status(StatusCode::METHOD_NOT_ALLOWED)
+            .header(header::ALLOW, "GET, PUT")
+            .body(Full::new(Bytes::from("Method not allowed")))
+            .expect("Unable to build response")),
+    }
+}
+
+#[cfg(test)]
+mod log_level {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    fn peer(address: &str) -> Peer {
+        Peer {
+            address: Some(address.parse().unwrap()),
+            client_certificate: false,
+        }
+    }
+
+    fn unix_socket() -> Peer {
+        Peer {
+            address: None,
+            client_certificate: false,
+        }
+    }
+
+    fn request(method: Method, token: Option<&str>, body: &str) -> Request<Full<Bytes>> {
+        let mut builder = Request::builder().method(method).uri("/debug/log-level");
+        if let Some(token) = token {
+            builder = builder.header(header::AUTHORIZATION, format!("Bearer {}", token));
+        }
+        builder
+            .body(Full::new(Bytes::from(body.to_string())))
+            .unwrap()
+    }
+
+    async fn body(response: Response<Full<Bytes>>) -> String {
+        String::from_utf8(
+            response
+                .into_body()
+                .collect()
+                .await
+                .unwrap()
+                .to_bytes()
+                .to_vec(),
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn reads_and_changes_the_directive_from_the_unix_socket() {
+        let (layer, filter) = LogFilter::new("info");
+        let _subscriber =
+            tracing::subscriber::set_default(tracing_subscriber::registry().with(layer));
+
+        let resp = log_level(
+            request(Method::PUT, None, "info,lib::vault=debug\n"),
+            &unix_socket(),
+            Some(&filter),
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let resp = log_level(
+            request(Method::GET, None, ""),
+            &unix_socket(),
+            Some(&filter),
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(body(resp).await, "lib::vault=debug,info");
+    }
+
+    #[tokio::test]
+    async fn requires_the_admin_token_from_tcp_clients() {
+        let (_layer, filter) = LogFilter::new("info");
+        let token = Source::Value("admin-token".to_string());
+        for (token, admin_token, status) in [
+            (None, Some(&token), StatusCode::UNAUTHORIZED),
+            (Some("wrong-token"), Some(&token), StatusCode::UNAUTHORIZED),
+            (Some("admin-token"), None, StatusCode::UNAUTHORIZED),
+            (Some("admin-token"), Some(&token), StatusCode::OK),
+        ] {
+            let resp = log_level(
+                request(Method::GET, token, ""),
+                &peer("127.0.0.1:50000"),
+                Some(&filter),
+                admin_token,
+            )
+            .await
+            .unwrap();
+            assert_eq!(resp.status(), status, "{:?}", token);
+        }
+    }
+
+    #[tokio::test]
+    async fn trusts_clients_with_a_verified_certificate() {
+        let (_layer, filter) = LogFilter::new("info");
+        let resp = log_level(
+            request(Method::GET, None, ""),
+            &Peer {
+                address: Some("10.0.0.5:50000".parse().unwrap()),
+                client_certificate: true,
+            },
+            Some(&filter),
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn rejects_invalid_directives() {
+        let (_layer, filter) = LogFilter::new("info");
+        let resp = log_level(
+            request(Method::PUT, None, "lib::vault=loud"),
+            &unix_socket(),
+            Some(&filter),
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(filter.directive().unwrap(), "info");
+    }
+
+    #[tokio::test]
+    async fn rejects_other_methods() {
+        let (_layer, filter) = LogFilter::new("info");
+        let resp = log_level(
+            request(Method::POST, None, "debug"),
+            &unix_socket(),
+            Some(&filter),
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(resp.status(), StatusCode::METHOD_NOT_ALLOWED);
+    }
+
+    #[tokio::test]
+    async fn is_unavailable_without_a_reloadable_filter() {
+        let resp = log_level(request(Method::GET, None, ""), &unix_socket(), None, None)
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+}
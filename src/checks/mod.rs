@@ -1,131 +1,643 @@
+use crate::configuration::health::HealthCheckConfiguration;
+use crate::utilities::heartbeat::Heartbeats;
+use crate::utilities::logging::LogFilter;
+use crate::utilities::metrics::Metrics;
+use crate::utilities::socket::Socket;
+use crate::utilities::source::Source;
+use crate::utilities::watcher::RefreshStatus;
+use crate::vault::Initialization;
 use bytes::Bytes;
-use http::{Response, StatusCode};
+use http::{header, HeaderValue, Method, Request, Response, StatusCode, Uri};
 use http_body_util::Full;
+use hyper::body::Body;
 use hyper::server::conn::http1;
 use hyper::service::service_fn;
 use hyper_util::rt::TokioIo;
 use std::convert::Infallible;
+use std::fmt::{Display, Formatter};
 use std::net::SocketAddr;
-use std::str::FromStr;
-use tokio::net::TcpListener;
-use tracing::{error, info, instrument};
+use std::os::unix::fs::FileTypeExt;
+use std::sync::Arc;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::{TcpListener, UnixListener};
+use tokio_rustls::TlsAcceptor;
+use tracing::{error, info, instrument, warn};
 
 mod health;
+mod log_level;
 mod readiness;
+mod report;
+pub mod selftest;
+pub(crate) mod tls;
 
-async fn checks(uri: String, socket_path: String) -> Result<Response<Full<Bytes>>, Infallible> {
-    if uri.contains("ready") {
-        readiness::readiness_check(&socket_path).await
-    } else if uri.contains("health") {
-        health::health_check().await
+pub use report::{Check, CheckStatus, Report};
+
+/// `/livez` and `/readyz` follow Kubernetes, `/healthz` and `/health` are kept as liveness aliases.
+const LIVENESS_PATHS: [&str; 3] = ["/livez", "/healthz", "/health"];
+const READINESS_PATHS: [&str; 2] = ["/readyz", "/ready"];
+const METRICS_PATH: &str = "/metrics";
+const LOG_LEVEL_PATH: &str = "/debug/log-level";
+const SELFTEST_PATH: &str = "/selftest";
+const UNIX_SOCKET_PREFIX: &str = "unix://";
+
+fn response(status: StatusCode, message: &'static str) -> Response<Full<Bytes>> {
+    Response::builder()
+        .status(status)
+        .body(Full::new(Bytes::from(message)))
+        .expect("Unable to build response")
+}
+
+/// Whether the query asks for every check to be listed, ex: `?verbose` or `?verbose=true`.
+fn is_verbose(uri: &Uri) -> bool {
+    uri.query().is_some_and(|query| {
+        query.split('&').any(|parameter| {
+            let (name, value) = parameter.split_once('=').unwrap_or((parameter, "true"));
+            name == "verbose" && !matches!(value, "false" | "0")
+        })
+    })
+}
+
+/// Client of a connection to the health check server.
+#[derive(Clone, Debug)]
+pub struct Peer {
+    /// Address of a TCP client, clients of the unix socket have none.
+    address: Option<SocketAddr>,
+    /// Whether the client presented a certificate signed by the configured client CA.
+    client_certificate: bool,
+}
+
+impl Peer {
+    /// Clients of the unix socket and those with a verified certificate can use admin endpoints, TCP clients
+    /// are not trusted by their address as other containers of the pod can reach localhost.
+    pub fn is_trusted(&self) -> bool {
+        self.client_certificate || self.address.is_none()
+    }
+}
+
+impl Display for Peer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.address {
+            Some(address) => write!(f, "{}", address),
+            None => f.write_str("unix socket"),
+        }
+    }
+}
+
+/// Compares every byte regardless of where the first difference is, so the token cannot be guessed by timing.
+fn matches(expected: &[u8], actual: &[u8]) -> bool {
+    expected.len() == actual.len()
+        && expected
+            .iter()
+            .zip(actual)
+            .fold(0, |difference, (a, b)| difference | (a ^ b))
+            == 0
+}
+
+/// Requests from trusted peers are allowed, others must carry the admin token as a bearer token.
+fn is_authorized<B>(request: &Request<B>, peer: &Peer, admin_token: Option<&Source>) -> bool {
+    if peer.is_trusted() {
+        return true;
+    }
+    let Some(bearer) = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+    else {
+        return false;
+    };
+    match admin_token.map(Source::trimmed) {
+        Some(Ok(token)) => matches(token.as_bytes(), bearer.trim().as_bytes()),
+        Some(Err(error)) => {
+            warn!("Unable to read the admin token: {}", error);
+            false
+        }
+        None => false,
+    }
+}
+
+fn unauthorized() -> Response<Full<Bytes>> {
+    Response::builder()
+        .status(StatusCode::UNAUTHORIZED)
+        .header(header::WWW_AUTHENTICATE, "Bearer")
+        .body(Full::new(Bytes::from("Unauthorized")))
+        .expect("Unable to build response")
+}
+
+/// Shared by every connection to the health check server.
+#[derive(Clone)]
+struct State {
+    socket_path: String,
+    refresh_status: Arc<RefreshStatus>,
+    initialization: Arc<Initialization>,
+    metrics: Arc<Metrics>,
+    heartbeats: Arc<Heartbeats>,
+    admin_token: Option<Source>,
+}
+
+/// Routes requests by their exact path. Probes and metrics answer `GET` and `HEAD` requests, the
+/// body of the latter is left out by hyper. Admin endpoints require the peer to be authorized.
+async fn route<B>(
+    request: Request<B>,
+    peer: &Peer,
+    state: &State,
+) -> Result<Response<Full<Bytes>>, Infallible>
+where
+    B: Body,
+    B::Error: Display,
+{
+    let path = request.uri().path();
+    if path == LOG_LEVEL_PATH {
+        return log_level::log_level(
+            request,
+            peer,
+            LogFilter::global(),
+            state.admin_token.as_ref(),
+        )
+        .await;
+    }
+    if path == SELFTEST_PATH {
+        if !is_authorized(&request, peer, state.admin_token.as_ref()) {
+            warn!("Rejected self-test request from {}", peer);
+            return Ok(unauthorized());
+        }
+        if request.method() != Method::GET {
+            let mut response = response(StatusCode::METHOD_NOT_ALLOWED, "Method not allowed");
+            response
+                .headers_mut()
+                .insert(header::ALLOW, HeaderValue::from_static("GET"));
+            return Ok(response);
+        }
+        info!("Running self-test requested by {}", peer);
+        return Ok(selftest::run(&Socket::default(), &state.socket_path)
+            .await
+            .response());
+    }
+    if !LIVENESS_PATHS.contains(&path) && !READINESS_PATHS.contains(&path) && path != METRICS_PATH {
+        return Ok(response(StatusCode::NOT_FOUND, "Not found"));
+    }
+    if !matches!(*request.method(), Method::GET | Method::HEAD) {
+        let mut response = response(StatusCode::METHOD_NOT_ALLOWED, "Method not allowed");
+        response
+            .headers_mut()
+            .insert(header::ALLOW, HeaderValue::from_static("GET, HEAD"));
+        return Ok(response);
+    }
+    let verbose = is_verbose(request.uri());
+    Ok(if path == METRICS_PATH {
+        Response::builder()
+            .status(StatusCode::OK)
+            .header(header::CONTENT_TYPE, "text/plain; version=0.0.4")
+            .body(Full::new(Bytes::from(state.metrics.render())))
+            .expect("Unable to build response")
+    } else if READINESS_PATHS.contains(&path) {
+        Report::new(readiness::readiness_checks(
+            &state.socket_path,
+            &state.refresh_status,
+            &state.initialization,
+        ))
+        .response(verbose)
     } else {
-        Ok(Response::builder()
-            .status(StatusCode::NOT_FOUND)
-            .body(Full::new(Bytes::from("Not found")))
-            .expect("Unable to build response"))
-    }
-}
-
-#[instrument]
-pub async fn serve(http_address: &str, socket_path: &str) -> Result<(), std::io::Error> {
-    let addr = SocketAddr::from_str(&http_address)
-        .expect(&format!("Invalid http address: {:?}", http_address));
-    let listener = TcpListener::bind(addr).await?;
-    info!(
-        "Health and liveness checks listening at: {:?}",
-        addr.to_string()
-    );
-    loop {
-        let (stream, _) = listener.accept().await?;
-        let io = TokioIo::new(stream);
-        let socket_path = socket_path.to_string();
-
-        tokio::task::spawn(async move {
-            if let Err(err) = http1::Builder::new()
-                .serve_connection(
-                    io,
-                    service_fn(|request| {
-                        checks(request.uri().path().to_string(), socket_path.clone())
-                    }),
-                )
-                .await
-            {
-                error!("Error serving connection: {:?}", err);
+        Report::new(health::liveness_checks(&state.heartbeats)).response(verbose)
+    })
+}
+
+async fn serve_connection<S>(stream: S, peer: Peer, state: State)
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    if let Err(err) = http1::Builder::new()
+        .serve_connection(
+            TokioIo::new(stream),
+            service_fn(|request| {
+                let (peer, state) = (peer.clone(), state.clone());
+                async move { route(request, &peer, &state).await }
+            }),
+        )
+        .await
+    {
+        error!("Error serving connection: {:?}", err);
+    }
+}
+
+/// Completes the TLS handshake when serving HTTPS, then serves the connection.
+async fn accept<S>(stream: S, address: Option<SocketAddr>, tls: Option<TlsAcceptor>, state: State)
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let mut peer = Peer {
+        address,
+        client_certificate: false,
+    };
+    match tls {
+        None => serve_connection(stream, peer, state).await,
+        Some(tls) => match tls.accept(stream).await {
+            Ok(stream) => {
+                peer.client_certificate = stream.get_ref().1.peer_certificates().is_some();
+                serve_connection(stream, peer, state).await
+            }
+            Err(error) => warn!("TLS handshake with {} failed: {}", peer, error),
+        },
+    }
+}
+
+/// Binds the unix socket, replacing a socket left behind by a previous run.
+fn bind_unix(path: &str) -> Result<UnixListener, std::io::Error> {
+    if std::fs::symlink_metadata(path).is_ok_and(|metadata| metadata.file_type().is_socket()) {
+        std::fs::remove_file(path)?;
+    }
+    UnixListener::bind(path)
+}
+
+#[instrument(skip(config, refresh_status, initialization, metrics, heartbeats))]
+pub async fn serve(
+    config: &HealthCheckConfiguration,
+    socket_path: &str,
+    refresh_status: Arc<RefreshStatus>,
+    initialization: Arc<Initialization>,
+    metrics: Arc<Metrics>,
+    heartbeats: Arc<Heartbeats>,
+) -> Result<(), std::io::Error> {
+    let tls = match (&config.cert, &config.key) {
+        (Some(cert), Some(key)) => Some(tls::acceptor(cert, key, config.client_ca.as_deref())?),
+        (None, None) => None,
+        _ => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "Both a certificate and a key are required to serve health checks over HTTPS",
+            ))
+        }
+    };
+    let state = State {
+        socket_path: socket_path.to_string(),
+        refresh_status,
+        initialization,
+        metrics,
+        heartbeats,
+        admin_token: config.admin_token.clone(),
+    };
+    let scheme = if tls.is_some() { "https" } else { "http" };
+    match config.endpoint.strip_prefix(UNIX_SOCKET_PREFIX) {
+        Some(path) => {
+            let listener = bind_unix(path)?;
+            info!(
+                "Health and liveness checks listening ({}) at: {:?}",
+                scheme, config.endpoint
+            );
+            loop {
+                let (stream, _) = listener.accept().await?;
+                tokio::task::spawn(accept(stream, None, tls.clone(), state.clone()));
+            }
+        }
+        None => {
+            let listener = TcpListener::bind(&config.endpoint).await?;
+            info!(
+                "Health and liveness checks listening ({}) at: {:?}",
+                scheme,
+                listener.local_addr()?.to_string()
+            );
+            loop {
+                let (stream, address) = listener.accept().await?;
+                tokio::task::spawn(accept(stream, Some(address), tls.clone(), state.clone()));
             }
-        });
+        }
     }
 }
 
 #[cfg(test)]
 mod serve {
-    use super::serve;
+    use super::{serve, SELFTEST_PATH};
+    use crate::configuration::health::HealthCheckConfiguration;
+    use crate::utilities::heartbeat::Heartbeats;
+    use crate::utilities::metrics::Metrics;
+    use crate::utilities::watcher::RefreshStatus;
+    use crate::vault::Initialization;
     use reqwest::StatusCode;
+    use std::future::Future;
+    use std::sync::Arc;
     use std::time::Duration;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    const CERT: &str = "test_files/certs/tls.crt";
+    const KEY: &str = "test_files/certs/tls.key";
+    const CA: &str = "test_files/certs/ca.crt";
+
+    fn config(endpoint: &str) -> HealthCheckConfiguration {
+        HealthCheckConfiguration {
+            endpoint: endpoint.to_string(),
+            admin_token: None,
+            cert: None,
+            key: None,
+            client_ca: None,
+            liveness_threshold: Duration::from_secs(60),
+            selftest_on_startup: false,
+        }
+    }
+
+    /// Runs `request` against the health check server once it has had time to start.
+    async fn against<F, T>(config: HealthCheckConfiguration, request: F) -> T
+    where
+        F: Future<Output = T>,
+    {
+        tokio::select! {
+            result = async {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                request.await
+            } => result,
+            result = serve(
+                &config,
+                "socket/path",
+                Arc::new(RefreshStatus::default()),
+                Arc::new(Initialization::default()),
+                Arc::new(Metrics::default()),
+                Arc::new(Heartbeats::new(config.liveness_threshold)),
+            ) => {
+                panic!("Health checks stopped serving: {:?}", result)
+            }
+        }
+    }
+
+    fn https_client(identity: bool) -> reqwest::Client {
+        let ca = std::fs::read(CA).unwrap();
+        let mut builder = reqwest::Client::builder()
+            .add_root_certificate(reqwest::Certificate::from_pem(&ca).unwrap());
+        if identity {
+            let mut pem = std::fs::read(CERT).unwrap();
+            pem.push(b'\n');
+            pem.extend(std::fs::read(KEY).unwrap());
+            builder = builder.identity(reqwest::Identity::from_pem(&pem).unwrap());
+        }
+        builder.build().unwrap()
+    }
 
     #[tokio::test]
     async fn responds_to_http_requests() {
         let path = "127.0.0.1:8085";
-        let socket_path = "socket/path";
-        let result = tokio::select! {
-            r = async {
-                tokio::time::sleep(Duration::from_millis(100)).await;
-                reqwest::get(&format!("http://{}/health", path)).await.unwrap().status()
-            } => Some(r),
-            _ = async {
-                serve(path, socket_path).await.unwrap()
-            } => None
+        let result = against(config(path), async {
+            reqwest::get(&format!("http://{}/health", path))
+                .await
+                .unwrap()
+                .status()
+        })
+        .await;
+        assert_eq!(result, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn responds_to_head_requests_without_a_body() {
+        let path = "127.0.0.1:8088";
+        let response = against(config(path), async {
+            reqwest::Client::new()
+                .head(format!("http://{}/livez", path))
+                .send()
+                .await
+                .unwrap()
+        })
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.bytes().await.unwrap().len(), 0);
+    }
+
+    #[tokio::test]
+    async fn responds_to_https_requests_with_or_without_a_client_certificate() {
+        let path = "127.0.0.1:8086";
+        let config = HealthCheckConfiguration {
+            cert: Some(CERT.to_string()),
+            key: Some(KEY.to_string()),
+            client_ca: Some(CA.to_string()),
+            ..config(path)
+        };
+        let url = format!("https://{}/health", path);
+        let (with_certificate, without_certificate) = against(config, async {
+            (
+                https_client(true).get(&url).send().await,
+                https_client(false).get(&url).send().await,
+            )
+        })
+        .await;
+        assert_eq!(with_certificate.unwrap().status(), StatusCode::OK);
+        assert_eq!(without_certificate.unwrap().status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn limits_admin_endpoints_to_clients_with_a_certificate() {
+        let path = "127.0.0.1:8089";
+        let config = HealthCheckConfiguration {
+            cert: Some(CERT.to_string()),
+            key: Some(KEY.to_string()),
+            client_ca: Some(CA.to_string()),
+            ..config(path)
+        };
+        let url = format!("https://{}{}", path, SELFTEST_PATH);
+        let (with_certificate, without_certificate) = against(config, async {
+            (
+                https_client(true).get(&url).send().await.unwrap(),
+                https_client(false).get(&url).send().await.unwrap(),
+            )
+        })
+        .await;
+        assert_ne!(with_certificate.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(without_certificate.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn does_not_trust_clients_on_localhost() {
+        let path = "127.0.0.1:8090";
+        let status = against(config(path), async {
+            reqwest::get(&format!("http://{}{}", path, SELFTEST_PATH))
+                .await
+                .unwrap()
+                .status()
+        })
+        .await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn responds_to_requests_on_a_unix_socket() {
+        let path = std::env::temp_dir().join(format!(
+            "vault-kms-provider-checks-{}.sock",
+            std::process::id()
+        ));
+        let endpoint = format!("unix://{}", path.display());
+        let response = against(config(&endpoint), async {
+            let mut stream = tokio::net::UnixStream::connect(&path).await.unwrap();
+            stream
+                .write_all(b"GET /health HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+                .await
+                .unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).await.unwrap();
+            response
+        })
+        .await;
+        let _ = std::fs::remove_file(&path);
+        assert!(response.starts_with("HTTP/1.1 200 OK"), "{}", response);
+    }
+
+    #[tokio::test]
+    async fn requires_both_a_certificate_and_a_key() {
+        let config = HealthCheckConfiguration {
+            cert: Some(CERT.to_string()),
+            ..config("127.0.0.1:8087")
         };
-        assert_eq!(result, Some(StatusCode::OK));
+        let result = serve(
+            &config,
+            "socket/path",
+            Arc::new(RefreshStatus::default()),
+            Arc::new(Initialization::default()),
+            Arc::new(Metrics::default()),
+            Arc::new(Heartbeats::new(config.liveness_threshold)),
+        )
+        .await;
+        assert!(result.is_err());
     }
 }
 
 #[cfg(test)]
-mod checks {
-    use super::checks;
-    use http;
-    use http::StatusCode;
+mod route {
+    use super::*;
+    use http_body_util::BodyExt;
+    use pretty_assertions::assert_eq;
+    use serde_json::Value;
+    use std::time::Duration;
+
+    fn state(socket_path: &str, metrics: Arc<Metrics>) -> State {
+        State {
+            socket_path: socket_path.to_string(),
+            refresh_status: Arc::new(RefreshStatus::default()),
+            initialization: {
+                let initialization = Initialization::default();
+                initialization.succeeded();
+                Arc::new(initialization)
+            },
+            metrics,
+            heartbeats: Arc::new(Heartbeats::new(Duration::from_secs(60))),
+            admin_token: None,
+        }
+    }
+
+    async fn request(method: Method, uri: &str, state: &State) -> Response<Full<Bytes>> {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .body(Full::new(Bytes::new()))
+            .unwrap();
+        let peer = Peer {
+            address: None,
+            client_certificate: false,
+        };
+        route(request, &peer, state).await.unwrap()
+    }
+
+    async fn json(response: Response<Full<Bytes>>) -> Value {
+        serde_json::from_slice(&response.into_body().collect().await.unwrap().to_bytes()).unwrap()
+    }
 
     #[tokio::test]
     async fn ready_returns_ok_if_socket_exists() {
-        let resp = checks(
-            "/ready".to_string(),
-            "test_files/vault-kms-provider.yaml".to_string(),
-        )
-        .await
-        .unwrap();
-        assert_eq!(resp.status(), StatusCode::OK);
+        let state = state(
+            "test_files/vault-kms-provider.yaml",
+            Arc::new(Metrics::default()),
+        );
+        for path in READINESS_PATHS {
+            let resp = request(Method::GET, path, &state).await;
+            assert_eq!(resp.status(), StatusCode::OK, "{}", path);
+        }
     }
 
     #[tokio::test]
     async fn ready_returns_error_status_if_socket_does_not_exist() {
-        let resp = checks(
-            "/ready".to_string(),
-            "test_files/non-existent-file".to_string(),
-        )
-        .await
-        .unwrap();
-        assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        let state = state("test_files/non-existent-file", Arc::new(Metrics::default()));
+        let resp = request(Method::GET, "/readyz", &state).await;
+        assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let body = json(resp).await;
+        assert_eq!(body["status"], "failed");
+        assert_eq!(body["checks"][0]["name"], "socket");
+        assert_eq!(body["checks"].as_array().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn ready_lists_every_check_when_verbose() {
+        let state = state(
+            "test_files/vault-kms-provider.yaml",
+            Arc::new(Metrics::default()),
+        );
+        let body = json(request(Method::GET, "/readyz?verbose", &state).await).await;
+        assert_eq!(body["checks"][0]["name"], "socket");
+        assert_eq!(body["checks"][1]["name"], "initialization");
+        assert_eq!(body["checks"][2]["name"], "token-refresh");
+        let body = json(request(Method::GET, "/readyz?verbose=false", &state).await).await;
+        assert_eq!(body["checks"].as_array().unwrap().len(), 0);
     }
 
     #[tokio::test]
     async fn health_returns_ok_if_app_is_working() {
-        let resp = checks(
-            "/health".to_string(),
-            "test_files/vault-kms-provider.yaml".to_string(),
-        )
-        .await
-        .unwrap();
-        assert_eq!(resp.status(), StatusCode::OK);
+        let state = state("test_files/non-existent-file", Arc::new(Metrics::default()));
+        for path in LIVENESS_PATHS {
+            let resp = request(Method::GET, path, &state).await;
+            assert_eq!(resp.status(), StatusCode::OK, "{}", path);
+        }
     }
 
     #[tokio::test]
     async fn returns_not_found_if_no_matching_path_exists() {
-        let resp = checks(
-            "/invalid".to_string(),
-            "test_files/vault-kms-provider.yaml".to_string(),
-        )
-        .await
-        .unwrap();
-        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+        let state = state(
+            "test_files/vault-kms-provider.yaml",
+            Arc::new(Metrics::default()),
+        );
+        for path in [
+            "/invalid",
+            "/unhealthy",
+            "/already",
+            "/readyz/extra",
+            "/metrics-old",
+        ] {
+            let resp = request(Method::GET, path, &state).await;
+            assert_eq!(resp.status(), StatusCode::NOT_FOUND, "{}", path);
+        }
+    }
+
+    #[tokio::test]
+    async fn returns_method_not_allowed_for_other_methods() {
+        let state = state(
+            "test_files/vault-kms-provider.yaml",
+            Arc::new(Metrics::default()),
+        );
+        let resp = request(Method::POST, "/healthz", &state).await;
+        assert_eq!(resp.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(resp.headers()[header::ALLOW], "GET, HEAD");
+        let resp = request(Method::HEAD, "/healthz", &state).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn selftest_is_limited_to_authorized_peers() {
+        let state = state(
+            "test_files/vault-kms-provider.yaml",
+            Arc::new(Metrics::default()),
+        );
+        let untrusted = Request::builder()
+            .uri(SELFTEST_PATH)
+            .body(Full::new(Bytes::new()))
+            .unwrap();
+        let peer = Peer {
+            address: Some("10.0.0.5:50000".parse().unwrap()),
+            client_certificate: false,
+        };
+        let resp = route(untrusted, &peer, &state).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        let resp = request(Method::POST, SELFTEST_PATH, &state).await;
+        assert_eq!(resp.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(resp.headers()[header::ALLOW], "GET");
+    }
+
+    #[tokio::test]
+    async fn metrics_returns_request_metrics() {
+        let metrics = Arc::new(Metrics::default());
+        metrics.rejected("Encrypt");
+        let state = state("test_files/vault-kms-provider.yaml", metrics);
+        let resp = request(Method::GET, "/metrics", &state).await;
+        assert_eq!(resp.status(), StatusCode::OK);
     }
 }
@@ -1,39 +1,128 @@
-use bytes::Bytes;
-use http::{Response, StatusCode};
-use http_body_util::Full;
-use std::convert::Infallible;
+use crate::checks::report::Check;
+use crate::utilities::watcher::RefreshStatus;
+use crate::vault::Initialization;
 use std::path::Path;
 
-pub async fn readiness_check(socket_path: &str) -> Result<Response<Full<Bytes>>, Infallible> {
-    let status = if Path::new(&socket_path).exists() {
-        StatusCode::OK
+/// Checks that the provider is able to serve KMS requests.
+pub fn readiness_checks(
+    socket_path: &str,
+    refresh_status: &RefreshStatus,
+    initialization: &Initialization,
+) -> Vec<Check> {
+    let socket = if Path::new(&socket_path).exists() {
+        Check::ok("socket")
     } else {
-        StatusCode::INTERNAL_SERVER_ERROR
+        Check::failed("socket", format!("{} does not exist", socket_path))
     };
-    Ok(Response::builder()
-        .status(status)
-        .body(Full::new(Bytes::from("Not ready")))
-        .expect("Unable to build request"))
+    let token_refresh = if refresh_status.is_failing() {
+        Check::failed(
+            "token-refresh",
+            format!(
+                "Token refresh has failed {} time(s): {}",
+                refresh_status.consecutive_failures(),
+                refresh_status.last_error().unwrap_or_default()
+            ),
+        )
+    } else {
+        Check::ok("token-refresh")
+    };
+    let initialized = match initialization.failure() {
+        Some(failure) => Check::failed("initialization", failure),
+        None => Check::ok("initialization"),
+    };
+    vec![socket, initialized, token_refresh]
 }
 
 #[cfg(test)]
 mod readiness {
-    use super::readiness_check;
-    use http::StatusCode;
-
-    #[tokio::test]
-    async fn returns_ok_if_socket_exists() {
-        let resp = readiness_check("test_files/vault-kms-provider.yaml")
-            .await
-            .unwrap();
-        assert_eq!(resp.status(), StatusCode::OK);
+    use super::readiness_checks;
+    use crate::checks::report::Check;
+    use crate::utilities::watcher::RefreshStatus;
+    use crate::vault::Initialization;
+    use pretty_assertions::assert_eq;
+
+    fn initialized() -> Initialization {
+        let initialization = Initialization::default();
+        initialization.succeeded();
+        initialization
+    }
+
+    #[test]
+    fn passes_if_socket_exists() {
+        let checks = readiness_checks(
+            "test_files/vault-kms-provider.yaml",
+            &RefreshStatus::default(),
+            &initialized(),
+        );
+        assert_eq!(
+            checks,
+            vec![
+                Check::ok("socket"),
+                Check::ok("initialization"),
+                Check::ok("token-refresh")
+            ]
+        );
+    }
+
+    #[test]
+    fn fails_if_socket_does_not_exist() {
+        let checks = readiness_checks(
+            "test_files/non-existent-file",
+            &RefreshStatus::default(),
+            &initialized(),
+        );
+        assert_eq!(
+            checks[0],
+            Check::failed("socket", "test_files/non-existent-file does not exist")
+        );
+    }
+
+    #[test]
+    fn fails_if_token_refresh_is_failing() {
+        let status = RefreshStatus::default();
+        status.failed("permission denied");
+        let checks = readiness_checks(
+            "test_files/vault-kms-provider.yaml",
+            &status,
+            &initialized(),
+        );
+        assert_eq!(
+            checks[2],
+            Check::failed(
+                "token-refresh",
+                "Token refresh has failed 1 time(s): permission denied"
+            )
+        );
+    }
+
+    #[test]
+    fn passes_once_token_refresh_recovers() {
+        let status = RefreshStatus::default();
+        status.failed("permission denied");
+        status.succeeded();
+        let checks = readiness_checks(
+            "test_files/vault-kms-provider.yaml",
+            &status,
+            &initialized(),
+        );
+        assert!(checks.iter().all(Check::is_ok));
     }
 
-    #[tokio::test]
-    async fn returns_error_status_if_socket_does_not_exist() {
-        let resp = readiness_check("test_files/non-existent-file")
-            .await
-            .unwrap();
-        assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    #[test]
+    fn fails_until_vault_encryption_is_initialized() {
+        let initialization = Initialization::default();
+        initialization.failed("Failed to authenticate: connection refused");
+        let checks = readiness_checks(
+            "test_files/vault-kms-provider.yaml",
+            &RefreshStatus::default(),
+            &initialization,
+        );
+        assert_eq!(
+            checks[1],
+            Check::failed(
+                "initialization",
+                "Vault encryption is not initialized: Failed to authenticate: connection refused"
+            )
+        );
     }
 }
@@ -0,0 +1,124 @@
+use bytes::Bytes;
+use http::{header, Response, StatusCode};
+use http_body_util::Full;
+use serde::Serialize;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Ok,
+    Failed,
+}
+
+/// Result of an individual check.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Check {
+    pub name: &'static str,
+    pub status: CheckStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+impl Check {
+    pub fn ok(name: &'static str) -> Self {
+        Self {
+            name,
+            status: CheckStatus::Ok,
+            reason: None,
+        }
+    }
+
+    pub fn failed(name: &'static str, reason: impl Into<String>) -> Self {
+        Self {
+            name,
+            status: CheckStatus::Failed,
+            reason: Some(reason.into()),
+        }
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.status == CheckStatus::Ok
+    }
+}
+
+/// Results of the checks behind an endpoint, which passes once all of them pass.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Report {
+    pub status: CheckStatus,
+    pub checks: Vec<Check>,
+}
+
+impl Report {
+    pub fn new(checks: Vec<Check>) -> Self {
+        Self {
+            status: if checks.iter().all(Check::is_ok) {
+                CheckStatus::Ok
+            } else {
+                CheckStatus::Failed
+            },
+            checks,
+        }
+    }
+
+    /// Responds with the report as JSON, listing every check when `verbose` and only the failed ones otherwise.
+    pub fn response(mut self, verbose: bool) -> Response<Full<Bytes>> {
+        if !verbose {
+            self.checks.retain(|check| !check.is_ok());
+        }
+        Response::builder()
+            .status(match self.status {
+                CheckStatus::Ok => StatusCode::OK,
+                CheckStatus::Failed => StatusCode::SERVICE_UNAVAILABLE,
+            })
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Full::new(Bytes::from(
+                serde_json::to_string(&self).expect("Unable to serialize check report"),
+            )))
+            .expect("Unable to build response")
+    }
+}
+
+#[cfg(test)]
+mod report {
+    use super::*;
+    use http_body_util::BodyExt;
+    use pretty_assertions::assert_eq;
+    use serde_json::{json, Value};
+
+    async fn body(response: Response<Full<Bytes>>) -> Value {
+        serde_json::from_slice(&response.into_body().collect().await.unwrap().to_bytes()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn lists_only_failed_checks_unless_verbose() {
+        let checks = || {
+            vec![
+                Check::ok("socket"),
+                Check::failed("token-refresh", "permission denied"),
+            ]
+        };
+        let response = Report::new(checks()).response(false);
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(
+            body(response).await,
+            json!({
+                "status": "failed",
+                "checks": [{ "name": "token-refresh", "status": "failed", "reason": "permission denied" }]
+            })
+        );
+        assert_eq!(
+            body(Report::new(checks()).response(true)).await["checks"][0],
+            json!({ "name": "socket", "status": "ok" })
+        );
+    }
+
+    #[tokio::test]
+    async fn passes_once_every_check_passes() {
+        let response = Report::new(vec![Check::ok("ping")]).response(false);
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            body(response).await,
+            json!({ "status": "ok", "checks": [] })
+        );
+    }
+}
@@ -0,0 +1,318 @@
+use crate::checks::report::CheckStatus;
+use crate::kms::key_management_service_client::KeyManagementServiceClient;
+use crate::kms::{DecryptRequest, EncryptRequest, StatusRequest};
+use crate::utilities::socket::Socket;
+use bytes::Bytes;
+use http::{header, Response, StatusCode};
+use http_body_util::Full;
+use serde::Serialize;
+use std::future::Future;
+use std::time::{Duration, Instant};
+use tonic::transport::Channel;
+use tonic::Request;
+use tracing::{info, warn};
+
+const PAYLOAD_SIZE: usize = 32;
+const STEP_TIMEOUT: Duration = Duration::from_secs(10);
+const OKAY_RESPONSE: &str = "ok";
+
+/// Result and latency of a step of the self-test.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Step {
+    pub name: &'static str,
+    pub status: CheckStatus,
+    pub latency_ms: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// Results of a round trip through the provider's own gRPC socket, which passes once every step passes.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct SelfTest {
+    pub status: CheckStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key_id: Option<String>,
+    pub steps: Vec<Step>,
+}
+
+impl SelfTest {
+    pub fn is_ok(&self) -> bool {
+        self.status == CheckStatus::Ok
+    }
+
+    /// Responds with `200` when the self-test passed and `503` otherwise, listing every step that ran.
+    pub fn response(&self) -> Response<Full<Bytes>> {
+        Response::builder()
+            .status(if self.is_ok() {
+                StatusCode::OK
+            } else {
+                StatusCode::SERVICE_UNAVAILABLE
+            })
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Full::new(Bytes::from(
+                serde_json::to_vec(self).expect("Unable to serialize self-test"),
+            )))
+            .expect("Unable to build response")
+    }
+}
+
+/// Runs the self-test steps in order, stopping at the first failure.
+struct Steps {
+    steps: Vec<Step>,
+}
+
+impl Steps {
+    async fn run<T, F>(&mut self, name: &'static str, step: F) -> Option<T>
+    where
+        F: Future<Output = Result<T, String>>,
+    {
+        let start = Instant::now();
+        let result = tokio::time::timeout(STEP_TIMEOUT, step)
+            .await
+            .unwrap_or_else(|_| Err(format!("Timed out after {:?}", STEP_TIMEOUT)));
+        let latency_ms = start.elapsed().as_secs_f64() * 1000.0;
+        let (status, reason, value) = match result {
+            Ok(value) => (CheckStatus::Ok, None, Some(value)),
+            Err(reason) => (CheckStatus::Failed, Some(reason), None),
+        };
+        self.steps.push(Step {
+            name,
+            status,
+            latency_ms,
+            reason,
+        });
+        value
+    }
+}
+
+fn request<T>(message: T) -> Request<T> {
+    let mut request = Request::new(message);
+    request.set_timeout(STEP_TIMEOUT);
+    request
+}
+
+async fn round_trip(steps: &mut Steps, socket: &Socket, socket_path: &str) -> Option<String> {
+    let mut client = steps
+        .run("connect", async {
+            socket
+                .connect(socket_path)
+                .await
+                .map(KeyManagementServiceClient::<Channel>::new)
+                .map_err(|error| error.to_string())
+        })
+        .await?;
+    let uid = format!("selftest-{:016x}", fastrand::u64(..));
+    let plaintext: Vec<u8> = std::iter::repeat_with(|| fastrand::u8(..))
+        .take(PAYLOAD_SIZE)
+        .collect();
+    let encrypted = steps
+        .run("encrypt", async {
+            client
+                .encrypt(request(EncryptRequest {
+                    plaintext: plaintext.clone(),
+                    uid: uid.clone(),
+                }))
+                .await
+                .map(|response| response.into_inner())
+                .map_err(|status| status.message().to_string())
+        })
+        .await?;
+    steps
+        .run("decrypt", async {
+            let decrypted = client
+                .decrypt(request(DecryptRequest {
+                    ciphertext: encrypted.ciphertext.clone(),
+                    uid: uid.clone(),
+                    key_id: encrypted.key_id.clone(),
+                    annotations: encrypted.annotations.clone(),
+                }))
+                .await
+                .map_err(|status| status.message().to_string())?
+                .into_inner();
+            if decrypted.plaintext == plaintext {
+                Ok(())
+            } else {
+                Err("Decrypted plaintext does not match the encrypted payload".to_string())
+            }
+        })
+        .await?;
+    steps
+        .run("status", async {
+            let status = client
+                .status(request(StatusRequest {}))
+                .await
+                .map_err(|status| status.message().to_string())?
+                .into_inner();
+            if status.healthz == OKAY_RESPONSE {
+                Ok(status.key_id)
+            } else {
+                Err(format!("Unhealthy: {}", status.healthz))
+            }
+        })
+        .await
+        .or(Some(encrypted.key_id))
+}
+
+/// Encrypts a random payload through the gRPC socket the API server uses, decrypts and compares it, then calls
+/// `Status`, the same as the API server would.
+pub async fn run(socket: &Socket, socket_path: &str) -> SelfTest {
+    let mut steps = Steps { steps: vec![] };
+    let key_id = round_trip(&mut steps, socket, socket_path)
+        .await
+        .filter(|key_id| !key_id.is_empty());
+    let status = if steps
+        .steps
+        .iter()
+        .all(|step| step.status == CheckStatus::Ok)
+    {
+        info!("Self-test passed using key: {:?}", key_id);
+        CheckStatus::Ok
+    } else {
+        warn!("Self-test failed: {:?}", steps.steps.last());
+        CheckStatus::Failed
+    };
+    SelfTest {
+        status,
+        key_id,
+        steps: steps.steps,
+    }
+}
+
+#[cfg(test)]
+mod selftest {
+    use super::*;
+    use crate::kms::key_management_service_server::{
+        KeyManagementService, KeyManagementServiceServer,
+    };
+    use crate::kms::{DecryptResponse, EncryptResponse, StatusResponse};
+    use pretty_assertions::assert_eq;
+    use std::collections::HashMap;
+    use std::ffi::OsString;
+    use std::sync::OnceLock;
+    use tonic::transport::Server;
+    use tonic::{Response, Status};
+
+    static PASSING_SOCKET_PATH: OnceLock<OsString> = OnceLock::new();
+    static CORRUPTING_SOCKET_PATH: OnceLock<OsString> = OnceLock::new();
+    static MISSING_SOCKET_PATH: OnceLock<OsString> = OnceLock::new();
+
+    /// Reverses the plaintext as its "encryption", decrypting to the wrong plaintext when corrupting.
+    struct Mock {
+        corrupting: bool,
+    }
+
+    #[tonic::async_trait]
+    impl KeyManagementService for Mock {
+        async fn status(
+            &self,
+            _: tonic::Request<StatusRequest>,
+        ) -> Result<Response<StatusResponse>, Status> {
+            Ok(Response::new(StatusResponse {
+                version: "v2".to_string(),
+                healthz: OKAY_RESPONSE.to_string(),
+                key_id: "1".to_string(),
+            }))
+        }
+
+        async fn decrypt(
+            &self,
+            request: tonic::Request<DecryptRequest>,
+        ) -> Result<Response<DecryptResponse>, Status> {
+            let mut plaintext = request.into_inner().ciphertext;
+            if !self.corrupting {
+                plaintext.reverse();
+            }
+            Ok(Response::new(DecryptResponse { plaintext }))
+        }
+
+        async fn encrypt(
+            &self,
+            request: tonic::Request<EncryptRequest>,
+        ) -> Result<Response<EncryptResponse>, Status> {
+            let mut ciphertext = request.into_inner().plaintext;
+            ciphertext.reverse();
+            Ok(Response::new(EncryptResponse {
+                ciphertext,
+                key_id: "1".to_string(),
+                annotations: HashMap::new(),
+            }))
+        }
+    }
+
+    async fn against(path: &str, lock: &'static OnceLock<OsString>, mock: Mock) -> SelfTest {
+        let socket = Socket::with_path(lock);
+        let stream = socket.listen(path).unwrap();
+        tokio::select! {
+            result = run(&socket, path) => result,
+            result = Server::builder()
+                .add_service(KeyManagementServiceServer::new(mock))
+                .serve_with_incoming(stream) => panic!("Server stopped: {:?}", result),
+        }
+    }
+
+    fn names(selftest: &SelfTest) -> Vec<(&'static str, CheckStatus)> {
+        selftest
+            .steps
+            .iter()
+            .map(|step| (step.name, step.status))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn passes_a_round_trip_through_the_socket() {
+        let selftest = against(
+            "@test_files/selftest-passing.sock",
+            &PASSING_SOCKET_PATH,
+            Mock { corrupting: false },
+        )
+        .await;
+        assert!(selftest.is_ok());
+        assert_eq!(selftest.key_id, Some("1".to_string()));
+        assert_eq!(
+            names(&selftest),
+            vec![
+                ("connect", CheckStatus::Ok),
+                ("encrypt", CheckStatus::Ok),
+                ("decrypt", CheckStatus::Ok),
+                ("status", CheckStatus::Ok),
+            ]
+        );
+        assert_eq!(selftest.response().status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn fails_if_the_decrypted_payload_differs() {
+        let selftest = against(
+            "@test_files/selftest-corrupting.sock",
+            &CORRUPTING_SOCKET_PATH,
+            Mock { corrupting: true },
+        )
+        .await;
+        assert!(!selftest.is_ok());
+        assert_eq!(
+            names(&selftest),
+            vec![
+                ("connect", CheckStatus::Ok),
+                ("encrypt", CheckStatus::Ok),
+                ("decrypt", CheckStatus::Failed),
+            ]
+        );
+        assert_eq!(
+            selftest.steps[2].reason,
+            Some("Decrypted plaintext does not match the encrypted payload".to_string())
+        );
+        assert_eq!(
+            selftest.response().status(),
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+    }
+
+    #[tokio::test]
+    async fn fails_if_the_socket_is_unavailable() {
+        let socket = Socket::with_path(&MISSING_SOCKET_PATH);
+        let selftest = run(&socket, "@test_files/selftest-missing.sock").await;
+        assert!(!selftest.is_ok());
+        assert_eq!(selftest.key_id, None);
+        assert_eq!(names(&selftest), vec![("connect", CheckStatus::Failed)]);
+    }
+}
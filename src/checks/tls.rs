@@ -0,0 +1,196 @@
+use rustls::crypto::{aws_lc_rs, CryptoProvider};
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::server::{ClientHello, ResolvesServerCert, WebPkiClientVerifier};
+use rustls::sign::CertifiedKey;
+use rustls::{RootCertStore, ServerConfig};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::SystemTime;
+use tokio_rustls::TlsAcceptor;
+use tracing::{info, warn};
+
+fn invalid(path: &Path, error: impl std::fmt::Display) -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::InvalidData,
+        format!("Unable to load \"{}\": {}", path.display(), error),
+    )
+}
+
+fn certificates(path: &Path) -> std::io::Result<Vec<CertificateDer<'static>>> {
+    CertificateDer::pem_file_iter(path)
+        .and_then(|certificates| certificates.collect::<Result<Vec<_>, _>>())
+        .map_err(|error| invalid(path, error))
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+/// Serves the certificate and key read from files, reloading them on the next handshake once either
+/// file has changed. The previous certificate is kept if the new files cannot be loaded, ex: while
+/// only one of them has been replaced.
+#[derive(Debug)]
+struct ReloadingCertificate {
+    cert: PathBuf,
+    key: PathBuf,
+    provider: Arc<CryptoProvider>,
+    current: RwLock<(Option<SystemTime>, Option<SystemTime>, Arc<CertifiedKey>)>,
+}
+
+impl ReloadingCertificate {
+    fn load(
+        cert: &Path,
+        key: &Path,
+        provider: &CryptoProvider,
+    ) -> std::io::Result<Arc<CertifiedKey>> {
+        let certificates = certificates(cert)?;
+        let key = PrivateKeyDer::from_pem_file(key).map_err(|error| invalid(key, error))?;
+        CertifiedKey::from_der(certificates, key, provider)
+            .map(Arc::new)
+            .map_err(|error| invalid(cert, error))
+    }
+
+    fn new(cert: PathBuf, key: PathBuf, provider: Arc<CryptoProvider>) -> std::io::Result<Self> {
+        let modified = (modified(&cert), modified(&key));
+        let certified = Self::load(&cert, &key, &provider)?;
+        Ok(Self {
+            current: RwLock::new((modified.0, modified.1, certified)),
+            cert,
+            key,
+            provider,
+        })
+    }
+
+    fn reload_if_changed(&self) {
+        let modified = (modified(&self.cert), modified(&self.key));
+        {
+            let current = self.current.read().unwrap();
+            if (current.0, current.1) == modified {
+                return;
+            }
+        }
+        let mut current = self.current.write().unwrap();
+        (current.0, current.1) = modified;
+        match Self::load(&self.cert, &self.key, &self.provider) {
+            Ok(certified) => {
+                info!("Reloaded health check certificate: {}", self.cert.display());
+                current.2 = certified;
+            }
+            Err(error) => warn!(
+                "Unable to reload the health check certificate, serving the previous one: {}",
+                error
+            ),
+        }
+    }
+}
+
+impl ResolvesServerCert for ReloadingCertificate {
+    fn resolve(&self, _client_hello: ClientHello<'_>) -> Option<Arc<CertifiedKey>> {
+        self.reload_if_changed();
+        Some(self.current.read().unwrap().2.clone())
+    }
+}
+
+/// Accepts TLS connections with the certificate and key files, verifying client certificates against
+/// `client_ca` when set. Clients without a certificate are still accepted for the probes, the admin
+/// endpoints check whether one was presented.
+pub fn acceptor(cert: &str, key: &str, client_ca: Option<&str>) -> std::io::Result<TlsAcceptor> {
+    let provider = Arc::new(aws_lc_rs::default_provider());
+    let builder = ServerConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()
+        .map_err(std::io::Error::other)?;
+    let builder = match client_ca {
+        Some(client_ca) => {
+            let client_ca = PathBuf::from(client_ca);
+            let mut roots = RootCertStore::empty();
+            for certificate in certificates(&client_ca)? {
+                roots
+                    .add(certificate)
+                    .map_err(|error| invalid(&client_ca, error))?;
+            }
+            let verifier =
+                WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider.clone())
+                    .allow_unauthenticated()
+                    .build()
+                    .map_err(|error| invalid(&client_ca, error))?;
+            builder.with_client_cert_verifier(verifier)
+        }
+        None => builder.with_no_client_auth(),
+    };
+    let mut config = builder.with_cert_resolver(Arc::new(ReloadingCertificate::new(
+        PathBuf::from(cert),
+        PathBuf::from(key),
+        provider,
+    )?));
+    config.alpn_protocols = vec![b"http/1.1".to_vec()];
+    Ok(TlsAcceptor::from(Arc::new(config)))
+}
+
+#[cfg(test)]
+mod reloading_certificate {
+    use super::*;
+    use std::time::Duration;
+
+    const CERT: &str = "test_files/certs/tls.crt";
+    const KEY: &str = "test_files/certs/tls.key";
+
+    fn copy(name: &str) -> (PathBuf, PathBuf) {
+        let directory = std::env::temp_dir().join(format!(
+            "vault-kms-provider-tls-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&directory);
+        std::fs::create_dir_all(&directory).unwrap();
+        let (cert, key) = (directory.join("tls.crt"), directory.join("tls.key"));
+        std::fs::copy(CERT, &cert).unwrap();
+        std::fs::copy(KEY, &key).unwrap();
+        (cert, key)
+    }
+
+    fn certificate(resolver: &ReloadingCertificate) -> Vec<u8> {
+        resolver.reload_if_changed();
+        resolver.current.read().unwrap().2.cert[0].to_vec()
+    }
+
+    #[test]
+    fn fails_without_a_valid_certificate() {
+        let provider = Arc::new(aws_lc_rs::default_provider());
+        assert!(ReloadingCertificate::new(
+            PathBuf::from("test_files/certs/missing.crt"),
+            PathBuf::from(KEY),
+            provider.clone()
+        )
+        .is_err());
+        assert!(
+            ReloadingCertificate::new(PathBuf::from(CERT), PathBuf::from(CERT), provider).is_err()
+        );
+    }
+
+    #[test]
+    fn reloads_the_certificate_once_it_changes() {
+        let (cert, key) = copy("reload");
+        let resolver = ReloadingCertificate::new(
+            cert.clone(),
+            key.clone(),
+            Arc::new(aws_lc_rs::default_provider()),
+        )
+        .unwrap();
+        let original = certificate(&resolver);
+
+        std::fs::write(&cert, "not a certificate").unwrap();
+        let file = std::fs::File::options().write(true).open(&cert).unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(1))
+            .unwrap();
+        assert_eq!(certificate(&resolver), original);
+
+        std::fs::copy("test_files/certs/reloaded.crt", &cert).unwrap();
+        std::fs::copy("test_files/certs/reloaded.key", &key).unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(2))
+            .unwrap();
+        assert_ne!(certificate(&resolver), original);
+    }
+}
@@ -0,0 +1,61 @@
+use crate::configuration::authentication::Credentials;
+use crate::utilities::environment::Environment;
+use crate::utilities::source::Source;
+
+/// Settings for talking to Vault through a local Vault Agent (or Vault Proxy) listener.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AgentConfiguration {
+    pub address: String,
+    pub sink: Option<Source>,
+}
+
+impl AgentConfiguration {
+    pub fn new(address: String, sink: Option<Source>) -> Self {
+        Self { address, sink }
+    }
+
+    pub fn from_env() -> Option<Self> {
+        Environment::VaultAgentAddress.get().map(|address| {
+            Self::new(
+                address,
+                Environment::VaultAgentSinkPath.get().map(Source::FilePath),
+            )
+        })
+    }
+
+    /// The token written to the auto-auth sink is used when a sink is configured, otherwise
+    /// requests are sent without a token for the agent's cache to add its auto-auth token.
+    pub fn credentials(&self) -> Vec<Credentials> {
+        vec![self
+            .sink
+            .clone()
+            .map(Credentials::Token)
+            .unwrap_or(Credentials::Agent)]
+    }
+}
+
+#[cfg(test)]
+mod agent_configuration {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn uses_the_sink_file_as_a_token() {
+        let agent = AgentConfiguration::new(
+            "http://127.0.0.1:8100".to_string(),
+            Some(Source::FilePath("/vault/sink/token".to_string())),
+        );
+        assert_eq!(
+            agent.credentials(),
+            vec![Credentials::Token(Source::FilePath(
+                "/vault/sink/token".to_string()
+            ))]
+        );
+    }
+
+    #[test]
+    fn relies_on_the_auto_auth_token_without_a_sink() {
+        let agent = AgentConfiguration::new("http://127.0.0.1:8100".to_string(), None);
+        assert_eq!(agent.credentials(), vec![Credentials::Agent]);
+    }
+}
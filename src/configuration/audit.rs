@@ -0,0 +1,85 @@
+use crate::utilities::environment::Environment;
+use std::path::PathBuf;
+
+const STDOUT_SINK: &str = "stdout";
+const DEFAULT_MAX_FILE_SIZE: u64 = 100 * 1024 * 1024;
+const DEFAULT_MAX_FILES: usize = 5;
+
+/// Where audit records are written.
+#[derive(Clone, Debug, PartialEq)]
+pub enum AuditSink {
+    Stdout,
+    File(PathBuf),
+}
+
+impl From<&str> for AuditSink {
+    fn from(value: &str) -> Self {
+        if value.eq_ignore_ascii_case(STDOUT_SINK) {
+            Self::Stdout
+        } else {
+            Self::File(PathBuf::from(value))
+        }
+    }
+}
+
+/// Audit log of KMS requests, kept separate from the diagnostic logs. Disabled unless a sink is set.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AuditConfiguration {
+    pub sink: Option<AuditSink>,
+    /// Size in bytes at which the audit file is rotated.
+    pub max_file_size: u64,
+    /// Rotated audit files kept besides the current one.
+    pub max_files: usize,
+}
+
+impl Default for AuditConfiguration {
+    fn default() -> Self {
+        Self::new(
+            Environment::AuditLog.get().as_deref().map(AuditSink::from),
+            Environment::AuditLogMaxSize.parsed(),
+            Environment::AuditLogMaxFiles.parsed(),
+        )
+    }
+}
+
+impl AuditConfiguration {
+    pub fn new(
+        sink: Option<AuditSink>,
+        max_file_size: Option<u64>,
+        max_files: Option<usize>,
+    ) -> Self {
+        Self {
+            sink,
+            max_file_size: max_file_size.unwrap_or(DEFAULT_MAX_FILE_SIZE),
+            max_files: max_files.unwrap_or(DEFAULT_MAX_FILES),
+        }
+    }
+}
+
+#[cfg(test)]
+mod audit_configuration {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn initializes_values_via_default_method() {
+        assert_eq!(
+            AuditConfiguration::default(),
+            AuditConfiguration {
+                sink: None,
+                max_file_size: DEFAULT_MAX_FILE_SIZE,
+                max_files: DEFAULT_MAX_FILES,
+            }
+        );
+    }
+
+    #[test]
+    fn parses_the_sink() {
+        assert_eq!(AuditSink::from("stdout"), AuditSink::Stdout);
+        assert_eq!(AuditSink::from("STDOUT"), AuditSink::Stdout);
+        assert_eq!(
+            AuditSink::from("/var/log/kms/audit.log"),
+            AuditSink::File(PathBuf::from("/var/log/kms/audit.log"))
+        );
+    }
+}
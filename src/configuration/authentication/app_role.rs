@@ -4,17 +4,79 @@ const DEFAULT_MOUNT_PATH: &str = "approle";
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct AppRole {
-    pub role_id: String,
+    pub role_id: Source,
     pub secret_id: Source,
-    pub mount_path: String,
+    pub mount_path: Source,
+    /// `secret_id` holds a single-use response-wrapping token that is unwrapped to obtain the secret_id.
+    pub wrapped: bool,
 }
 
 impl AppRole {
-    pub fn new(role_id: String, secret_id: Source, mount_path: Option<String>) -> Self {
+    pub fn new(role_id: Source, secret_id: Source, mount_path: Option<Source>) -> Self {
         Self {
             role_id,
             secret_id,
-            mount_path: mount_path.unwrap_or(DEFAULT_MOUNT_PATH.to_string()),
+            mount_path: mount_path.unwrap_or(Source::Value(DEFAULT_MOUNT_PATH.to_string())),
+            wrapped: false,
         }
     }
+
+    pub fn with_wrapped_secret_id(
+        role_id: Source,
+        wrapping_token: Source,
+        mount_path: Option<Source>,
+    ) -> Self {
+        Self {
+            wrapped: true,
+            ..Self::new(role_id, wrapping_token, mount_path)
+        }
+    }
+
+    pub fn paths(&self) -> Vec<String> {
+        [&self.role_id, &self.secret_id, &self.mount_path]
+            .iter()
+            .filter_map(|source| source.path())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod app_role_configuration {
+    use super::AppRole;
+    use crate::utilities::source::Source;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn returns_paths_of_all_file_backed_components() {
+        let app_role = AppRole::new(
+            Source::FilePath("/role_id".to_string()),
+            Source::FilePath("/secret_id".to_string()),
+            Some(Source::Value("approle".to_string())),
+        );
+        assert_eq!(
+            app_role.paths(),
+            vec!["/role_id".to_string(), "/secret_id".to_string()]
+        );
+    }
+
+    #[test]
+    fn secret_ids_are_not_wrapped_by_default() {
+        let app_role = AppRole::new(
+            Source::Value("role_id".to_string()),
+            Source::Value("secret_id".to_string()),
+            None,
+        );
+        assert!(!app_role.wrapped);
+    }
+
+    #[test]
+    fn watches_the_wrapping_token_of_a_wrapped_secret_id() {
+        let app_role = AppRole::with_wrapped_secret_id(
+            Source::Value("role_id".to_string()),
+            Source::FilePath("/wrapping_token".to_string()),
+            None,
+        );
+        assert!(app_role.wrapped);
+        assert_eq!(app_role.paths(), vec!["/wrapping_token".to_string()]);
+    }
 }
@@ -0,0 +1,92 @@
+use crate::utilities::source::Source;
+
+const DEFAULT_AWS_AUTH_MOUNT: &str = "aws";
+const DEFAULT_AWS_REGION: &str = "us-east-1";
+/// Global endpoint, which only accepts requests signed for `us-east-1`.
+const DEFAULT_STS_ENDPOINT: &str = "https://sts.amazonaws.com";
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Aws {
+    pub role: Option<Source>,
+    pub header_value: Option<Source>,
+    pub mount_path: Source,
+    pub region: String,
+    pub sts_endpoint: String,
+}
+
+impl Aws {
+    pub fn new(
+        role: Option<Source>,
+        header_value: Option<Source>,
+        mount_path: Option<Source>,
+        region: Option<String>,
+        sts_endpoint: Option<String>,
+    ) -> Self {
+        Self {
+            role,
+            header_value,
+            mount_path: mount_path.unwrap_or(Source::Value(DEFAULT_AWS_AUTH_MOUNT.to_string())),
+            sts_endpoint: sts_endpoint.unwrap_or_else(|| match &region {
+                Some(region) => format!("https://sts.{}.amazonaws.com", region),
+                None => DEFAULT_STS_ENDPOINT.to_string(),
+            }),
+            region: region.unwrap_or(DEFAULT_AWS_REGION.to_string()),
+        }
+    }
+
+    pub fn paths(&self) -> Vec<String> {
+        [
+            self.role.as_ref(),
+            self.header_value.as_ref(),
+            Some(&self.mount_path),
+        ]
+        .into_iter()
+        .flatten()
+        .filter_map(|source| source.path())
+        .collect()
+    }
+}
+
+#[cfg(test)]
+mod aws_configuration {
+    use super::{Aws, DEFAULT_AWS_AUTH_MOUNT, DEFAULT_AWS_REGION, DEFAULT_STS_ENDPOINT};
+    use crate::utilities::source::Source;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn initialization_defaults_to_the_global_sts_endpoint() {
+        let aws = Aws::new(None, None, None, None, None);
+        assert_eq!(
+            aws,
+            Aws {
+                role: None,
+                header_value: None,
+                mount_path: Source::Value(DEFAULT_AWS_AUTH_MOUNT.to_string()),
+                region: DEFAULT_AWS_REGION.to_string(),
+                sts_endpoint: DEFAULT_STS_ENDPOINT.to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn defaults_to_the_sts_endpoint_of_the_region() {
+        let aws = Aws::new(None, None, None, Some("eu-west-1".to_string()), None);
+        assert_eq!(aws.region, "eu-west-1");
+        assert_eq!(aws.sts_endpoint, "https://sts.eu-west-1.amazonaws.com");
+    }
+
+    #[test]
+    fn returns_paths_of_all_file_backed_components() {
+        let aws = Aws::new(
+            Some(Source::FilePath("/role".to_string())),
+            Some(Source::FilePath("/header".to_string())),
+            None,
+            None,
+            None,
+        );
+        assert_eq!(
+            aws.paths(),
+            vec!["/role".to_string(), "/header".to_string()]
+        );
+    }
+}
@@ -0,0 +1,74 @@
+use crate::utilities::source::Source;
+
+const DEFAULT_AZURE_AUTH_MOUNT: &str = "azure";
+const DEFAULT_AZURE_RESOURCE: &str = "https://management.azure.com/";
+const DEFAULT_AZURE_METADATA_ENDPOINT: &str = "http://169.254.169.254";
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Azure {
+    pub role: Source,
+    pub mount_path: Source,
+    pub resource: String,
+    pub metadata_endpoint: String,
+}
+
+impl Azure {
+    pub fn new(
+        role: Source,
+        mount_path: Option<Source>,
+        resource: Option<String>,
+        metadata_endpoint: Option<String>,
+    ) -> Self {
+        Self {
+            role,
+            mount_path: mount_path.unwrap_or(Source::Value(DEFAULT_AZURE_AUTH_MOUNT.to_string())),
+            resource: resource.unwrap_or(DEFAULT_AZURE_RESOURCE.to_string()),
+            metadata_endpoint: metadata_endpoint
+                .unwrap_or(DEFAULT_AZURE_METADATA_ENDPOINT.to_string()),
+        }
+    }
+
+    pub fn paths(&self) -> Vec<String> {
+        [&self.role, &self.mount_path]
+            .into_iter()
+            .filter_map(|source| source.path())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod azure_configuration {
+    use super::{
+        Azure, DEFAULT_AZURE_AUTH_MOUNT, DEFAULT_AZURE_METADATA_ENDPOINT, DEFAULT_AZURE_RESOURCE,
+    };
+    use crate::utilities::source::Source;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn initialization_defaults_to_the_instance_metadata_service() {
+        let azure = Azure::new(Source::Value("role".to_string()), None, None, None);
+        assert_eq!(
+            azure,
+            Azure {
+                role: Source::Value("role".to_string()),
+                mount_path: Source::Value(DEFAULT_AZURE_AUTH_MOUNT.to_string()),
+                resource: DEFAULT_AZURE_RESOURCE.to_string(),
+                metadata_endpoint: DEFAULT_AZURE_METADATA_ENDPOINT.to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn returns_paths_of_all_file_backed_components() {
+        let azure = Azure::new(
+            Source::FilePath("/role".to_string()),
+            Some(Source::FilePath("/mount".to_string())),
+            None,
+            None,
+        );
+        assert_eq!(
+            azure.paths(),
+            vec!["/role".to_string(), "/mount".to_string()]
+        );
+    }
+}
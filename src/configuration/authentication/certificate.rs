@@ -1,16 +1,25 @@
+use crate::utilities::source::Source;
+
 const DEFAULT_MOUNT_PATH: &str = "cert";
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct Certificate {
-    pub name: String,
-    pub mount_path: String,
+    pub name: Source,
+    pub mount_path: Source,
 }
 
 impl Certificate {
-    pub fn new(name: String, mount_path: Option<String>) -> Self {
+    pub fn new(name: Source, mount_path: Option<Source>) -> Self {
         Self {
             name,
-            mount_path: mount_path.unwrap_or(DEFAULT_MOUNT_PATH.to_string()),
+            mount_path: mount_path.unwrap_or(Source::Value(DEFAULT_MOUNT_PATH.to_string())),
         }
     }
+
+    pub fn paths(&self) -> Vec<String> {
+        [&self.name, &self.mount_path]
+            .iter()
+            .filter_map(|source| source.path())
+            .collect()
+    }
 }
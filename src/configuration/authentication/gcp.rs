@@ -0,0 +1,76 @@
+use crate::utilities::source::Source;
+
+const DEFAULT_GCP_AUTH_MOUNT: &str = "gcp";
+const DEFAULT_GCP_METADATA_ENDPOINT: &str = "http://metadata.google.internal";
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Gcp {
+    pub role: Source,
+    pub service_account_key: Option<Source>,
+    pub mount_path: Source,
+    pub metadata_endpoint: String,
+}
+
+impl Gcp {
+    pub fn new(
+        role: Source,
+        service_account_key: Option<Source>,
+        mount_path: Option<Source>,
+        metadata_endpoint: Option<String>,
+    ) -> Self {
+        Self {
+            role,
+            service_account_key,
+            mount_path: mount_path.unwrap_or(Source::Value(DEFAULT_GCP_AUTH_MOUNT.to_string())),
+            metadata_endpoint: metadata_endpoint
+                .unwrap_or(DEFAULT_GCP_METADATA_ENDPOINT.to_string()),
+        }
+    }
+
+    pub fn paths(&self) -> Vec<String> {
+        [
+            Some(&self.role),
+            self.service_account_key.as_ref(),
+            Some(&self.mount_path),
+        ]
+        .into_iter()
+        .flatten()
+        .filter_map(|source| source.path())
+        .collect()
+    }
+}
+
+#[cfg(test)]
+mod gcp_configuration {
+    use super::{Gcp, DEFAULT_GCP_AUTH_MOUNT, DEFAULT_GCP_METADATA_ENDPOINT};
+    use crate::utilities::source::Source;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn initialization_defaults_to_the_metadata_server() {
+        let gcp = Gcp::new(Source::Value("role".to_string()), None, None, None);
+        assert_eq!(
+            gcp,
+            Gcp {
+                role: Source::Value("role".to_string()),
+                service_account_key: None,
+                mount_path: Source::Value(DEFAULT_GCP_AUTH_MOUNT.to_string()),
+                metadata_endpoint: DEFAULT_GCP_METADATA_ENDPOINT.to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn returns_paths_of_all_file_backed_components() {
+        let gcp = Gcp::new(
+            Source::FilePath("/role".to_string()),
+            Some(Source::FilePath("/key.json".to_string())),
+            None,
+            None,
+        );
+        assert_eq!(
+            gcp.paths(),
+            vec!["/role".to_string(), "/key.json".to_string()]
+        );
+    }
+}
@@ -1,35 +1,106 @@
 use crate::utilities::source::Source;
+use jsonwebtoken::Algorithm;
+use std::time::Duration;
 
 const DEFAULT_JWT_AUTH_MOUNT: &str = "jwt";
+const DEFAULT_JWT_ISSUER: &str = "vault-kms-provider";
+const DEFAULT_JWT_SUBJECT: &str = "vault-kms-provider";
+const DEFAULT_JWT_AUDIENCE: &str = "vault";
+const DEFAULT_JWT_TTL: Duration = Duration::from_secs(300);
+
+/// Settings used to sign a short-lived JWT for each login, rather than reading one issued externally.
+#[derive(Clone, Debug, PartialEq)]
+pub struct JwtSigner {
+    pub private_key: Source,
+    pub algorithm: Algorithm,
+    pub issuer: String,
+    pub subject: String,
+    pub audience: String,
+    pub ttl: Duration,
+}
+
+impl JwtSigner {
+    pub fn new(
+        private_key: Source,
+        algorithm: Option<Algorithm>,
+        issuer: Option<String>,
+        subject: Option<String>,
+        audience: Option<String>,
+        ttl: Option<Duration>,
+    ) -> Self {
+        Self {
+            private_key,
+            algorithm: algorithm.unwrap_or(Algorithm::RS256),
+            issuer: issuer.unwrap_or(DEFAULT_JWT_ISSUER.to_string()),
+            subject: subject.unwrap_or(DEFAULT_JWT_SUBJECT.to_string()),
+            audience: audience.unwrap_or(DEFAULT_JWT_AUDIENCE.to_string()),
+            ttl: ttl.unwrap_or(DEFAULT_JWT_TTL),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum JwtSource {
+    Token(Source),
+    Signed(JwtSigner),
+}
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct Jwt {
-    pub role: Option<String>,
-    pub jwt: Source,
-    pub mount_path: String,
+    pub role: Option<Source>,
+    pub jwt: JwtSource,
+    pub mount_path: Source,
 }
 
 impl Jwt {
-    pub fn new(source: Source, role: Option<String>, mount_path: Option<String>) -> Self {
+    pub fn new(source: Source, role: Option<Source>, mount_path: Option<Source>) -> Self {
+        Self::with_source(JwtSource::Token(source), role, mount_path)
+    }
+
+    pub fn signed(signer: JwtSigner, role: Option<Source>, mount_path: Option<Source>) -> Self {
+        Self::with_source(JwtSource::Signed(signer), role, mount_path)
+    }
+
+    fn with_source(jwt: JwtSource, role: Option<Source>, mount_path: Option<Source>) -> Self {
         Self {
             role,
-            jwt: source,
-            mount_path: mount_path.unwrap_or(DEFAULT_JWT_AUTH_MOUNT.to_string()),
+            jwt,
+            mount_path: mount_path.unwrap_or(Source::Value(DEFAULT_JWT_AUTH_MOUNT.to_string())),
         }
     }
+
+    /// Signed JWTs cannot be watched for changes, so a new JWT is minted and used to log in again once two thirds
+    /// of the Vault token's lease has passed. Tokens without a lease never expire, so are not renewed.
+    pub fn refresh_in(&self, lease_duration: Duration) -> Option<Duration> {
+        match &self.jwt {
+            JwtSource::Signed(_) if !lease_duration.is_zero() => Some(lease_duration * 2 / 3),
+            _ => None,
+        }
+    }
+
+    pub fn paths(&self) -> Vec<String> {
+        let jwt = match &self.jwt {
+            JwtSource::Token(source) => source,
+            JwtSource::Signed(signer) => &signer.private_key,
+        };
+        [Some(jwt), self.role.as_ref(), Some(&self.mount_path)]
+            .into_iter()
+            .flatten()
+            .filter_map(|source| source.path())
+            .collect()
+    }
 }
 
 #[cfg(test)]
 mod jwt_configuration {
-    use super::{Jwt, DEFAULT_JWT_AUTH_MOUNT};
-    use crate::utilities::source::Source;
+    use super::*;
     use pretty_assertions::assert_eq;
 
     #[test]
     fn initialization_via_new_defaults_to_jwt_mount_path() {
         assert_eq!(
             Jwt::new(Source::Value("hello!".to_string()), None, None).mount_path,
-            DEFAULT_JWT_AUTH_MOUNT.to_string()
+            Source::Value(DEFAULT_JWT_AUTH_MOUNT.to_string())
         );
     }
 
@@ -40,10 +111,61 @@ mod jwt_configuration {
             Jwt::new(
                 Source::Value("hello!".to_string()),
                 None,
-                Some(path.to_string())
+                Some(Source::Value(path.to_string()))
             )
             .mount_path,
-            path.to_string()
+            Source::Value(path.to_string())
+        );
+    }
+
+    #[test]
+    fn returns_paths_of_all_file_backed_components() {
+        let jwt = Jwt::new(
+            Source::FilePath("/jwt".to_string()),
+            Some(Source::FilePath("/role".to_string())),
+            None,
+        );
+        assert_eq!(jwt.paths(), vec!["/jwt".to_string(), "/role".to_string()]);
+    }
+
+    #[test]
+    fn signer_initialization_uses_defaults() {
+        let private_key = Source::FilePath("/key.pem".to_string());
+        assert_eq!(
+            JwtSigner::new(private_key.clone(), None, None, None, None, None),
+            JwtSigner {
+                private_key,
+                algorithm: Algorithm::RS256,
+                issuer: DEFAULT_JWT_ISSUER.to_string(),
+                subject: DEFAULT_JWT_SUBJECT.to_string(),
+                audience: DEFAULT_JWT_AUDIENCE.to_string(),
+                ttl: DEFAULT_JWT_TTL,
+            }
         );
     }
+
+    #[test]
+    fn signed_jwts_are_refreshed_before_the_vault_token_expires() {
+        let signer = JwtSigner::new(
+            Source::FilePath("/key.pem".to_string()),
+            None,
+            None,
+            None,
+            None,
+            Some(Duration::from_secs(300)),
+        );
+        let jwt = Jwt::signed(signer, None, None);
+        assert_eq!(
+            jwt.refresh_in(Duration::from_secs(3600)),
+            Some(Duration::from_secs(2400))
+        );
+        assert_eq!(jwt.refresh_in(Duration::ZERO), None);
+        assert_eq!(jwt.paths(), vec!["/key.pem".to_string()]);
+    }
+
+    #[test]
+    fn jwts_read_from_a_source_are_not_refreshed_on_a_schedule() {
+        let jwt = Jwt::new(Source::FilePath("/jwt".to_string()), None, None);
+        assert_eq!(jwt.refresh_in(Duration::from_secs(3600)), None);
+    }
 }
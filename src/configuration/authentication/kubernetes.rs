@@ -5,19 +5,27 @@ const DEFAULT_VAULT_ROLE: &str = "vault-kms-provider";
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct Kubernetes {
-    pub role: String,
+    pub role: Source,
     pub jwt: Source,
-    pub mount_path: String,
+    pub mount_path: Source,
 }
 
 impl Kubernetes {
-    pub fn new(source: Source, role: Option<String>, mount_path: Option<String>) -> Self {
+    pub fn new(source: Source, role: Option<Source>, mount_path: Option<Source>) -> Self {
         Self {
-            role: role.unwrap_or(DEFAULT_VAULT_ROLE.to_string()),
+            role: role.unwrap_or(Source::Value(DEFAULT_VAULT_ROLE.to_string())),
             jwt: source,
-            mount_path: mount_path.unwrap_or(DEFAULT_KUBERNETES_AUTH_MOUNT.to_string()),
+            mount_path: mount_path
+                .unwrap_or(Source::Value(DEFAULT_KUBERNETES_AUTH_MOUNT.to_string())),
         }
     }
+
+    pub fn paths(&self) -> Vec<String> {
+        [&self.jwt, &self.role, &self.mount_path]
+            .iter()
+            .filter_map(|source| source.path())
+            .collect()
+    }
 }
 
 #[cfg(test)]
@@ -30,7 +38,7 @@ mod jwt_configuration {
     fn initialization_defaults_to_kubernetes_mount_path() {
         assert_eq!(
             Kubernetes::new(Source::Value("hello!".to_string()), None, None).mount_path,
-            DEFAULT_KUBERNETES_AUTH_MOUNT.to_string()
+            Source::Value(DEFAULT_KUBERNETES_AUTH_MOUNT.to_string())
         );
     }
 
@@ -41,10 +49,27 @@ mod jwt_configuration {
             Kubernetes::new(
                 Source::Value("hello!".to_string()),
                 None,
-                Some(path.to_string())
+                Some(Source::Value(path.to_string()))
             )
             .mount_path,
-            path.to_string()
+            Source::Value(path.to_string())
+        );
+    }
+
+    #[test]
+    fn returns_paths_of_all_file_backed_components() {
+        let kubernetes = Kubernetes::new(
+            Source::FilePath("/jwt".to_string()),
+            Some(Source::FilePath("/role".to_string())),
+            Some(Source::FilePath("/mount".to_string())),
+        );
+        assert_eq!(
+            kubernetes.paths(),
+            vec![
+                "/jwt".to_string(),
+                "/role".to_string(),
+                "/mount".to_string()
+            ]
         );
     }
 }
@@ -1,20 +1,40 @@
 mod app_role;
+mod aws;
+mod azure;
 mod certificate;
+mod gcp;
 mod jwt;
 mod kubernetes;
 mod user_pass;
 
 pub use app_role::AppRole;
+pub use aws::Aws;
+pub use azure::Azure;
 pub use certificate::Certificate;
-pub use jwt::Jwt;
+pub use gcp::Gcp;
+pub use jwt::{Jwt, JwtSigner, JwtSource};
 pub use kubernetes::Kubernetes;
 pub use user_pass::UserPass;
 
 use crate::utilities::{environment::Environment, source::Source};
+use std::time::Duration;
+use tracing::warn;
 
 const DEFAULT_USER: &str = "vault-kms-provider";
+const METHOD_PRIORITY: [&str; 9] = [
+    "token",
+    "kubernetes",
+    "userpass",
+    "approle",
+    "jwt",
+    "certificate",
+    "aws",
+    "azure",
+    "gcp",
+];
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, strum_macros::Display)]
+#[strum(serialize_all = "snake_case")]
 pub enum Credentials {
     AppRole(AppRole),
     UserPass(UserPass),
@@ -22,37 +42,275 @@ pub enum Credentials {
     Certificate(Certificate),
     Token(Source),
     Jwt(Jwt),
+    Aws(Aws),
+    Azure(Azure),
+    Gcp(Gcp),
+    /// Requests carry no token, Vault Agent's cache adds its auto-auth token to them.
+    Agent,
     None,
 }
 
 impl Credentials {
     pub fn from_env() -> Self {
-        let auth_mount = Environment::VaultAuthMount.get();
-        if let Some(token) = Environment::VaultToken.source() {
-            Self::Token(token)
-        } else if let Some(jwt) = Environment::VaultKubernetesJwt.source() {
-            Self::Kubernetes(Kubernetes::new(
-                jwt,
-                Environment::VaultKubernetesRole.get(),
-                auth_mount,
-            ))
-        } else if let Some(password) = Environment::VaultPassword.source() {
-            Self::UserPass(UserPass::new(
-                Environment::VaultUser.or(DEFAULT_USER),
-                password,
-                auth_mount,
-            ))
-        } else if let Some((role_id, secret_id)) = Environment::VaultRoleId
-            .get()
-            .zip(Environment::VaultSecretId.source())
-        {
-            Self::AppRole(AppRole::new(role_id, secret_id, auth_mount))
-        } else if let Some(jwt) = Environment::VaultJwt.source() {
-            Self::Jwt(Jwt::new(jwt, Environment::VaultJwtRole.get(), auth_mount))
-        } else if let Some(certificate) = Environment::VaultCertificateName.get() {
-            Self::Certificate(Certificate::new(certificate, auth_mount))
+        METHOD_PRIORITY
+            .iter()
+            .find_map(|method| Self::from_method(method))
+            .unwrap_or(Self::None)
+    }
+
+    pub fn chain_from_env() -> Vec<Self> {
+        if let Some(methods) = Environment::VaultAuthMethods.get() {
+            Self::chain(&methods)
         } else {
-            Self::None
+            match Self::from_env() {
+                Self::None => vec![],
+                credentials => vec![credentials],
+            }
+        }
+    }
+
+    pub fn chain(methods: &str) -> Vec<Self> {
+        let methods: Vec<&str> = methods
+            .split(',')
+            .map(str::trim)
+            .filter(|method| !method.is_empty())
+            .collect();
+        let default_mount = Environment::VaultAuthMount.source();
+        if methods.len() > 1 && default_mount.is_some() {
+            warn!(
+                "Ignoring {} as several authentication methods are configured, set the mount of each method instead",
+                Environment::VaultAuthMount
+            );
+        }
+        let default_mount = default_mount.filter(|_| methods.len() == 1);
+        methods
+            .into_iter()
+            .filter_map(|method| {
+                let credentials = Self::with_default_mount(method, default_mount.clone());
+                if credentials.is_none() {
+                    warn!(
+                        "Authentication method \"{}\" is unknown or not configured, skipping",
+                        method
+                    );
+                }
+                credentials
+            })
+            .collect()
+    }
+
+    /// Variable setting the auth mount of `method`.
+    fn auth_mount(method: &str) -> Option<Environment> {
+        match method {
+            "kubernetes" => Some(Environment::VaultKubernetesAuthMount),
+            "userpass" | "user_pass" => Some(Environment::VaultUserpassAuthMount),
+            "approle" | "app_role" => Some(Environment::VaultApproleAuthMount),
+            "jwt" => Some(Environment::VaultJwtAuthMount),
+            "certificate" | "cert" => Some(Environment::VaultCertificateAuthMount),
+            "aws" => Some(Environment::VaultAwsAuthMount),
+            "azure" => Some(Environment::VaultAzureAuthMount),
+            "gcp" => Some(Environment::VaultGcpAuthMount),
+            _ => None,
+        }
+    }
+
+    /// Credentials of a single method, mounted at its own auth mount or at `VAULT_AUTH_MOUNT`.
+    pub fn from_method(method: &str) -> Option<Self> {
+        Self::with_default_mount(method, Environment::VaultAuthMount.source())
+    }
+
+    fn with_default_mount(method: &str, default_mount: Option<Source>) -> Option<Self> {
+        let method = method.to_lowercase();
+        let auth_mount = Self::auth_mount(&method)
+            .and_then(|variable| variable.source())
+            .or(default_mount);
+        match method.as_str() {
+            "token" => Environment::VaultToken.source().map(Self::Token),
+            "kubernetes" => Environment::VaultKubernetesJwt.source().map(|jwt| {
+                Self::Kubernetes(Kubernetes::new(
+                    jwt,
+                    Environment::VaultKubernetesRole.source(),
+                    auth_mount,
+                ))
+            }),
+            "userpass" | "user_pass" => Environment::VaultPassword.source().map(|password| {
+                Self::UserPass(UserPass::new(
+                    Environment::VaultUser
+                        .source()
+                        .unwrap_or(Source::Value(DEFAULT_USER.to_string())),
+                    password,
+                    auth_mount,
+                ))
+            }),
+            "approle" | "app_role" => Environment::VaultRoleId
+                .source()
+                .zip(Environment::VaultSecretId.source())
+                .map(|(role_id, secret_id)| {
+                    Self::AppRole(
+                        if Environment::VaultSecretIdWrapped.parsed().unwrap_or(false) {
+                            AppRole::with_wrapped_secret_id(role_id, secret_id, auth_mount)
+                        } else {
+                            AppRole::new(role_id, secret_id, auth_mount)
+                        },
+                    )
+                }),
+            "jwt" => Environment::VaultJwt
+                .source()
+                .map(|jwt| Jwt::new(jwt, Environment::VaultJwtRole.source(), auth_mount.clone()))
+                .or(Environment::VaultJwtPrivateKey.source().map(|private_key| {
+                    Jwt::signed(
+                        JwtSigner::new(
+                            private_key,
+                            Environment::VaultJwtAlgorithm.parsed(),
+                            Environment::VaultJwtIssuer.get(),
+                            Environment::VaultJwtSubject.get(),
+                            Environment::VaultJwtAudience.get(),
+                            Environment::VaultJwtTtl.parsed().map(Duration::from_secs),
+                        ),
+                        Environment::VaultJwtRole.source(),
+                        auth_mount,
+                    )
+                }))
+                .map(Self::Jwt),
+            "certificate" | "cert" => Environment::VaultCertificateName
+                .source()
+                .map(|name| Self::Certificate(Certificate::new(name, auth_mount))),
+            "aws" => Environment::VaultAwsRole.source().map(|role| {
+                Self::Aws(Aws::new(
+                    Some(role),
+                    Environment::VaultAwsHeaderValue.source(),
+                    auth_mount,
+                    Environment::VaultAwsRegion
+                        .get()
+                        .or(Environment::AwsRegion.get()),
+                    Environment::VaultAwsStsEndpoint.get(),
+                ))
+            }),
+            "azure" => Environment::VaultAzureRole.source().map(|role| {
+                Self::Azure(Azure::new(
+                    role,
+                    auth_mount,
+                    Environment::VaultAzureResource.get(),
+                    Environment::VaultAzureMetadataEndpoint.get(),
+                ))
+            }),
+            "gcp" => Environment::VaultGcpRole.source().map(|role| {
+                Self::Gcp(Gcp::new(
+                    role,
+                    Environment::VaultGcpServiceAccountKey.source(),
+                    auth_mount,
+                    Environment::VaultGcpMetadataEndpoint.get(),
+                ))
+            }),
+            _ => None,
+        }
+    }
+
+    /// Time after a successful login, issuing a token leased for `lease_duration`, at which the credentials should
+    /// be used to log in again.
+    pub fn refresh_in(&self, lease_duration: Duration) -> Option<Duration> {
+        match self {
+            Self::Jwt(credentials) => credentials.refresh_in(lease_duration),
+            _ => None,
+        }
+    }
+
+    pub fn paths(&self) -> Vec<String> {
+        match self {
+            Self::AppRole(credentials) => credentials.paths(),
+            Self::UserPass(credentials) => credentials.paths(),
+            Self::Kubernetes(credentials) => credentials.paths(),
+            Self::Certificate(credentials) => credentials.paths(),
+            Self::Jwt(credentials) => credentials.paths(),
+            Self::Aws(credentials) => credentials.paths(),
+            Self::Azure(credentials) => credentials.paths(),
+            Self::Gcp(credentials) => credentials.paths(),
+            Self::Token(token) => token.path().into_iter().collect(),
+            Self::Agent | Self::None => vec![],
+        }
+    }
+}
+
+#[cfg(test)]
+mod credentials {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn returns_the_path_of_a_file_backed_token() {
+        let credentials = Credentials::Token(Source::FilePath("/token".to_string()));
+        assert_eq!(credentials.paths(), vec!["/token".to_string()]);
+    }
+
+    #[test]
+    fn returns_no_paths_for_value_backed_credentials() {
+        let credentials = Credentials::UserPass(UserPass::new(
+            Source::Value("user".to_string()),
+            Source::Value("password".to_string()),
+            None,
+        ));
+        assert_eq!(credentials.paths(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn returns_no_paths_when_no_credentials_are_defined() {
+        assert_eq!(Credentials::None.paths(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn displays_the_name_of_the_authentication_method() {
+        let credentials = Credentials::Token(Source::Value("token".to_string()));
+        assert_eq!(credentials.to_string(), "token");
+    }
+
+    #[test]
+    fn builds_a_chain_of_configured_methods_in_order() {
+        unsafe {
+            std::env::set_var(Environment::VaultCertificateName.to_string(), "certificate");
+        }
+        assert_eq!(
+            Credentials::chain("unknown, certificate,,"),
+            vec![Credentials::Certificate(Certificate::new(
+                Source::Value("certificate".to_string()),
+                None
+            ))]
+        );
+    }
+
+    #[test]
+    fn uses_the_auth_mount_of_each_method() {
+        unsafe {
+            std::env::set_var(Environment::VaultAzureRole.to_string(), "azure-role");
+            std::env::set_var(Environment::VaultAzureAuthMount.to_string(), "azure-east");
         }
+        assert_eq!(
+            Credentials::with_default_mount("azure", Some(Source::Value("auth".to_string()))),
+            Some(Credentials::Azure(Azure::new(
+                Source::Value("azure-role".to_string()),
+                Some(Source::Value("azure-east".to_string())),
+                None,
+                None
+            )))
+        );
+    }
+
+    #[test]
+    fn falls_back_to_the_shared_auth_mount() {
+        unsafe {
+            std::env::set_var(Environment::VaultGcpRole.to_string(), "gcp-role");
+        }
+        assert_eq!(
+            Credentials::with_default_mount("gcp", Some(Source::Value("auth".to_string()))),
+            Some(Credentials::Gcp(Gcp::new(
+                Source::Value("gcp-role".to_string()),
+                None,
+                Some(Source::Value("auth".to_string())),
+                None
+            )))
+        );
+    }
+
+    #[test]
+    fn does_not_build_methods_that_are_not_configured() {
+        assert_eq!(Credentials::from_method("userpass"), None);
     }
 }
@@ -4,17 +4,25 @@ const DEFAULT_USER_PASS_AUTH_PATH: &str = "userpass";
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct UserPass {
-    pub username: String,
+    pub username: Source,
     pub password: Source,
-    pub mount_path: String,
+    pub mount_path: Source,
 }
 
 impl UserPass {
-    pub fn new(username: String, password: Source, mount_path: Option<String>) -> Self {
+    pub fn new(username: Source, password: Source, mount_path: Option<Source>) -> Self {
         Self {
             username,
             password,
-            mount_path: mount_path.unwrap_or(DEFAULT_USER_PASS_AUTH_PATH.to_string()),
+            mount_path: mount_path
+                .unwrap_or(Source::Value(DEFAULT_USER_PASS_AUTH_PATH.to_string())),
         }
     }
+
+    pub fn paths(&self) -> Vec<String> {
+        [&self.username, &self.password, &self.mount_path]
+            .iter()
+            .filter_map(|source| source.path())
+            .collect()
+    }
 }
@@ -1,23 +1,49 @@
 use crate::utilities::environment::Environment;
+use crate::utilities::source::Source;
+use std::time::Duration;
 
 const DEFAULT_HEALTH_ENDPOINT: &str = "0.0.0.0:8080";
+const DEFAULT_LIVENESS_THRESHOLD: Duration = Duration::from_secs(60);
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct HealthCheckConfiguration {
+    /// Address to listen on, ex: `127.0.0.1:8080` to only serve localhost, or the path of a unix socket
+    /// prefixed with `unix://`.
     pub endpoint: String,
+    /// Bearer token granting access to admin endpoints over TCP, which are otherwise only served to
+    /// clients of the unix socket or with a verified certificate.
+    pub admin_token: Option<Source>,
+    /// Certificate and key files to serve HTTPS with, reloaded whenever they change.
+    pub cert: Option<String>,
+    pub key: Option<String>,
+    /// CA certificates verifying client certificates, clients presenting one can use admin endpoints.
+    pub client_ca: Option<String>,
+    /// How long the gRPC server or a background task can go without making progress before liveness fails.
+    pub liveness_threshold: Duration,
+    /// Runs the self-test once the gRPC server is listening, exiting if it fails.
+    pub selftest_on_startup: bool,
 }
 
 impl Default for HealthCheckConfiguration {
     fn default() -> Self {
         Self {
             endpoint: Environment::HttpAddress.or(DEFAULT_HEALTH_ENDPOINT),
+            admin_token: Environment::HttpAdminToken.source(),
+            cert: Environment::HttpTlsCert.get(),
+            key: Environment::HttpTlsKey.get(),
+            client_ca: Environment::HttpTlsClientCa.get(),
+            liveness_threshold: Environment::HttpLivenessThreshold
+                .parsed()
+                .map(Duration::from_secs)
+                .unwrap_or(DEFAULT_LIVENESS_THRESHOLD),
+            selftest_on_startup: Environment::SelftestOnStartup.parsed().unwrap_or(false),
         }
     }
 }
 
 #[cfg(test)]
 mod health_configuration {
-    use super::{HealthCheckConfiguration, DEFAULT_HEALTH_ENDPOINT};
+    use super::{HealthCheckConfiguration, DEFAULT_HEALTH_ENDPOINT, DEFAULT_LIVENESS_THRESHOLD};
     use pretty_assertions::assert_eq;
 
     #[test]
@@ -26,6 +52,12 @@ mod health_configuration {
             HealthCheckConfiguration::default(),
             HealthCheckConfiguration {
                 endpoint: DEFAULT_HEALTH_ENDPOINT.to_string(),
+                admin_token: None,
+                cert: None,
+                key: None,
+                client_ca: None,
+                liveness_threshold: DEFAULT_LIVENESS_THRESHOLD,
+                selftest_on_startup: false,
             }
         )
     }
@@ -0,0 +1,103 @@
+use crate::utilities::environment::Environment;
+
+const DEFAULT_MAX_IN_FLIGHT_REQUESTS: usize = 64;
+const DEFAULT_MAX_QUEUED_REQUESTS: usize = 256;
+
+/// Back-pressure applied to KMS requests before they reach Vault.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LimitsConfiguration {
+    /// Requests handled concurrently across all methods.
+    pub max_in_flight: usize,
+    /// Requests waiting for a free slot, any beyond this are rejected with `ResourceExhausted`.
+    pub max_queued: usize,
+    pub max_in_flight_encrypt: Option<usize>,
+    pub max_in_flight_decrypt: Option<usize>,
+    pub max_in_flight_status: Option<usize>,
+}
+
+impl Default for LimitsConfiguration {
+    fn default() -> Self {
+        Self::new(
+            Environment::MaxInFlightRequests.parsed(),
+            Environment::MaxQueuedRequests.parsed(),
+            Environment::MaxInFlightEncryptRequests.parsed(),
+            Environment::MaxInFlightDecryptRequests.parsed(),
+            Environment::MaxInFlightStatusRequests.parsed(),
+        )
+    }
+}
+
+impl LimitsConfiguration {
+    pub fn new(
+        max_in_flight: Option<usize>,
+        max_queued: Option<usize>,
+        max_in_flight_encrypt: Option<usize>,
+        max_in_flight_decrypt: Option<usize>,
+        max_in_flight_status: Option<usize>,
+    ) -> Self {
+        // A limit of zero would leave every request waiting in the queue or rejected, so it is ignored.
+        Self {
+            max_in_flight: max_in_flight
+                .filter(|limit| *limit > 0)
+                .unwrap_or(DEFAULT_MAX_IN_FLIGHT_REQUESTS),
+            max_queued: max_queued.unwrap_or(DEFAULT_MAX_QUEUED_REQUESTS),
+            max_in_flight_encrypt: max_in_flight_encrypt.filter(|limit| *limit > 0),
+            max_in_flight_decrypt: max_in_flight_decrypt.filter(|limit| *limit > 0),
+            max_in_flight_status: max_in_flight_status.filter(|limit| *limit > 0),
+        }
+    }
+
+    /// Concurrency limit for a KMS method (`Encrypt`, `Decrypt` or `Status`), if one is set.
+    pub fn method_limit(&self, method: &str) -> Option<usize> {
+        match method {
+            "Encrypt" => self.max_in_flight_encrypt,
+            "Decrypt" => self.max_in_flight_decrypt,
+            "Status" => self.max_in_flight_status,
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod limits_configuration {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn initializes_values_via_default_method() {
+        assert_eq!(
+            LimitsConfiguration::default(),
+            LimitsConfiguration {
+                max_in_flight: DEFAULT_MAX_IN_FLIGHT_REQUESTS,
+                max_queued: DEFAULT_MAX_QUEUED_REQUESTS,
+                max_in_flight_encrypt: None,
+                max_in_flight_decrypt: None,
+                max_in_flight_status: None,
+            }
+        );
+    }
+
+    #[test]
+    fn returns_the_limit_for_each_method() {
+        let limits = LimitsConfiguration::new(None, None, Some(1), Some(2), Some(3));
+        assert_eq!(limits.method_limit("Encrypt"), Some(1));
+        assert_eq!(limits.method_limit("Decrypt"), Some(2));
+        assert_eq!(limits.method_limit("Status"), Some(3));
+        assert_eq!(limits.method_limit("Unknown"), None);
+    }
+
+    #[test]
+    fn ignores_limits_of_zero() {
+        let limits = LimitsConfiguration::new(Some(0), Some(0), Some(0), Some(0), Some(0));
+        assert_eq!(
+            limits,
+            LimitsConfiguration {
+                max_in_flight: DEFAULT_MAX_IN_FLIGHT_REQUESTS,
+                max_queued: 0,
+                max_in_flight_encrypt: None,
+                max_in_flight_decrypt: None,
+                max_in_flight_status: None,
+            }
+        );
+    }
+}
@@ -1,14 +1,93 @@
 use crate::utilities::environment::Environment;
 use crate::utilities::logging::str_to_log_level;
+use std::path::PathBuf;
+use std::time::Duration;
 use tracing::Level;
 
 const DEFAULT_LOG_LEVEL: &str = "info";
 const DEFAULT_LOG_FORMAT: &str = "compact";
+const DEFAULT_LOG_OUTPUT: &str = "stdout";
+const DEFAULT_MAX_FILE_SIZE: u64 = 100 * 1024 * 1024;
+const DEFAULT_MAX_FILES: usize = 5;
+const DEFAULT_JOURNALD_SOCKET: &str = "/run/systemd/journal/socket";
+const DEFAULT_SYSLOG_SOCKET: &str = "/dev/log";
+
+/// Where logs are written, set as a comma separated list of `stdout`, `journald`, `syslog`,
+/// `syslog:<socket path>` or the path of a file.
+#[derive(Clone, Debug, PartialEq)]
+pub enum LogOutput {
+    Stdout,
+    File(PathBuf),
+    /// Socket of the journald native protocol.
+    Journald(PathBuf),
+    /// Datagram socket of the local syslog daemon.
+    Syslog(PathBuf),
+}
+
+impl From<&str> for LogOutput {
+    fn from(value: &str) -> Self {
+        let (name, socket) = match value.split_once(':') {
+            Some((name, socket)) => (name, Some(PathBuf::from(socket))),
+            None => (value, None),
+        };
+        match name.to_lowercase().as_str() {
+            "stdout" if socket.is_none() => Self::Stdout,
+            "journald" => Self::Journald(socket.unwrap_or(PathBuf::from(DEFAULT_JOURNALD_SOCKET))),
+            "syslog" => Self::Syslog(socket.unwrap_or(PathBuf::from(DEFAULT_SYSLOG_SOCKET))),
+            _ => Self::File(PathBuf::from(value)),
+        }
+    }
+}
+
+impl LogOutput {
+    pub fn list(outputs: &str) -> Vec<Self> {
+        outputs
+            .split(',')
+            .map(str::trim)
+            .filter(|output| !output.is_empty())
+            .map(Self::from)
+            .collect()
+    }
+}
+
+/// Rotation of log files besides the size limit.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LogRotation {
+    Never,
+    Hourly,
+    Daily,
+}
+
+impl From<&str> for LogRotation {
+    fn from(value: &str) -> Self {
+        match value.to_lowercase().as_str() {
+            "hourly" => Self::Hourly,
+            "daily" => Self::Daily,
+            _ => Self::Never,
+        }
+    }
+}
+
+impl LogRotation {
+    pub fn interval(&self) -> Option<Duration> {
+        match self {
+            Self::Never => None,
+            Self::Hourly => Some(Duration::from_secs(60 * 60)),
+            Self::Daily => Some(Duration::from_secs(24 * 60 * 60)),
+        }
+    }
+}
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct LoggingConfiguration {
     pub level: Level,
     pub format: String,
+    pub outputs: Vec<LogOutput>,
+    /// Size in bytes at which log files are rotated.
+    pub max_file_size: u64,
+    /// Rotated log files kept besides the current one.
+    pub max_files: usize,
+    pub rotation: LogRotation,
 }
 
 impl Default for LoggingConfiguration {
@@ -16,14 +95,26 @@ impl Default for LoggingConfiguration {
         Self {
             level: str_to_log_level(Environment::LogLevel.or(DEFAULT_LOG_LEVEL).as_str()),
             format: Environment::LogFormat.or(DEFAULT_LOG_FORMAT),
+            outputs: LogOutput::list(&Environment::LogOutput.or(DEFAULT_LOG_OUTPUT)),
+            max_file_size: Environment::LogFileMaxSize
+                .parsed()
+                .unwrap_or(DEFAULT_MAX_FILE_SIZE),
+            max_files: Environment::LogFileMaxFiles
+                .parsed()
+                .unwrap_or(DEFAULT_MAX_FILES),
+            rotation: Environment::LogFileRotation
+                .get()
+                .as_deref()
+                .map(LogRotation::from)
+                .unwrap_or(LogRotation::Never),
         }
     }
 }
 
 #[cfg(test)]
 mod logging_configuration {
-    use super::{LoggingConfiguration, DEFAULT_LOG_FORMAT};
-    use tracing::Level;
+    use super::*;
+    use pretty_assertions::assert_eq;
 
     #[test]
     fn initializes_values_with_default() {
@@ -32,7 +123,35 @@ mod logging_configuration {
             LoggingConfiguration {
                 level: Level::INFO,
                 format: DEFAULT_LOG_FORMAT.to_string(),
+                outputs: vec![LogOutput::Stdout],
+                max_file_size: DEFAULT_MAX_FILE_SIZE,
+                max_files: DEFAULT_MAX_FILES,
+                rotation: LogRotation::Never,
             }
         )
     }
+
+    #[test]
+    fn parses_a_list_of_outputs() {
+        assert_eq!(
+            LogOutput::list("stdout, journald,syslog,syslog:/run/syslog.sock,/var/log/kms.log,"),
+            vec![
+                LogOutput::Stdout,
+                LogOutput::Journald(PathBuf::from(DEFAULT_JOURNALD_SOCKET)),
+                LogOutput::Syslog(PathBuf::from(DEFAULT_SYSLOG_SOCKET)),
+                LogOutput::Syslog(PathBuf::from("/run/syslog.sock")),
+                LogOutput::File(PathBuf::from("/var/log/kms.log")),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_the_rotation() {
+        assert_eq!(LogRotation::from("Daily"), LogRotation::Daily);
+        assert_eq!(
+            LogRotation::from("hourly").interval(),
+            Some(Duration::from_secs(3600))
+        );
+        assert_eq!(LogRotation::from("never").interval(), None);
+    }
 }
@@ -0,0 +1,45 @@
+use crate::utilities::environment::Environment;
+
+/// Protections keeping plaintext and credentials held in memory from reaching disk.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MemoryConfiguration {
+    /// Locks the process's memory so it is never swapped, requires `CAP_IPC_LOCK` or a sufficient `RLIMIT_MEMLOCK`.
+    pub lock: bool,
+    /// Prevents core dumps, which would contain the process's memory.
+    pub disable_core_dumps: bool,
+}
+
+impl Default for MemoryConfiguration {
+    fn default() -> Self {
+        Self::new(
+            Environment::LockMemory.parsed(),
+            Environment::DisableCoreDumps.parsed(),
+        )
+    }
+}
+
+impl MemoryConfiguration {
+    pub fn new(lock: Option<bool>, disable_core_dumps: Option<bool>) -> Self {
+        Self {
+            lock: lock.unwrap_or(false),
+            disable_core_dumps: disable_core_dumps.unwrap_or(false),
+        }
+    }
+}
+
+#[cfg(test)]
+mod memory_configuration {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn initializes_values_via_default_method() {
+        assert_eq!(
+            MemoryConfiguration::default(),
+            MemoryConfiguration {
+                lock: false,
+                disable_core_dumps: false,
+            }
+        );
+    }
+}
@@ -1,7 +1,12 @@
+pub mod agent;
+pub mod audit;
 pub mod authentication;
 pub mod health;
+pub mod limits;
 pub mod logging;
+pub mod memory;
 pub mod socket;
+pub mod telemetry;
 pub mod tls;
 pub mod vault;
 
@@ -11,6 +16,9 @@ pub struct ServerConfiguration {
     pub vault: vault::VaultConfiguration,
     pub tls: tls::TlsConfiguration,
     pub health: health::HealthCheckConfiguration,
+    pub limits: limits::LimitsConfiguration,
+    pub audit: audit::AuditConfiguration,
+    pub memory: memory::MemoryConfiguration,
 }
 
 impl Default for ServerConfiguration {
@@ -20,6 +28,9 @@ impl Default for ServerConfiguration {
             vault: vault::VaultConfiguration::default(),
             tls: tls::TlsConfiguration::default(),
             health: health::HealthCheckConfiguration::default(),
+            limits: limits::LimitsConfiguration::default(),
+            audit: audit::AuditConfiguration::default(),
+            memory: memory::MemoryConfiguration::default(),
         }
     }
 }
@@ -38,6 +49,9 @@ mod server_configuration {
                 vault: vault::VaultConfiguration::default(),
                 tls: tls::TlsConfiguration::default(),
                 health: health::HealthCheckConfiguration::default(),
+                limits: limits::LimitsConfiguration::default(),
+                audit: audit::AuditConfiguration::default(),
+                memory: memory::MemoryConfiguration::default(),
             }
         );
     }
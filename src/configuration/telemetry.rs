@@ -0,0 +1,71 @@
+use crate::utilities::environment::Environment;
+
+const DEFAULT_SERVICE_NAME: &str = "vault-kms-provider";
+const DEFAULT_SAMPLING_RATIO: f64 = 1.0;
+
+/// Export of traces to an OpenTelemetry collector over OTLP/HTTP, disabled unless an endpoint is set.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TelemetryConfiguration {
+    /// Base url of the collector, spans are sent to `<endpoint>/v1/traces`.
+    pub endpoint: Option<String>,
+    /// Share of traces started by the provider that are exported, traces started by the caller
+    /// follow the caller's sampling decision.
+    pub sampling_ratio: f64,
+    pub service_name: String,
+}
+
+impl Default for TelemetryConfiguration {
+    fn default() -> Self {
+        Self::new(
+            Environment::OtelExporterOtlpEndpoint.get(),
+            Environment::OtelTracesSamplerArg.parsed(),
+            Environment::OtelServiceName.get(),
+        )
+    }
+}
+
+impl TelemetryConfiguration {
+    pub fn new(
+        endpoint: Option<String>,
+        sampling_ratio: Option<f64>,
+        service_name: Option<String>,
+    ) -> Self {
+        Self {
+            endpoint,
+            sampling_ratio: sampling_ratio
+                .unwrap_or(DEFAULT_SAMPLING_RATIO)
+                .clamp(0.0, 1.0),
+            service_name: service_name.unwrap_or(DEFAULT_SERVICE_NAME.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod telemetry_configuration {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn initializes_values_via_default_method() {
+        assert_eq!(
+            TelemetryConfiguration::default(),
+            TelemetryConfiguration {
+                endpoint: None,
+                sampling_ratio: DEFAULT_SAMPLING_RATIO,
+                service_name: DEFAULT_SERVICE_NAME.to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn limits_the_sampling_ratio_to_between_zero_and_one() {
+        assert_eq!(
+            TelemetryConfiguration::new(None, Some(2.0), None).sampling_ratio,
+            1.0
+        );
+        assert_eq!(
+            TelemetryConfiguration::new(None, Some(-1.0), None).sampling_ratio,
+            0.0
+        );
+    }
+}
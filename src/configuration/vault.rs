@@ -1,32 +1,161 @@
+use crate::configuration::agent::AgentConfiguration;
 use crate::configuration::authentication::Credentials;
 use crate::utilities::environment::Environment;
+use std::time::Duration;
+use tracing::info;
 
+const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+const DEFAULT_RATE_LIMIT_RETRIES: u32 = 3;
 const DEFAULT_VAULT_ADDRESS: &str = "https://vault.vault.svc.cluster.local:8200";
 const DEFAULT_VAULT_TRANSIT_KEY: &str = "vault-kms-provider";
 const DEFAULT_TRANSIT_MOUNT_PATH: &str = "transit";
 
+/// Limits on how long a single request to Vault may take.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Timeouts {
+    pub connect: Duration,
+    pub request: Duration,
+}
+
+impl Default for Timeouts {
+    fn default() -> Self {
+        Self::new(None, None)
+    }
+}
+
+impl Timeouts {
+    pub fn new(connect: Option<Duration>, request: Option<Duration>) -> Self {
+        Self {
+            connect: connect.unwrap_or(DEFAULT_CONNECT_TIMEOUT),
+            request: request.unwrap_or(DEFAULT_REQUEST_TIMEOUT),
+        }
+    }
+
+    pub fn from_env() -> Self {
+        Self::new(
+            Environment::VaultConnectTimeout
+                .parsed()
+                .map(Duration::from_secs),
+            Environment::VaultRequestTimeout
+                .parsed()
+                .map(Duration::from_secs),
+        )
+    }
+}
+
+/// Client side rate limiting of transit requests, and retrying of requests rejected by a Vault rate limit quota.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RateLimit {
+    /// Transit requests sent per second, unlimited when not set.
+    pub rate: Option<f64>,
+    /// Requests that can be sent at once before being limited to `rate`.
+    pub burst: u32,
+    /// Times a request rejected with `429 Too Many Requests` is retried.
+    pub retries: u32,
+}
+
+impl Default for RateLimit {
+    fn default() -> Self {
+        Self::new(None, None, None)
+    }
+}
+
+impl RateLimit {
+    pub fn new(rate: Option<f64>, burst: Option<u32>, retries: Option<u32>) -> Self {
+        let rate = rate.filter(|rate| *rate > 0.0);
+        Self {
+            rate,
+            burst: burst
+                .or(rate.map(|rate| rate.ceil() as u32))
+                .unwrap_or(1)
+                .max(1),
+            retries: retries.unwrap_or(DEFAULT_RATE_LIMIT_RETRIES),
+        }
+    }
+
+    pub fn from_env() -> Self {
+        Self::new(
+            Environment::VaultRateLimit.parsed(),
+            Environment::VaultRateLimitBurst.parsed(),
+            Environment::VaultRateLimitRetries.parsed(),
+        )
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct VaultConfiguration {
-    pub credentials: Credentials,
+    pub credentials: Vec<Credentials>,
     pub address: String,
+    /// Addresses tried in order when the node at `address` is unreachable or sealed.
+    pub failover_addresses: Vec<String>,
+    pub timeouts: Timeouts,
+    pub rate_limit: RateLimit,
     pub transit_key: String,
     pub mount_path: String,
+    /// How often a canary encryption checks that the token can still encrypt, disabled when not set.
+    pub canary_interval: Option<Duration>,
 }
 
 impl Default for VaultConfiguration {
     fn default() -> Self {
-        Self {
-            credentials: Credentials::from_env(),
+        let configuration = Self {
+            credentials: Credentials::chain_from_env(),
             address: Environment::VaultAddress.or(DEFAULT_VAULT_ADDRESS),
+            failover_addresses: Environment::VaultFailoverAddresses
+                .get()
+                .map(|addresses| Self::parse_addresses(&addresses))
+                .unwrap_or_default(),
+            timeouts: Timeouts::from_env(),
+            rate_limit: RateLimit::from_env(),
             transit_key: Environment::VaultTransitKey.or(DEFAULT_VAULT_TRANSIT_KEY),
             mount_path: Environment::VaultTransitMount.or(DEFAULT_TRANSIT_MOUNT_PATH),
+            canary_interval: Environment::VaultCanaryInterval
+                .parsed()
+                .filter(|seconds| *seconds > 0)
+                .map(Duration::from_secs),
+        };
+        match AgentConfiguration::from_env() {
+            Some(agent) => configuration.with_agent(&agent),
+            None => configuration,
+        }
+    }
+}
+
+impl VaultConfiguration {
+    /// Routes requests through a Vault Agent listener, authenticating with its auto-auth token.
+    pub fn with_agent(self, agent: &AgentConfiguration) -> Self {
+        info!("Using Vault Agent listening at: {}", agent.address);
+        Self {
+            credentials: agent.credentials(),
+            address: agent.address.clone(),
+            failover_addresses: vec![],
+            ..self
         }
     }
+
+    /// The primary address followed by the failover addresses, in the order they are tried.
+    pub fn addresses(&self) -> Vec<String> {
+        std::iter::once(&self.address)
+            .chain(&self.failover_addresses)
+            .cloned()
+            .collect()
+    }
+
+    fn parse_addresses(addresses: &str) -> Vec<String> {
+        addresses
+            .split(',')
+            .map(str::trim)
+            .filter(|address| !address.is_empty())
+            .map(str::to_string)
+            .collect()
+    }
 }
 
 #[cfg(test)]
 mod vault_configuration {
     use super::*;
+    use crate::utilities::source::Source;
     use pretty_assertions::assert_eq;
 
     #[test]
@@ -34,11 +163,98 @@ mod vault_configuration {
         assert_eq!(
             VaultConfiguration::default(),
             VaultConfiguration {
-                credentials: Credentials::from_env(),
+                credentials: Credentials::chain_from_env(),
                 address: Environment::VaultAddress.or(DEFAULT_VAULT_ADDRESS),
+                failover_addresses: vec![],
+                timeouts: Timeouts::from_env(),
+                rate_limit: RateLimit::from_env(),
                 transit_key: Environment::VaultTransitKey.or(DEFAULT_VAULT_TRANSIT_KEY),
                 mount_path: Environment::VaultTransitMount.or(DEFAULT_TRANSIT_MOUNT_PATH),
+                canary_interval: None,
             }
         );
     }
+
+    #[test]
+    fn agent_mode_replaces_the_address_and_credentials() {
+        let agent = AgentConfiguration::new(
+            "http://127.0.0.1:8100".to_string(),
+            Some(Source::FilePath("/vault/sink/token".to_string())),
+        );
+        let configuration = VaultConfiguration {
+            credentials: vec![Credentials::None],
+            address: DEFAULT_VAULT_ADDRESS.to_string(),
+            failover_addresses: vec!["https://vault-dr:8200".to_string()],
+            timeouts: Timeouts::default(),
+            rate_limit: RateLimit::default(),
+            transit_key: DEFAULT_VAULT_TRANSIT_KEY.to_string(),
+            mount_path: DEFAULT_TRANSIT_MOUNT_PATH.to_string(),
+            canary_interval: None,
+        }
+        .with_agent(&agent);
+        assert_eq!(configuration.address, "http://127.0.0.1:8100");
+        assert_eq!(configuration.credentials, agent.credentials());
+        assert_eq!(configuration.failover_addresses, Vec::<String>::new());
+        assert_eq!(configuration.transit_key, DEFAULT_VAULT_TRANSIT_KEY);
+    }
+
+    #[test]
+    fn uses_default_timeouts_when_none_are_configured() {
+        assert_eq!(
+            Timeouts::new(None, Some(Duration::from_secs(2))),
+            Timeouts {
+                connect: DEFAULT_CONNECT_TIMEOUT,
+                request: Duration::from_secs(2),
+            }
+        );
+    }
+
+    #[test]
+    fn allows_a_burst_of_one_second_of_requests_by_default() {
+        assert_eq!(
+            RateLimit::new(Some(2.5), None, None),
+            RateLimit {
+                rate: Some(2.5),
+                burst: 3,
+                retries: DEFAULT_RATE_LIMIT_RETRIES,
+            }
+        );
+    }
+
+    #[test]
+    fn does_not_limit_requests_without_a_rate() {
+        assert_eq!(RateLimit::new(Some(0.0), Some(5), None).rate, None);
+    }
+
+    #[test]
+    fn parses_a_comma_separated_list_of_failover_addresses() {
+        assert_eq!(
+            VaultConfiguration::parse_addresses("https://vault-1:8200, https://vault-2:8200,,"),
+            vec![
+                "https://vault-1:8200".to_string(),
+                "https://vault-2:8200".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn tries_the_primary_address_first() {
+        let configuration = VaultConfiguration {
+            credentials: vec![],
+            address: "https://vault:8200".to_string(),
+            failover_addresses: vec!["https://vault-dr:8200".to_string()],
+            timeouts: Timeouts::default(),
+            rate_limit: RateLimit::default(),
+            transit_key: DEFAULT_VAULT_TRANSIT_KEY.to_string(),
+            mount_path: DEFAULT_TRANSIT_MOUNT_PATH.to_string(),
+            canary_interval: None,
+        };
+        assert_eq!(
+            configuration.addresses(),
+            vec![
+                "https://vault:8200".to_string(),
+                "https://vault-dr:8200".to_string()
+            ]
+        );
+    }
 }
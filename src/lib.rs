@@ -1,52 +1,141 @@
 extern crate core;
 
 use crate::configuration::ServerConfiguration;
+use crate::kms::key_management_service_client::KeyManagementServiceClient;
 use crate::kms::key_management_service_server::KeyManagementServiceServer;
-use crate::utilities::{socket::Socket, watcher};
+use crate::utilities::backoff::Backoff;
+use crate::utilities::heartbeat::{Heartbeat, Heartbeats};
+use crate::utilities::limiter::{Limiter, LimiterLayer};
+use crate::utilities::memory;
+use crate::utilities::metrics::Metrics;
+use crate::utilities::{socket::Socket, watcher, watcher::RefreshStatus};
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::RwLock;
 use tonic::transport::Server;
-use vaultrs::client::{VaultClient, VaultClientSettingsBuilder};
+use tracing::warn;
 
+pub mod audit;
 pub mod checks;
 pub mod configuration;
+pub mod telemetry;
 pub mod utilities;
 pub mod vault;
 pub mod kms {
     tonic::include_proto!("v2");
 }
 
+/// Time a liveness probe waits for the gRPC server to respond.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Sends a `Status` request over the KMS socket whenever the gRPC server has not answered a request for a heartbeat
+/// interval, so the server keeps beating from the request path while the API server is idle. The probe waits on the
+/// Vault client lock but is answered without calling Vault.
+async fn probe_while_idle(socket: &Socket, socket_path: &str, heartbeat: &Heartbeat) {
+    let mut ticks = tokio::time::interval(heartbeat.interval());
+    loop {
+        ticks.tick().await;
+        if heartbeat.since_last_beat() < heartbeat.interval() {
+            continue;
+        }
+        let probe = async {
+            let mut client = KeyManagementServiceClient::new(socket.connect(socket_path).await?);
+            let mut request = tonic::Request::new(kms::StatusRequest {});
+            request.set_timeout(PROBE_TIMEOUT);
+            request.metadata_mut().insert(
+                vault::LIVENESS_PROBE,
+                tonic::metadata::MetadataValue::from_static("1"),
+            );
+            client.status(request).await?;
+            Ok::<(), Box<dyn std::error::Error + Send + Sync>>(())
+        };
+        if let Err(error) = probe.await {
+            warn!("Liveness probe over the KMS socket failed: {}", error);
+        }
+    }
+}
+
 pub async fn server(
     ServerConfiguration {
         socket: socket_config,
         tls: tls_config,
         vault: vault_config,
         health: health_config,
+        limits: limits_config,
+        audit: audit_config,
+        memory: memory_config,
     }: ServerConfiguration,
 ) -> Result<(), Box<dyn std::error::Error>> {
+    memory::protect(&memory_config)?;
     let socket = Socket::with_permissions(&socket_config.permissions);
     let stream = socket.listen(&socket_config.socket_path)?;
-    let settings = VaultClientSettingsBuilder::default()
-        .address(&vault_config.address)
-        .identity(tls_config.identity())
-        .ca_certs(tls_config.certs())
-        .build()?;
-    let client = Arc::new(RwLock::new(vault::Client::new(
-        VaultClient::new(settings).unwrap(),
+    let client = Arc::new(RwLock::new(vault::Cluster::connect(
         &vault_config,
-    )));
-    let vault_kms_server = vault::VaultKmsServer::new(client.clone());
-    vault_kms_server.initialize().await?;
+        &tls_config,
+    )?));
+    let vault_kms_server =
+        vault::VaultKmsServer::new(client.clone(), audit::Auditor::new(&audit_config)?);
+    let initialization = vault_kms_server.initialization();
+    let heartbeats = Arc::new(Heartbeats::new(health_config.liveness_threshold));
+    let grpc_heartbeat = Arc::new(heartbeats.register("grpc-server"));
+    let initialize =
+        vault_kms_server.initialize(Backoff::default(), heartbeats.register("initialization"));
+    let canary = vault_config
+        .canary_interval
+        .map(|interval| vault_kms_server.canary(interval, heartbeats.register("canary")));
+    let refresh_status = Arc::new(RefreshStatus::default());
+    let metrics = Arc::new(Metrics::default());
+    let limiter = LimiterLayer::new(
+        Limiter::new(&limits_config, metrics.clone()),
+        grpc_heartbeat.clone(),
+    );
     tokio::try_join!(
         async {
-            Server::builder()
-                .add_service(KeyManagementServiceServer::new(vault_kms_server))
-                .serve_with_incoming(stream)
-                .await
-                .map_err(|error| std::io::Error::other(error.to_string()))
+            tokio::select! {
+                result = Server::builder()
+                    .layer(limiter)
+                    .add_service(KeyManagementServiceServer::new(vault_kms_server))
+                    .serve_with_incoming(stream) => {
+                    result.map_err(|error| std::io::Error::other(error.to_string()))
+                }
+                _ = probe_while_idle(&socket, &socket_config.socket_path, &grpc_heartbeat) => Ok(()),
+            }
         },
-        checks::serve(&health_config.endpoint, &socket_config.socket_path),
-        watcher::watch_credentials(vault_config.credentials, client)
+        checks::serve(
+            &health_config,
+            &socket_config.socket_path,
+            refresh_status.clone(),
+            initialization,
+            metrics,
+            heartbeats.clone()
+        ),
+        async {
+            // Credentials are watched once initialization has authenticated, ready to schedule token refreshes.
+            initialize.await;
+            if health_config.selftest_on_startup {
+                let selftest = checks::selftest::run(&socket, &socket_config.socket_path).await;
+                if let Some(step) = selftest.steps.last().filter(|_| !selftest.is_ok()) {
+                    return Err(std::io::Error::other(format!(
+                        "Self-test failed at the {} step: {}",
+                        step.name,
+                        step.reason.clone().unwrap_or_default()
+                    )));
+                }
+            }
+            watcher::watch_credentials(
+                vault_config.credentials,
+                client.clone(),
+                refresh_status,
+                heartbeats.register("credentials-watcher"),
+            )
+            .await
+        },
+        async {
+            if let Some(canary) = canary {
+                canary.await;
+            }
+            Ok(())
+        }
     )?;
     Ok(())
 }
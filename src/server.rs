@@ -1,9 +1,12 @@
 extern crate lib;
 
+use lib::telemetry;
 use lib::utilities::logging;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    logging::initialize();
-    lib::server(lib::configuration::ServerConfiguration::default()).await
+    logging::initialize()?;
+    let result = lib::server(lib::configuration::ServerConfiguration::default()).await;
+    telemetry::shutdown();
+    result
 }
@@ -0,0 +1,185 @@
+use crate::configuration::telemetry::TelemetryConfiguration;
+use opentelemetry::propagation::TextMapPropagator;
+use opentelemetry::trace::TracerProvider;
+use opentelemetry::KeyValue;
+use opentelemetry_otlp::{Protocol, SpanExporter, WithExportConfig};
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::{Sampler, SdkTracer, SdkTracerProvider, TracerProviderBuilder};
+use opentelemetry_sdk::Resource;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use std::time::Duration;
+use tracing::{warn, Subscriber};
+use tracing_opentelemetry::{OpenTelemetryLayer, OpenTelemetrySpanExt};
+use tracing_subscriber::registry::LookupSpan;
+
+/// Header carrying the trace context between services, see https://www.w3.org/TR/trace-context/
+pub const TRACEPARENT: &str = "traceparent";
+const TRACES_PATH: &str = "v1/traces";
+const EXPORT_TIMEOUT: Duration = Duration::from_secs(10);
+
+static PROVIDER: OnceLock<SdkTracerProvider> = OnceLock::new();
+
+/// Samples the configured share of new traces, traces started by the caller follow the caller's decision.
+pub fn tracer_provider(config: &TelemetryConfiguration) -> TracerProviderBuilder {
+    SdkTracerProvider::builder()
+        .with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(
+            config.sampling_ratio,
+        ))))
+        .with_resource(
+            Resource::builder()
+                .with_service_name(config.service_name.clone())
+                .with_attribute(KeyValue::new("service.version", env!("CARGO_PKG_VERSION")))
+                .build(),
+        )
+}
+
+/// Layer recording spans with the tracers of `provider`.
+pub fn tracing_layer<S>(provider: &SdkTracerProvider) -> OpenTelemetryLayer<S, SdkTracer>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    tracing_opentelemetry::layer().with_tracer(provider.tracer(env!("CARGO_PKG_NAME")))
+}
+
+/// Builds a layer exporting spans in batches to the configured collector over OTLP/HTTP (JSON).
+pub fn layer<S>(config: &TelemetryConfiguration) -> Option<OpenTelemetryLayer<S, SdkTracer>>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    let endpoint = config.endpoint.as_ref()?;
+    let exporter = SpanExporter::builder()
+        .with_http()
+        .with_protocol(Protocol::HttpJson)
+        .with_endpoint(format!(
+            "{}/{}",
+            endpoint.trim_end_matches('/'),
+            TRACES_PATH
+        ))
+        .with_timeout(EXPORT_TIMEOUT)
+        .build();
+    match exporter {
+        Ok(exporter) => {
+            let provider = PROVIDER.get_or_init(|| {
+                tracer_provider(config)
+                    .with_batch_exporter(exporter)
+                    .build()
+            });
+            Some(tracing_layer(provider))
+        }
+        Err(error) => {
+            warn!("Unable to export traces to {}: {}", endpoint, error);
+            None
+        }
+    }
+}
+
+/// Exports the spans still waiting in the batch, before the process exits.
+pub fn shutdown() {
+    if let Some(Err(error)) = PROVIDER.get().map(SdkTracerProvider::shutdown) {
+        warn!("Unable to export the remaining spans: {}", error);
+    }
+}
+
+/// Continues the trace of a `traceparent` header in `span`, which must not have been entered yet.
+pub fn continue_trace(span: &tracing::Span, traceparent: Option<&str>) {
+    if let Some(traceparent) = traceparent {
+        let carrier = HashMap::from([(TRACEPARENT.to_string(), traceparent.to_string())]);
+        let _ = span.set_parent(TraceContextPropagator::new().extract(&carrier));
+    }
+}
+
+/// `traceparent` header value continuing the current trace, if spans are being traced.
+pub fn traceparent() -> Option<String> {
+    let mut carrier = HashMap::new();
+    TraceContextPropagator::new().inject_context(&tracing::Span::current().context(), &mut carrier);
+    carrier.remove(TRACEPARENT)
+}
+
+#[cfg(test)]
+mod telemetry {
+    use super::*;
+    use opentelemetry::trace::SpanKind;
+    use opentelemetry_sdk::trace::{InMemorySpanExporter, SpanData};
+    use pretty_assertions::assert_eq;
+    use tracing::info_span;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    const CALLER_TRACEPARENT: &str = "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01";
+
+    fn traced(sampling_ratio: f64, spans: impl FnOnce()) -> Vec<SpanData> {
+        let exporter = InMemorySpanExporter::default();
+        let provider = tracer_provider(&TelemetryConfiguration::new(
+            None,
+            Some(sampling_ratio),
+            None,
+        ))
+        .with_simple_exporter(exporter.clone())
+        .build();
+        let subscriber = tracing_subscriber::registry().with(tracing_layer(&provider));
+        tracing::subscriber::with_default(subscriber, spans);
+        exporter.get_finished_spans().unwrap()
+    }
+
+    #[test]
+    fn exports_spans_within_the_same_trace() {
+        let spans = traced(1.0, || {
+            let parent = info_span!("encrypt", uid = "4b9a", otel.kind = "server");
+            let _entered = parent.enter();
+            info_span!("request_encryption", attempt = 1).in_scope(|| {});
+        });
+        assert_eq!(spans.len(), 2);
+        let (child, parent) = (&spans[0], &spans[1]);
+        assert_eq!(child.name, "request_encryption");
+        assert_eq!(
+            child.span_context.trace_id(),
+            parent.span_context.trace_id()
+        );
+        assert_eq!(child.parent_span_id, parent.span_context.span_id());
+        assert_eq!(parent.span_kind, SpanKind::Server);
+        assert_eq!(child.span_kind, SpanKind::Internal);
+        assert!(parent.attributes.contains(&KeyValue::new("uid", "4b9a")));
+    }
+
+    #[test]
+    fn continues_a_remote_trace() {
+        let spans = traced(0.0, || {
+            let span = info_span!("status");
+            continue_trace(&span, Some(CALLER_TRACEPARENT));
+            span.in_scope(|| {
+                let traceparent = traceparent().unwrap();
+                assert!(traceparent.starts_with("00-0af7651916cd43dd8448eb211c80319c-"));
+                assert_ne!(traceparent, CALLER_TRACEPARENT);
+            });
+        });
+        assert_eq!(spans.len(), 1);
+        assert_eq!(spans[0].parent_span_id.to_string(), "b7ad6b7169203331");
+    }
+
+    #[test]
+    fn does_not_export_unsampled_traces() {
+        let spans = traced(0.0, || {
+            info_span!("status").in_scope(|| {
+                assert!(traceparent().is_some_and(|traceparent| traceparent.ends_with("-00")));
+            });
+        });
+        assert!(spans.is_empty());
+    }
+
+    #[test]
+    fn has_no_context_outside_of_a_span() {
+        let spans = traced(1.0, || assert_eq!(traceparent(), None));
+        assert!(spans.is_empty());
+    }
+
+    #[test]
+    fn ignores_invalid_trace_context() {
+        let spans = traced(1.0, || {
+            let span = info_span!("status");
+            continue_trace(&span, Some("00-xyz-b7ad6b7169203331-01"));
+            span.in_scope(|| {});
+        });
+        assert_eq!(spans.len(), 1);
+        assert_eq!(spans[0].parent_span_id.to_string(), "0000000000000000");
+    }
+}
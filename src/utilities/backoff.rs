@@ -0,0 +1,75 @@
+use std::time::Duration;
+
+const DEFAULT_INITIAL_DELAY: Duration = Duration::from_secs(1);
+const DEFAULT_MAXIMUM_DELAY: Duration = Duration::from_secs(60);
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Backoff {
+    initial: Duration,
+    maximum: Duration,
+    current: Duration,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self::new(DEFAULT_INITIAL_DELAY, DEFAULT_MAXIMUM_DELAY)
+    }
+}
+
+impl Backoff {
+    pub fn new(initial: Duration, maximum: Duration) -> Self {
+        Self {
+            initial,
+            maximum,
+            current: initial,
+        }
+    }
+
+    pub fn next_delay(&mut self) -> Duration {
+        let delay = self.current;
+        self.current = (self.current * 2).min(self.maximum);
+        delay
+    }
+
+    pub fn reset(&mut self) {
+        self.current = self.initial;
+    }
+}
+
+#[cfg(test)]
+mod backoff {
+    use super::Backoff;
+    use pretty_assertions::assert_eq;
+    use std::time::Duration;
+
+    #[test]
+    fn doubles_the_delay_on_each_attempt() {
+        let mut backoff = Backoff::new(Duration::from_secs(1), Duration::from_secs(60));
+        let delays: Vec<Duration> = (0..4).map(|_| backoff.next_delay()).collect();
+        assert_eq!(
+            delays,
+            vec![1, 2, 4, 8]
+                .into_iter()
+                .map(Duration::from_secs)
+                .collect::<Vec<Duration>>()
+        );
+    }
+
+    #[test]
+    fn does_not_exceed_the_maximum_delay() {
+        let mut backoff = Backoff::new(Duration::from_secs(1), Duration::from_secs(3));
+        (0..5).for_each(|_| {
+            backoff.next_delay();
+        });
+        assert_eq!(backoff.next_delay(), Duration::from_secs(3));
+    }
+
+    #[test]
+    fn resets_to_the_initial_delay() {
+        let mut backoff = Backoff::new(Duration::from_secs(1), Duration::from_secs(60));
+        backoff.next_delay();
+        backoff.next_delay();
+        backoff.reset();
+        assert_eq!(backoff.next_delay(), Duration::from_secs(1));
+    }
+}
@@ -2,38 +2,125 @@ use crate::utilities::source::Source;
 use convert_case::{Case, Casing};
 use std::cmp::PartialEq;
 use std::fmt::{Display, Formatter};
+use std::str::FromStr;
 use strum::IntoEnumIterator;
 use strum_macros::EnumIter;
-use tracing::{debug, instrument};
+use tracing::{debug, instrument, warn};
 
 #[derive(Debug, Clone, Copy, PartialEq, EnumIter)]
 pub enum Environment {
+    AuditLog,
+    AuditLogMaxFiles,
+    AuditLogMaxSize,
+    AwsAccessKeyId,
+    AwsRegion,
+    AwsRoleArn,
+    AwsRoleSessionName,
+    AwsSecretAccessKey,
+    AwsSessionToken,
+    AwsWebIdentityTokenFile,
+    VaultAwsHeaderValue,
+    VaultAwsHeaderValuePath,
+    VaultAwsRegion,
+    VaultAwsRole,
+    VaultAwsRolePath,
+    VaultAwsStsEndpoint,
+    VaultAzureMetadataEndpoint,
+    VaultAzureResource,
+    VaultAzureRole,
+    VaultAzureRolePath,
     VaultCertificateName,
+    VaultCertificateNamePath,
+    VaultGcpMetadataEndpoint,
+    VaultGcpRole,
+    VaultGcpRolePath,
+    VaultGcpServiceAccountKey,
+    VaultGcpServiceAccountKeyPath,
     VaultJwt,
     VaultJwtPath,
+    VaultJwtAlgorithm,
+    VaultJwtAudience,
+    VaultJwtIssuer,
+    VaultJwtPrivateKey,
+    VaultJwtPrivateKeyPath,
     VaultJwtRole,
+    VaultJwtRolePath,
+    VaultJwtSubject,
+    VaultJwtTtl,
     VaultAuthMount,
+    VaultAuthMountPath,
+    VaultApproleAuthMount,
+    VaultApproleAuthMountPath,
+    VaultAwsAuthMount,
+    VaultAwsAuthMountPath,
+    VaultAzureAuthMount,
+    VaultAzureAuthMountPath,
+    VaultCertificateAuthMount,
+    VaultCertificateAuthMountPath,
+    VaultGcpAuthMount,
+    VaultGcpAuthMountPath,
+    VaultJwtAuthMount,
+    VaultJwtAuthMountPath,
+    VaultKubernetesAuthMount,
+    VaultKubernetesAuthMountPath,
+    VaultUserpassAuthMount,
+    VaultUserpassAuthMountPath,
+    VaultAuthMethods,
     VaultToken,
     VaultTokenPath,
     VaultKubernetesJwt,
     VaultKubernetesJwtPath,
     VaultKubernetesRole,
+    VaultKubernetesRolePath,
     VaultPassword,
     VaultPasswordPath,
     VaultUser,
+    VaultUserPath,
     VaultRoleId,
+    VaultRoleIdPath,
     VaultSecretId,
     VaultSecretIdPath,
+    VaultSecretIdWrapped,
+    DisableCoreDumps,
     HttpAddress,
+    HttpAdminToken,
+    HttpAdminTokenPath,
+    HttpLivenessThreshold,
+    HttpTlsCert,
+    HttpTlsClientCa,
+    HttpTlsKey,
+    LockMemory,
+    MaxInFlightRequests,
+    MaxInFlightEncryptRequests,
+    MaxInFlightDecryptRequests,
+    MaxInFlightStatusRequests,
+    MaxQueuedRequests,
     LogLevel,
     LogFormat,
+    LogFileMaxFiles,
+    LogFileMaxSize,
+    LogFileRotation,
+    LogOutput,
+    OtelExporterOtlpEndpoint,
+    OtelServiceName,
+    OtelTracesSamplerArg,
+    SelftestOnStartup,
     SocketPath,
     SocketPermissions,
+    VaultCanaryInterval,
     VaultCaPath,
     VaultCaCert,
     VaultClientCert,
     VaultClientKey,
     VaultAddress,
+    VaultFailoverAddresses,
+    VaultConnectTimeout,
+    VaultRequestTimeout,
+    VaultRateLimit,
+    VaultRateLimitBurst,
+    VaultRateLimitRetries,
+    VaultAgentAddress,
+    VaultAgentSinkPath,
     VaultTransitKey,
     VaultTransitMount,
     Unknown,
@@ -109,6 +196,21 @@ impl Environment {
         self.get().unwrap_or(default.to_string())
     }
 
+    /// Parses the variable into `T`, ignoring it with a warning if it cannot be parsed.
+    #[instrument]
+    pub fn parsed<T: FromStr>(&self) -> Option<T> {
+        self.get().and_then(|value| {
+            let parsed = value.parse::<T>().ok();
+            if parsed.is_none() {
+                warn!(
+                    "Ignoring environment variable \"{}\", unable to parse: \"{}\"",
+                    self, value
+                );
+            }
+            parsed
+        })
+    }
+
     #[instrument]
     pub fn source(&self) -> Option<Source> {
         if self == &Self::Unknown {
@@ -292,6 +394,27 @@ mod environment {
         }
     }
 
+    mod parsed {
+        use super::Environment;
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn parses_the_value_of_an_environment_variable() {
+            unsafe {
+                std::env::set_var(Environment::VaultJwtTtl.to_string(), "60");
+            }
+            assert_eq!(Environment::VaultJwtTtl.parsed::<u64>(), Some(60));
+        }
+
+        #[test]
+        fn returns_none_if_the_value_cannot_be_parsed() {
+            unsafe {
+                std::env::set_var(Environment::VaultJwtAlgorithm.to_string(), "not-a-number");
+            }
+            assert_eq!(Environment::VaultJwtAlgorithm.parsed::<u64>(), None);
+        }
+    }
+
     mod source {
         use super::Environment;
         use crate::utilities::source::Source;
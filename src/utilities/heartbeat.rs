@@ -0,0 +1,207 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Progress of a task, shared between the task and the registry.
+struct Pulse {
+    name: &'static str,
+    /// Milliseconds between the registry being created and the last beat.
+    last: AtomicU64,
+    stopped: AtomicBool,
+    completed: AtomicBool,
+}
+
+/// Health of a registered task, as of when the registry was inspected.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TaskStatus {
+    /// The task has beaten within the threshold, or completed its work.
+    Alive,
+    /// The task has not beaten for longer than the threshold.
+    Stalled(Duration),
+    /// The task has returned, panicked or been cancelled without completing.
+    Stopped,
+}
+
+/// Registry of the critical tasks of the provider, which report progress by beating at least every
+/// [`Heartbeat::interval`]. A task that stops beating, ex: waiting on a deadlocked lock or on a stuck runtime,
+/// fails liveness once the threshold has passed.
+pub struct Heartbeats {
+    started: Instant,
+    threshold: Duration,
+    pulses: Mutex<Vec<Arc<Pulse>>>,
+}
+
+/// Handle held by a registered task, the task is considered stopped once it has been dropped.
+pub struct Heartbeat {
+    started: Instant,
+    interval: Duration,
+    pulse: Arc<Pulse>,
+}
+
+impl Heartbeats {
+    pub fn new(threshold: Duration) -> Self {
+        Self {
+            started: Instant::now(),
+            threshold,
+            pulses: Mutex::new(vec![]),
+        }
+    }
+
+    pub fn register(&self, name: &'static str) -> Heartbeat {
+        let pulse = Arc::new(Pulse {
+            name,
+            last: AtomicU64::new(elapsed(self.started, Instant::now())),
+            stopped: AtomicBool::new(false),
+            completed: AtomicBool::new(false),
+        });
+        self.pulses
+            .lock()
+            .expect("Heartbeat registry lock poisoned")
+            .push(pulse.clone());
+        Heartbeat {
+            started: self.started,
+            // Leaves room for a couple of late beats before the threshold.
+            interval: (self.threshold / 3).max(Duration::from_millis(1)),
+            pulse,
+        }
+    }
+
+    /// Status of each registered task, in the order they were registered.
+    pub fn statuses(&self) -> Vec<(&'static str, TaskStatus)> {
+        self.statuses_at(Instant::now())
+    }
+
+    fn statuses_at(&self, now: Instant) -> Vec<(&'static str, TaskStatus)> {
+        let now = elapsed(self.started, now);
+        self.pulses
+            .lock()
+            .expect("Heartbeat registry lock poisoned")
+            .iter()
+            .map(|pulse| {
+                let since =
+                    Duration::from_millis(now.saturating_sub(pulse.last.load(Ordering::Relaxed)));
+                let status = if pulse.completed.load(Ordering::Relaxed) {
+                    TaskStatus::Alive
+                } else if pulse.stopped.load(Ordering::Relaxed) {
+                    TaskStatus::Stopped
+                } else if since > self.threshold {
+                    TaskStatus::Stalled(since)
+                } else {
+                    TaskStatus::Alive
+                };
+                (pulse.name, status)
+            })
+            .collect()
+    }
+}
+
+impl Heartbeat {
+    /// How often the task should beat while it is idle.
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Reports that the task is making progress.
+    pub fn beat(&self) {
+        self.pulse
+            .last
+            .store(elapsed(self.started, Instant::now()), Ordering::Relaxed);
+    }
+
+    /// Time since the task last beat.
+    pub fn since_last_beat(&self) -> Duration {
+        Duration::from_millis(
+            elapsed(self.started, Instant::now())
+                .saturating_sub(self.pulse.last.load(Ordering::Relaxed)),
+        )
+    }
+
+    /// Waits for `duration`, beating every interval, for tasks that idle longer than the interval between units of
+    /// work.
+    pub async fn idle(&self, duration: Duration) {
+        let deadline = tokio::time::Instant::now() + duration;
+        loop {
+            self.beat();
+            let remaining = deadline.saturating_duration_since(tokio::time::Instant::now());
+            if remaining.is_zero() {
+                return;
+            }
+            tokio::time::sleep(remaining.min(self.interval)).await;
+        }
+    }
+
+    /// Reports that the task has nothing left to do, so it no longer needs to beat.
+    pub fn complete(self) {
+        self.pulse.completed.store(true, Ordering::Relaxed);
+    }
+}
+
+impl Drop for Heartbeat {
+    fn drop(&mut self) {
+        self.pulse.stopped.store(true, Ordering::Relaxed);
+    }
+}
+
+fn elapsed(started: Instant, now: Instant) -> u64 {
+    now.saturating_duration_since(started).as_millis() as u64
+}
+
+#[cfg(test)]
+mod heartbeat {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn reports_tasks_that_beat_within_the_threshold_as_alive() {
+        let heartbeats = Heartbeats::new(Duration::from_secs(10));
+        let heartbeat = heartbeats.register("task");
+        heartbeat.beat();
+        assert_eq!(
+            heartbeats.statuses_at(Instant::now() + Duration::from_secs(5)),
+            vec![("task", TaskStatus::Alive)]
+        );
+    }
+
+    #[test]
+    fn reports_tasks_that_have_not_beaten_within_the_threshold_as_stalled() {
+        let heartbeats = Heartbeats::new(Duration::from_secs(10));
+        let _heartbeat = heartbeats.register("task");
+        let statuses = heartbeats.statuses_at(Instant::now() + Duration::from_secs(11));
+        assert!(
+            matches!(statuses[0], ("task", TaskStatus::Stalled(since)) if since >= Duration::from_secs(11)),
+            "{:?}",
+            statuses
+        );
+    }
+
+    #[test]
+    fn reports_dropped_tasks_as_stopped() {
+        let heartbeats = Heartbeats::new(Duration::from_secs(10));
+        drop(heartbeats.register("task"));
+        assert_eq!(heartbeats.statuses(), vec![("task", TaskStatus::Stopped)]);
+    }
+
+    #[tokio::test]
+    async fn beats_while_idling() {
+        let heartbeats = Heartbeats::new(Duration::from_millis(150));
+        let heartbeat = heartbeats.register("task");
+        let idle = tokio::spawn(async move {
+            heartbeat.idle(Duration::from_millis(400)).await;
+            heartbeat
+        });
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert_eq!(heartbeats.statuses(), vec![("task", TaskStatus::Alive)]);
+        let heartbeat = idle.await.unwrap();
+        assert!(heartbeat.since_last_beat() < heartbeat.interval());
+    }
+
+    #[test]
+    fn reports_completed_tasks_as_alive() {
+        let heartbeats = Heartbeats::new(Duration::from_secs(10));
+        heartbeats.register("task").complete();
+        assert_eq!(
+            heartbeats.statuses_at(Instant::now() + Duration::from_secs(60)),
+            vec![("task", TaskStatus::Alive)]
+        );
+    }
+}
@@ -0,0 +1,361 @@
+use crate::configuration::limits::LimitsConfiguration;
+use crate::utilities::heartbeat::Heartbeat;
+use crate::utilities::metrics::Metrics;
+use futures::future::BoxFuture;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tonic::Status;
+use tower::{Layer, Service};
+use tracing::{debug, warn};
+
+const METHODS: [&str; 3] = ["Encrypt", "Decrypt", "Status"];
+/// The API server decides whether the plugin is healthy from `Status`, so it is never queued behind or rejected
+/// along with `Encrypt` and `Decrypt` requests.
+const STATUS: &str = "Status";
+
+/// Held for the duration of a request, releasing its concurrency slots when dropped.
+struct Permits {
+    _method: Option<OwnedSemaphorePermit>,
+    _global: Option<OwnedSemaphorePermit>,
+    metrics: Arc<Metrics>,
+}
+
+impl Drop for Permits {
+    fn drop(&mut self) {
+        self.metrics.finished();
+    }
+}
+
+/// Counts a request as queued until it is dropped, including when the caller stops waiting.
+struct Queued<'a> {
+    limiter: &'a Limiter,
+}
+
+impl Drop for Queued<'_> {
+    fn drop(&mut self) {
+        let queued = self.limiter.queued.fetch_sub(1, Ordering::SeqCst) - 1;
+        self.limiter.metrics.set_queued(queued);
+    }
+}
+
+/// Bounds the number of KMS requests handled at once, queueing a limited number of requests
+/// and rejecting the rest with `ResourceExhausted` so Vault isn't overwhelmed.
+pub struct Limiter {
+    global: Arc<Semaphore>,
+    methods: HashMap<&'static str, Arc<Semaphore>>,
+    max_queued: usize,
+    queued: AtomicUsize,
+    metrics: Arc<Metrics>,
+}
+
+impl Limiter {
+    pub fn new(config: &LimitsConfiguration, metrics: Arc<Metrics>) -> Self {
+        Self {
+            global: Arc::new(Semaphore::new(config.max_in_flight)),
+            methods: METHODS
+                .into_iter()
+                .filter_map(|method| {
+                    config
+                        .method_limit(method)
+                        .map(|limit| (method, Arc::new(Semaphore::new(limit))))
+                })
+                .collect(),
+            max_queued: config.max_queued,
+            queued: AtomicUsize::new(0),
+            metrics,
+        }
+    }
+
+    fn permits(
+        &self,
+        method: Option<OwnedSemaphorePermit>,
+        global: Option<OwnedSemaphorePermit>,
+    ) -> Permits {
+        self.metrics.started();
+        Permits {
+            _method: method,
+            _global: global,
+            metrics: self.metrics.clone(),
+        }
+    }
+
+    fn try_acquire(&self, method: Option<&Arc<Semaphore>>) -> Option<Permits> {
+        let method = match method {
+            Some(semaphore) => Some(semaphore.clone().try_acquire_owned().ok()?),
+            None => None,
+        };
+        let global = self.global.clone().try_acquire_owned().ok()?;
+        Some(self.permits(method, Some(global)))
+    }
+
+    /// `Status` only waits for its own limit, if one is set, outside of the shared limit and queue.
+    async fn acquire_status(&self) -> Result<Permits, Status> {
+        let permit = match self.methods.get(STATUS) {
+            Some(semaphore) => Some(
+                semaphore
+                    .clone()
+                    .acquire_owned()
+                    .await
+                    .map_err(|_| Status::unavailable("The server is shutting down"))?,
+            ),
+            None => None,
+        };
+        Ok(self.permits(permit, None))
+    }
+
+    /// Waits for a concurrency slot for `method`, unless the queue is already full.
+    async fn acquire(&self, method: &str) -> Result<Permits, Status> {
+        if method == STATUS {
+            return self.acquire_status().await;
+        }
+        let semaphore = self.methods.get(method);
+        if let Some(permits) = self.try_acquire(semaphore) {
+            return Ok(permits);
+        }
+        let queued = self.queued.fetch_add(1, Ordering::SeqCst);
+        if queued >= self.max_queued {
+            self.queued.fetch_sub(1, Ordering::SeqCst);
+            self.metrics.rejected(method);
+            warn!(
+                "Rejecting {} request, {} request(s) are already queued",
+                method, queued
+            );
+            return Err(Status::resource_exhausted(
+                "Too many concurrent requests, try again later",
+            ));
+        }
+        let position = Queued { limiter: self };
+        self.metrics.set_queued(queued + 1);
+        debug!("Queued {} request behind {} other(s)", method, queued);
+        let method_permit = match semaphore {
+            Some(semaphore) => Some(semaphore.clone().acquire_owned().await),
+            None => None,
+        }
+        .transpose();
+        let global_permit = self.global.clone().acquire_owned().await;
+        drop(position);
+        match (method_permit, global_permit) {
+            (Ok(method), Ok(global)) => Ok(self.permits(method, Some(global))),
+            _ => Err(Status::unavailable("The server is shutting down")),
+        }
+    }
+}
+
+/// Applies a [Limiter] to the gRPC server, see [tonic::transport::Server::layer]. Every request answered by the
+/// server beats `heartbeat`, so liveness follows whether requests are still being served. Rejected requests do not
+/// beat, as they are answered without reaching the server.
+#[derive(Clone)]
+pub struct LimiterLayer {
+    limiter: Arc<Limiter>,
+    heartbeat: Arc<Heartbeat>,
+}
+
+impl LimiterLayer {
+    pub fn new(limiter: Limiter, heartbeat: Arc<Heartbeat>) -> Self {
+        Self {
+            limiter: Arc::new(limiter),
+            heartbeat,
+        }
+    }
+}
+
+impl<S> Layer<S> for LimiterLayer {
+    type Service = LimiterService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        LimiterService {
+            inner,
+            limiter: self.limiter.clone(),
+            heartbeat: self.heartbeat.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct LimiterService<S> {
+    inner: S,
+    limiter: Arc<Limiter>,
+    heartbeat: Arc<Heartbeat>,
+}
+
+impl<S, RequestBody, ResponseBody> Service<http::Request<RequestBody>> for LimiterService<S>
+where
+    S: Service<http::Request<RequestBody>, Response = http::Response<ResponseBody>>
+        + Clone
+        + Send
+        + 'static,
+    S::Future: Send,
+    RequestBody: Send + 'static,
+    ResponseBody: Default,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, context: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(context)
+    }
+
+    fn call(&mut self, request: http::Request<RequestBody>) -> Self::Future {
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let (limiter, heartbeat) = (self.limiter.clone(), self.heartbeat.clone());
+        let method = request
+            .uri()
+            .path()
+            .rsplit('/')
+            .next()
+            .unwrap_or_default()
+            .to_string();
+        Box::pin(async move {
+            match limiter.acquire(&method).await {
+                Ok(permits) => {
+                    let response = inner.call(request).await;
+                    drop(permits);
+                    heartbeat.beat();
+                    response
+                }
+                Err(status) => Ok(status.into_http()),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod limiter {
+    use super::*;
+    use crate::utilities::heartbeat::Heartbeats;
+    use pretty_assertions::assert_eq;
+    use std::time::Duration;
+    use tonic::Code;
+
+    fn limiter(
+        max_in_flight: usize,
+        max_queued: usize,
+        max_in_flight_encrypt: Option<usize>,
+    ) -> Limiter {
+        Limiter::new(
+            &LimitsConfiguration::new(
+                Some(max_in_flight),
+                Some(max_queued),
+                max_in_flight_encrypt,
+                None,
+                None,
+            ),
+            Arc::new(Metrics::default()),
+        )
+    }
+
+    #[tokio::test]
+    async fn rejects_requests_when_the_queue_is_full() {
+        let limiter = limiter(1, 0, None);
+        let _permits = limiter.acquire("Encrypt").await.unwrap();
+        let rejected = limiter.acquire("Decrypt").await.err().unwrap();
+        assert_eq!(rejected.code(), Code::ResourceExhausted);
+        assert_eq!(limiter.metrics.rejections("Decrypt"), 1);
+    }
+
+    #[tokio::test]
+    async fn queues_requests_until_a_slot_is_released() {
+        let limiter = Arc::new(limiter(1, 1, None));
+        let permits = limiter.acquire("Encrypt").await.unwrap();
+        let queued = tokio::spawn({
+            let limiter = limiter.clone();
+            async move { limiter.acquire("Encrypt").await.is_ok() }
+        });
+        while limiter.metrics.queued() == 0 {
+            tokio::task::yield_now().await;
+        }
+        assert_eq!(limiter.metrics.in_flight(), 1);
+        drop(permits);
+        assert!(queued.await.unwrap());
+        assert_eq!(limiter.metrics.queued(), 0);
+        assert_eq!(limiter.metrics.in_flight(), 0);
+    }
+
+    #[tokio::test]
+    async fn limits_each_method_separately() {
+        let limiter = limiter(2, 0, Some(1));
+        let _encrypt = limiter.acquire("Encrypt").await.unwrap();
+        assert!(limiter.acquire("Encrypt").await.is_err());
+        assert!(limiter.acquire("Decrypt").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn responds_with_resource_exhausted_when_a_request_is_rejected() {
+        let heartbeats = Heartbeats::new(Duration::from_secs(60));
+        let layer = LimiterLayer::new(
+            limiter(1, 0, None),
+            Arc::new(heartbeats.register("grpc-server")),
+        );
+        let _permits = layer.limiter.acquire("Decrypt").await.unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let mut service = layer.layer(tower::service_fn(|_: http::Request<()>| async {
+            Ok::<_, std::convert::Infallible>(http::Response::new(tonic::body::Body::default()))
+        }));
+        let response = service
+            .call(
+                http::Request::builder()
+                    .uri("/v2.KeyManagementService/Encrypt")
+                    .body(())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            Status::from_header_map(response.headers()).unwrap().code(),
+            Code::ResourceExhausted
+        );
+        assert_eq!(layer.limiter.metrics.rejections("Encrypt"), 1);
+        assert!(layer.heartbeat.since_last_beat() >= Duration::from_millis(50));
+    }
+
+    #[tokio::test]
+    async fn beats_once_a_request_has_been_answered() {
+        let heartbeats = Heartbeats::new(Duration::from_secs(60));
+        let layer = LimiterLayer::new(
+            limiter(1, 0, None),
+            Arc::new(heartbeats.register("grpc-server")),
+        );
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let mut service = layer.layer(tower::service_fn(|_: http::Request<()>| async {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            Ok::<_, std::convert::Infallible>(http::Response::new(tonic::body::Body::default()))
+        }));
+        let response = service.call(
+            http::Request::builder()
+                .uri("/v2.KeyManagementService/Status")
+                .body(())
+                .unwrap(),
+        );
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert!(layer.heartbeat.since_last_beat() >= Duration::from_millis(50));
+        response.await.unwrap();
+        assert!(layer.heartbeat.since_last_beat() < Duration::from_millis(50));
+    }
+
+    #[tokio::test]
+    async fn never_queues_or_rejects_status_requests() {
+        let limiter = limiter(1, 0, None);
+        let _permits = limiter.acquire("Encrypt").await.unwrap();
+        assert!(limiter.acquire("Decrypt").await.is_err());
+        let status =
+            tokio::time::timeout(Duration::from_millis(100), limiter.acquire("Status")).await;
+        assert!(matches!(status, Ok(Ok(_))));
+        assert_eq!(limiter.metrics.rejections("Status"), 0);
+    }
+
+    #[tokio::test]
+    async fn leaves_the_queue_when_a_caller_stops_waiting() {
+        let limiter = limiter(1, 1, None);
+        let _permits = limiter.acquire("Encrypt").await.unwrap();
+        let abandoned =
+            tokio::time::timeout(Duration::from_millis(10), limiter.acquire("Encrypt")).await;
+        assert!(abandoned.is_err());
+        assert_eq!(limiter.metrics.queued(), 0);
+        assert_eq!(limiter.queued.load(Ordering::SeqCst), 0);
+    }
+}
@@ -0,0 +1,198 @@
+use std::io::Write;
+use std::os::unix::net::UnixDatagram;
+use std::path::Path;
+use tracing::{Level, Metadata};
+use tracing_subscriber::fmt::MakeWriter;
+
+const IDENTIFIER: &str = env!("CARGO_PKG_NAME");
+/// Syslog facility of system daemons.
+const FACILITY_DAEMON: u8 = 3;
+
+/// Message format understood by the receiving daemon.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Protocol {
+    /// Fields of the journald native protocol, see https://systemd.io/JOURNAL_NATIVE_PROTOCOL/
+    Journald,
+    /// Syslog messages as sent by `syslog(3)`, the receiving daemon adds the time and host.
+    Syslog,
+}
+
+/// Sends each formatted event as a datagram to the unix socket of journald or a syslog daemon.
+pub struct LogSocket {
+    socket: UnixDatagram,
+    protocol: Protocol,
+}
+
+fn severity(level: &Level) -> u8 {
+    match *level {
+        Level::ERROR => 3,
+        Level::WARN => 4,
+        Level::INFO => 6,
+        _ => 7,
+    }
+}
+
+/// Appends a journald field, values containing newlines are sent with their length instead.
+fn field(message: &mut Vec<u8>, name: &str, value: &[u8]) {
+    message.extend_from_slice(name.as_bytes());
+    if value.contains(&b'\n') {
+        message.push(b'\n');
+        message.extend_from_slice(&(value.len() as u64).to_le_bytes());
+    } else {
+        message.push(b'=');
+    }
+    message.extend_from_slice(value);
+    message.push(b'\n');
+}
+
+impl LogSocket {
+    pub fn connect(path: &Path, protocol: Protocol) -> std::io::Result<Self> {
+        let socket = UnixDatagram::unbound()?;
+        socket.connect(path)?;
+        Ok(Self { socket, protocol })
+    }
+
+    fn encode(&self, source: Option<&Source>, formatted: &[u8]) -> Vec<u8> {
+        let formatted = formatted.strip_suffix(b"\n").unwrap_or(formatted);
+        let severity = severity(source.map(|source| &source.level).unwrap_or(&Level::INFO));
+        let mut message = vec![];
+        match self.protocol {
+            Protocol::Journald => {
+                field(&mut message, "MESSAGE", formatted);
+                field(&mut message, "PRIORITY", severity.to_string().as_bytes());
+                field(&mut message, "SYSLOG_IDENTIFIER", IDENTIFIER.as_bytes());
+                field(
+                    &mut message,
+                    "SYSLOG_PID",
+                    std::process::id().to_string().as_bytes(),
+                );
+                if let Some(source) = source {
+                    field(&mut message, "TARGET", source.target.as_bytes());
+                    if let Some(file) = &source.file {
+                        field(&mut message, "CODE_FILE", file.as_bytes());
+                    }
+                    if let Some(line) = source.line {
+                        field(&mut message, "CODE_LINE", line.to_string().as_bytes());
+                    }
+                }
+            }
+            Protocol::Syslog => {
+                let _ = write!(
+                    message,
+                    "<{}>{}[{}]: ",
+                    FACILITY_DAEMON * 8 + severity,
+                    IDENTIFIER,
+                    std::process::id()
+                );
+                message.extend_from_slice(formatted);
+            }
+        }
+        message
+    }
+}
+
+/// Where an event was logged from.
+struct Source {
+    level: Level,
+    target: String,
+    file: Option<String>,
+    line: Option<u32>,
+}
+
+/// Writer for a single event, the fmt layer writes each formatted event at once.
+pub struct LogSocketWriter<'a> {
+    sink: &'a LogSocket,
+    source: Option<Source>,
+}
+
+impl Write for LogSocketWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.sink
+            .socket
+            .send(&self.sink.encode(self.source.as_ref(), buf))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a> MakeWriter<'a> for LogSocket {
+    type Writer = LogSocketWriter<'a>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        LogSocketWriter {
+            sink: self,
+            source: None,
+        }
+    }
+
+    fn make_writer_for(&'a self, metadata: &Metadata<'_>) -> Self::Writer {
+        LogSocketWriter {
+            sink: self,
+            source: Some(Source {
+                level: *metadata.level(),
+                target: metadata.target().to_string(),
+                file: metadata.file().map(str::to_string),
+                line: metadata.line(),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod log_socket {
+    use super::*;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    fn receive(protocol: Protocol, log: impl FnOnce()) -> String {
+        let path = std::env::temp_dir().join(format!(
+            "vault-kms-provider-{:?}-{}.sock",
+            protocol,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+        let receiver = UnixDatagram::bind(&path).unwrap();
+        let sink = LogSocket::connect(&path, protocol).unwrap();
+        let subscriber = tracing_subscriber::registry().with(
+            tracing_subscriber::fmt::layer()
+                .with_ansi(false)
+                .without_time()
+                .with_writer(sink),
+        );
+        tracing::subscriber::with_default(subscriber, log);
+        let mut buffer = vec![0; 4096];
+        let length = receiver.recv(&mut buffer).unwrap();
+        let _ = std::fs::remove_file(&path);
+        String::from_utf8_lossy(&buffer[..length]).to_string()
+    }
+
+    #[test]
+    fn sends_syslog_messages() {
+        let message = receive(Protocol::Syslog, || tracing::warn!("Token refresh failed"));
+        assert!(
+            message.starts_with(&format!("<28>{}[{}]: ", IDENTIFIER, std::process::id())),
+            "{}",
+            message
+        );
+        assert!(message.ends_with("Token refresh failed"), "{}", message);
+    }
+
+    #[test]
+    fn sends_journald_fields() {
+        let message = receive(
+            Protocol::Journald,
+            || tracing::error!(target: "lib::vault", "Unable to reach Vault\nconnection refused"),
+        );
+        assert!(message.contains("\nPRIORITY=3\n"), "{}", message);
+        assert!(message.contains("\nTARGET=lib::vault\n"), "{}", message);
+        assert!(
+            message.contains(&format!("\nSYSLOG_IDENTIFIER={}\n", IDENTIFIER)),
+            "{}",
+            message
+        );
+        assert!(message.starts_with("MESSAGE\n"), "{}", message);
+        assert!(message.contains("Unable to reach Vault\nconnection refused\n"));
+    }
+}
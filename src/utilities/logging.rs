@@ -1,9 +1,117 @@
-use crate::configuration::logging::LoggingConfiguration;
-use tracing::{debug, Level};
-use tracing_subscriber::EnvFilter;
+use crate::configuration::logging::{LogOutput, LoggingConfiguration};
+use crate::configuration::telemetry::TelemetryConfiguration;
+use crate::telemetry;
+use crate::utilities::log_socket::{LogSocket, Protocol};
+use crate::utilities::rotating_file::RotatingFile;
+use std::fmt::{Display, Formatter};
+use std::sync::{Mutex, OnceLock};
+use tracing::{debug, info, Level};
+use tracing_subscriber::filter::ParseError;
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::layer::{Layered, SubscriberExt};
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, reload, EnvFilter, Layer, Registry};
 
-pub fn initialize() {
+static LOG_FILTER: OnceLock<LogFilter> = OnceLock::new();
+
+type Filtered = Layered<reload::Layer<EnvFilter, Registry>, Registry>;
+type OutputLayer = Box<dyn Layer<Filtered> + Send + Sync>;
+
+/// Failure to change the filter directive of a [LogFilter].
+#[derive(Debug)]
+pub enum LogFilterError {
+    Invalid(ParseError),
+    Reload(reload::Error),
+}
+
+impl Display for LogFilterError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Invalid(error) => write!(f, "Invalid directive: {}", error),
+            Self::Reload(error) => write!(f, "Unable to reload the log filter: {}", error),
+        }
+    }
+}
+
+/// Handle to the filter of the running subscriber, allowing its directive (ex: `info,lib::vault=debug`) to be
+/// read and changed without restarting.
+pub struct LogFilter(reload::Handle<EnvFilter, Registry>);
+
+impl LogFilter {
+    pub fn new(directive: &str) -> (reload::Layer<EnvFilter, Registry>, Self) {
+        let (layer, handle) = reload::Layer::new(EnvFilter::new(directive));
+        (layer, Self(handle))
+    }
+
+    /// The filter installed by [initialize], if logging has been initialized.
+    pub fn global() -> Option<&'static Self> {
+        LOG_FILTER.get()
+    }
+
+    pub fn directive(&self) -> Result<String, LogFilterError> {
+        self.0
+            .with_current(|filter| filter.to_string())
+            .map_err(LogFilterError::Reload)
+    }
+
+    pub fn set_directive(&self, directive: &str) -> Result<(), LogFilterError> {
+        let filter = EnvFilter::try_new(directive).map_err(LogFilterError::Invalid)?;
+        self.0.reload(filter).map_err(LogFilterError::Reload)
+    }
+}
+
+fn format_layer<W>(format: &str, writer: W, ansi: bool) -> OutputLayer
+where
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    let layer = fmt::layer().with_ansi(ansi).with_writer(writer);
+    match format {
+        "json" => layer.json().boxed(),
+        "pretty" => layer.pretty().boxed(),
+        "compact" => layer.compact().boxed(),
+        _ => layer.boxed(),
+    }
+}
+
+/// journald and syslog add their own timestamp and level, so only the target and message are sent.
+fn socket_layer(socket: LogSocket) -> OutputLayer {
+    fmt::layer()
+        .with_ansi(false)
+        .without_time()
+        .with_level(false)
+        .compact()
+        .with_writer(socket)
+        .boxed()
+}
+
+fn output_layer(output: &LogOutput, config: &LoggingConfiguration) -> std::io::Result<OutputLayer> {
+    let error = |error: std::io::Error| {
+        std::io::Error::new(
+            error.kind(),
+            format!("Unable to open log output {:?}: {}", output, error),
+        )
+    };
+    match output {
+        LogOutput::Stdout => Ok(format_layer(&config.format, std::io::stdout, true)),
+        LogOutput::File(path) => {
+            let file = RotatingFile::open(path, config.max_file_size, config.max_files)
+                .and_then(|file| file.rotate_every(config.rotation.interval()))
+                .map_err(error)?;
+            Ok(format_layer(&config.format, Mutex::new(file), false))
+        }
+        LogOutput::Journald(path) => Ok(socket_layer(
+            LogSocket::connect(path, Protocol::Journald).map_err(error)?,
+        )),
+        LogOutput::Syslog(path) => Ok(socket_layer(
+            LogSocket::connect(path, Protocol::Syslog).map_err(error)?,
+        )),
+    }
+}
+
+/// Installs the global subscriber, writing to every configured output.
+pub fn initialize() -> std::io::Result<()> {
     let config = LoggingConfiguration::default();
+    let telemetry_config = TelemetryConfiguration::default();
     let directive = if config.level < tracing::Level::DEBUG {
         [&env!("CARGO_PKG_NAME").replace("-", "_"), "lib", "server"]
             .map(|target| format!("{}={}", target, config.level))
@@ -11,17 +119,26 @@ pub fn initialize() {
     } else {
         config.level.to_string()
     };
-    let subscriber =
-        tracing_subscriber::FmtSubscriber::builder().with_env_filter(EnvFilter::new(&directive));
-
-    match config.format.as_str() {
-        "json" => subscriber.json().init(),
-        "pretty" => subscriber.pretty().init(),
-        "compact" => subscriber.compact().init(),
-        _ => subscriber.init(),
-    };
+    let outputs = config
+        .outputs
+        .iter()
+        .map(|output| output_layer(output, &config))
+        .collect::<std::io::Result<Vec<OutputLayer>>>()?;
+
+    let (filter, handle) = LogFilter::new(&directive);
+    let _ = LOG_FILTER.set(handle);
 
-    debug!("Logging initialized");
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(outputs)
+        .with(telemetry::layer(&telemetry_config))
+        .init();
+
+    debug!("Logging initialized, writing to: {:?}", config.outputs);
+    if let Some(endpoint) = &telemetry_config.endpoint {
+        info!("Exporting traces to: {}", endpoint);
+    }
+    Ok(())
 }
 
 pub fn str_to_log_level(level: &str) -> Level {
@@ -34,6 +151,80 @@ pub fn str_to_log_level(level: &str) -> Level {
     }
 }
 
+#[cfg(test)]
+mod log_filter {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn changes_the_directive_at_runtime() {
+        let (layer, filter) = LogFilter::new("info");
+        let subscriber = tracing_subscriber::registry().with(layer);
+        tracing::subscriber::with_default(subscriber, || {
+            assert_eq!(filter.directive().unwrap(), "info");
+            assert!(!tracing::enabled!(target: "lib::vault", Level::DEBUG));
+
+            filter.set_directive("info,lib::vault=debug").unwrap();
+
+            assert_eq!(filter.directive().unwrap(), "lib::vault=debug,info");
+            assert!(tracing::enabled!(target: "lib::vault", Level::DEBUG));
+            assert!(!tracing::enabled!(target: "lib::checks", Level::DEBUG));
+        });
+    }
+
+    #[test]
+    fn rejects_invalid_directives() {
+        let (_layer, filter) = LogFilter::new("info");
+        assert!(matches!(
+            filter.set_directive("lib::vault=loud"),
+            Err(LogFilterError::Invalid(_))
+        ));
+        assert_eq!(filter.directive().unwrap(), "info");
+    }
+}
+
+#[cfg(test)]
+mod output_layer {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn writes_to_a_log_file() {
+        let path = std::env::temp_dir().join(format!(
+            "vault-kms-provider-output-{}.log",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+        let config = LoggingConfiguration {
+            format: "json".to_string(),
+            ..LoggingConfiguration::default()
+        };
+        let output = output_layer(&LogOutput::File(path.clone()), &config).unwrap();
+        let (filter, _handle) = LogFilter::new("info");
+        let subscriber = tracing_subscriber::registry()
+            .with(filter)
+            .with(vec![output]);
+        tracing::subscriber::with_default(subscriber, || info!("Written to a file"));
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert!(
+            contents.contains(r#""message":"Written to a file""#),
+            "{}",
+            contents
+        );
+    }
+
+    #[test]
+    fn fails_if_an_output_cannot_be_opened() {
+        let config = LoggingConfiguration::default();
+        let missing = PathBuf::from("test_files/missing-directory/provider.log");
+        assert!(output_layer(&LogOutput::File(missing), &config).is_err());
+        let missing = PathBuf::from("test_files/missing-syslog.sock");
+        assert!(output_layer(&LogOutput::Syslog(missing), &config).is_err());
+    }
+}
+
 #[cfg(test)]
 mod str_to_log_level {
     use super::str_to_log_level;
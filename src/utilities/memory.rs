@@ -0,0 +1,73 @@
+use crate::configuration::memory::MemoryConfiguration;
+use tracing::info;
+
+/// Locks current and future memory pages into RAM so they are never written to swap.
+fn lock_memory() -> std::io::Result<()> {
+    // SAFETY: mlockall only takes flags and has no memory safety requirements.
+    if unsafe { libc::mlockall(libc::MCL_CURRENT | libc::MCL_FUTURE) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    info!("Locked process memory");
+    Ok(())
+}
+
+/// Sets the core file size limit to zero and, on Linux, marks the process as not dumpable,
+/// which also stops other processes of the same user from attaching to it.
+fn disable_core_dumps() -> std::io::Result<()> {
+    let limit = libc::rlimit {
+        rlim_cur: 0,
+        rlim_max: 0,
+    };
+    // SAFETY: `limit` is a valid rlimit that outlives the call.
+    if unsafe { libc::setrlimit(libc::RLIMIT_CORE, &limit) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    #[cfg(target_os = "linux")]
+    // SAFETY: PR_SET_DUMPABLE only takes integer arguments.
+    if unsafe { libc::prctl(libc::PR_SET_DUMPABLE, 0, 0, 0, 0) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    info!("Disabled core dumps");
+    Ok(())
+}
+
+/// Applies the configured memory protections, failing if any of them cannot be applied.
+pub fn protect(config: &MemoryConfiguration) -> std::io::Result<()> {
+    if config.disable_core_dumps {
+        disable_core_dumps().map_err(|error| {
+            std::io::Error::new(
+                error.kind(),
+                format!("Failed to disable core dumps: {}", error),
+            )
+        })?;
+    }
+    if config.lock {
+        lock_memory().map_err(|error| {
+            std::io::Error::new(error.kind(), format!("Failed to lock memory: {}", error))
+        })?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod memory {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn does_nothing_unless_configured() {
+        assert!(protect(&MemoryConfiguration::new(None, None)).is_ok());
+    }
+
+    #[test]
+    fn disables_core_dumps() {
+        let config = MemoryConfiguration::new(None, Some(true));
+        protect(&config).unwrap();
+        let mut limit = libc::rlimit {
+            rlim_cur: 1,
+            rlim_max: 1,
+        };
+        assert_eq!(unsafe { libc::getrlimit(libc::RLIMIT_CORE, &mut limit) }, 0);
+        assert_eq!((limit.rlim_cur, limit.rlim_max), (0, 0));
+    }
+}
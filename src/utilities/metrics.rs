@@ -0,0 +1,116 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+const PREFIX: &str = "vault_kms_provider";
+
+/// Request metrics, rendered in the Prometheus text format by the `/metrics` endpoint.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    in_flight: AtomicUsize,
+    queued: AtomicUsize,
+    rejected: Mutex<BTreeMap<String, u64>>,
+}
+
+impl Metrics {
+    pub fn started(&self) {
+        self.in_flight.fetch_add(1, Ordering::SeqCst);
+    }
+
+    pub fn finished(&self) {
+        self.in_flight.fetch_sub(1, Ordering::SeqCst);
+    }
+
+    pub fn set_queued(&self, queued: usize) {
+        self.queued.store(queued, Ordering::SeqCst);
+    }
+
+    pub fn rejected(&self, method: &str) {
+        *self
+            .rejected
+            .lock()
+            .unwrap()
+            .entry(method.to_string())
+            .or_default() += 1;
+    }
+
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::SeqCst)
+    }
+
+    pub fn queued(&self) -> usize {
+        self.queued.load(Ordering::SeqCst)
+    }
+
+    pub fn rejections(&self, method: &str) -> u64 {
+        self.rejected
+            .lock()
+            .unwrap()
+            .get(method)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub fn render(&self) -> String {
+        let mut output = String::new();
+        let _ = writeln!(
+            output,
+            "# HELP {PREFIX}_in_flight_requests KMS requests currently being handled.\n\
+             # TYPE {PREFIX}_in_flight_requests gauge\n\
+             {PREFIX}_in_flight_requests {}",
+            self.in_flight()
+        );
+        let _ = writeln!(
+            output,
+            "# HELP {PREFIX}_queued_requests KMS requests waiting for a concurrency slot.\n\
+             # TYPE {PREFIX}_queued_requests gauge\n\
+             {PREFIX}_queued_requests {}",
+            self.queued()
+        );
+        let _ = writeln!(
+            output,
+            "# HELP {PREFIX}_rejected_requests_total KMS requests rejected because the queue was full.\n\
+             # TYPE {PREFIX}_rejected_requests_total counter"
+        );
+        for (method, count) in self.rejected.lock().unwrap().iter() {
+            let _ = writeln!(
+                output,
+                "{PREFIX}_rejected_requests_total{{method=\"{}\"}} {}",
+                method, count
+            );
+        }
+        output
+    }
+}
+
+#[cfg(test)]
+mod metrics {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn counts_rejections_per_method() {
+        let metrics = Metrics::default();
+        metrics.rejected("Encrypt");
+        metrics.rejected("Encrypt");
+        metrics.rejected("Decrypt");
+        assert_eq!(metrics.rejections("Encrypt"), 2);
+        assert_eq!(metrics.rejections("Decrypt"), 1);
+        assert_eq!(metrics.rejections("Status"), 0);
+    }
+
+    #[test]
+    fn renders_metrics_in_the_prometheus_text_format() {
+        let metrics = Metrics::default();
+        metrics.started();
+        metrics.set_queued(3);
+        metrics.rejected("Encrypt");
+        let rendered = metrics.render();
+        assert!(rendered.contains("vault_kms_provider_in_flight_requests 1\n"));
+        assert!(rendered.contains("vault_kms_provider_queued_requests 3\n"));
+        assert!(
+            rendered.contains("vault_kms_provider_rejected_requests_total{method=\"Encrypt\"} 1\n")
+        );
+    }
+}
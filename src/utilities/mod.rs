@@ -1,6 +1,14 @@
+pub mod backoff;
 pub mod date;
 pub mod environment;
+pub mod heartbeat;
+pub mod limiter;
+pub mod log_socket;
 pub mod logging;
+pub mod memory;
+pub mod metrics;
+pub mod redact;
+pub mod rotating_file;
 pub mod socket;
 pub mod source;
 pub mod watcher;
@@ -0,0 +1,52 @@
+use sha2::{Digest, Sha256};
+use std::fmt::{Debug, Display, Formatter};
+
+/// Bytes of the SHA-256 digest shown, enough to tell values apart without revealing them.
+const DIGEST_BYTES: usize = 8;
+
+/// Formats secret material (tokens, credentials, plaintext and ciphertext) by its size and a
+/// truncated SHA-256 digest, so it can be correlated in logs without being written to them.
+pub struct Redacted<'a>(&'a [u8]);
+
+impl<'a> Redacted<'a> {
+    pub fn new<T: AsRef<[u8]> + ?Sized>(value: &'a T) -> Self {
+        Self(value.as_ref())
+    }
+}
+
+impl Display for Redacted<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "<redacted {} bytes, sha256:{}>",
+            self.0.len(),
+            hex::encode(&Sha256::digest(self.0)[..DIGEST_BYTES])
+        )
+    }
+}
+
+impl Debug for Redacted<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(self, f)
+    }
+}
+
+#[cfg(test)]
+mod redacted {
+    use super::Redacted;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn describes_values_by_size_and_digest() {
+        assert_eq!(
+            Redacted::new("secret").to_string(),
+            "<redacted 6 bytes, sha256:2bb80d537b1da3e3>"
+        );
+    }
+
+    #[test]
+    fn formats_the_same_with_debug() {
+        let redacted = Redacted::new(b"secret");
+        assert_eq!(format!("{:?}", redacted), redacted.to_string());
+    }
+}
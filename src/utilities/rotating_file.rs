@@ -0,0 +1,170 @@
+use std::ffi::OsString;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Appends to a file, moving it to `<path>.1` once it would grow beyond `max_size`, or when the
+/// rotation interval it was started in has passed, and shifting older files up to
+/// `<path>.<max_files>`, beyond which they are deleted.
+pub struct RotatingFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    max_size: u64,
+    max_files: usize,
+    interval: Option<Duration>,
+    period: u64,
+}
+
+/// Index of the interval `time` falls in, counted from the unix epoch.
+fn period(time: SystemTime, interval: Option<Duration>) -> u64 {
+    match interval {
+        Some(interval) if !interval.is_zero() => {
+            time.duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs()
+                / interval.as_secs().max(1)
+        }
+        _ => 0,
+    }
+}
+
+impl RotatingFile {
+    pub fn open(path: &Path, max_size: u64, max_files: usize) -> std::io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            size: file.metadata()?.len(),
+            file,
+            max_size,
+            max_files,
+            interval: None,
+            period: 0,
+        })
+    }
+
+    /// Also rotates the file every `interval` (ex: hourly or daily), aligned to the unix epoch. An existing
+    /// file is rotated on the first write if it was last modified in an earlier interval.
+    pub fn rotate_every(mut self, interval: Option<Duration>) -> std::io::Result<Self> {
+        let modified = self.file.metadata()?.modified()?;
+        self.interval = interval;
+        self.period = period(modified, interval);
+        Ok(self)
+    }
+
+    fn rotated(&self, index: usize) -> PathBuf {
+        let mut path = OsString::from(self.path.as_os_str());
+        path.push(format!(".{}", index));
+        PathBuf::from(path)
+    }
+
+    fn rotate(&mut self) -> std::io::Result<()> {
+        if self.max_files == 0 {
+            std::fs::remove_file(&self.path)?;
+        } else {
+            for index in (1..self.max_files).rev() {
+                let from = self.rotated(index);
+                if from.exists() {
+                    std::fs::rename(from, self.rotated(index + 1))?;
+                }
+            }
+            std::fs::rename(&self.path, self.rotated(1))?;
+        }
+        self.file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+
+    fn write_at(&mut self, buf: &[u8], now: SystemTime) -> std::io::Result<()> {
+        let length = buf.len() as u64;
+        let period = period(now, self.interval);
+        if self.size > 0 && (self.size + length > self.max_size || period != self.period) {
+            self.rotate()?;
+        }
+        self.period = period;
+        self.file.write_all(buf)?;
+        self.size += length;
+        Ok(())
+    }
+
+    pub fn write_line(&mut self, line: &str) -> std::io::Result<()> {
+        self.write_at(format!("{}\n", line).as_bytes(), SystemTime::now())
+    }
+}
+
+/// Each write is kept whole within a single file, so writes should be complete lines.
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.write_at(buf, SystemTime::now())?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.file.flush()
+    }
+}
+
+#[cfg(test)]
+mod rotating_file {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn directory(name: &str) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!(
+            "vault-kms-provider-rotating-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&directory);
+        std::fs::create_dir_all(&directory).unwrap();
+        directory
+    }
+
+    #[test]
+    fn appends_lines_until_the_file_is_full() {
+        let path = directory("appends").join("audit.log");
+        let mut file = RotatingFile::open(&path, 1024, 2).unwrap();
+        file.write_line("first").unwrap();
+        file.write_line("second").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "first\nsecond\n");
+        assert!(!file.rotated(1).exists());
+    }
+
+    #[test]
+    fn rotates_the_file_once_it_is_full() {
+        let path = directory("rotates").join("audit.log");
+        let mut file = RotatingFile::open(&path, 8, 2).unwrap();
+        for line in ["first", "second", "third", "fourth"] {
+            file.write_line(line).unwrap();
+        }
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "fourth\n");
+        assert_eq!(std::fs::read_to_string(file.rotated(1)).unwrap(), "third\n");
+        assert_eq!(
+            std::fs::read_to_string(file.rotated(2)).unwrap(),
+            "second\n"
+        );
+        assert!(!file.rotated(3).exists());
+    }
+
+    #[test]
+    fn rotates_the_file_once_the_interval_has_passed() {
+        let path = directory("interval").join("provider.log");
+        let hour = Duration::from_secs(60 * 60);
+        let mut file = RotatingFile::open(&path, 1024, 1)
+            .unwrap()
+            .rotate_every(Some(hour))
+            .unwrap();
+        let now = SystemTime::now();
+        file.write_at(b"first\n", now).unwrap();
+        file.write_at(b"second\n", now).unwrap();
+        file.write_at(b"third\n", now + hour).unwrap();
+        file.write_at(b"fourth\n", now + hour * 2).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "fourth\n");
+        assert_eq!(std::fs::read_to_string(file.rotated(1)).unwrap(), "third\n");
+        assert!(!file.rotated(2).exists());
+    }
+}
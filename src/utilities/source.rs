@@ -1,25 +1,66 @@
+use crate::utilities::redact::Redacted;
+use std::fmt::{Debug, Display, Formatter};
 use std::fs;
 use vaultrs::error::ClientError;
+use zeroize::{Zeroize, Zeroizing};
 
-#[derive(Clone, Debug, PartialEq)]
+/// A configured value, or the path of a file containing it. Values may be secrets, so they are
+/// redacted when formatted and cleared from memory when dropped.
+#[derive(Clone, PartialEq)]
 pub enum Source {
     Value(String),
     FilePath(String),
 }
 
+impl Debug for Source {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Value(value) => f.debug_tuple("Value").field(&Redacted::new(value)).finish(),
+            Self::FilePath(path) => f.debug_tuple("FilePath").field(path).finish(),
+        }
+    }
+}
+
+impl Display for Source {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Value(value) => Display::fmt(&Redacted::new(value), f),
+            Self::FilePath(path) => write!(f, "file \"{}\"", path),
+        }
+    }
+}
+
+impl Drop for Source {
+    fn drop(&mut self) {
+        if let Self::Value(value) = self {
+            value.zeroize();
+        }
+    }
+}
+
 impl Source {
-    pub fn value(&self) -> Result<String, ClientError> {
+    /// The value, or the contents of the file, cleared from memory once dropped.
+    pub fn value(&self) -> Result<Zeroizing<String>, ClientError> {
         match self {
-            Self::Value(value) => Ok(value.to_string()),
-            Self::FilePath(path) => {
-                fs::read_to_string(path).map_err(|error| ClientError::FileReadError {
+            Self::Value(value) => Ok(Zeroizing::new(value.to_string())),
+            Self::FilePath(path) => fs::read_to_string(path)
+                .map(Zeroizing::new)
+                .map_err(|error| ClientError::FileReadError {
                     source: error,
                     path: path.to_string(),
-                })
-            }
+                }),
         }
     }
 
+    /// The value without trailing whitespace, for tokens, names and mount paths (ex: a role id) which cannot contain
+    /// whitespace but are usually written to files with a trailing new line.
+    pub fn trimmed(&self) -> Result<Zeroizing<String>, ClientError> {
+        let mut value = self.value()?;
+        let length = value.trim_end().len();
+        value.truncate(length);
+        Ok(value)
+    }
+
     pub fn path(&self) -> Option<String> {
         if let Self::FilePath(path) = self {
             Some(path.to_string())
@@ -41,14 +82,30 @@ mod source {
         let contents = "file contents!";
         let source = Source::FilePath(path.to_string());
         fs::write(path, contents).unwrap();
-        assert_str_eq!(source.value().unwrap(), contents);
+        assert_str_eq!(source.value().unwrap().as_str(), contents);
+    }
+
+    #[test]
+    fn removes_trailing_new_lines_from_trimmed_file_contents() {
+        let path = "./test_files/source_test_new_line";
+        let source = Source::FilePath(path.to_string());
+        fs::write(path, "role_id\n").unwrap();
+        assert_str_eq!(source.trimmed().unwrap().as_str(), "role_id");
+    }
+
+    #[test]
+    fn keeps_trailing_whitespace_in_file_contents() {
+        let path = "./test_files/source_test_whitespace";
+        let source = Source::FilePath(path.to_string());
+        fs::write(path, "password \n").unwrap();
+        assert_str_eq!(source.value().unwrap().as_str(), "password \n");
     }
 
     #[test]
     fn retrieves_a_value_if_defined() {
         let value = "test";
         let source = Source::Value(value.to_string());
-        assert_str_eq!(source.value().unwrap(), value);
+        assert_str_eq!(source.value().unwrap().as_str(), value);
     }
 
     #[test]
@@ -58,6 +115,23 @@ mod source {
         assert_eq!(source.path(), Some(path.to_string()));
     }
 
+    #[test]
+    fn redacts_values_when_formatted() {
+        let source = Source::Value("s.SiQOECxwSDCeQt1r0n5kqQCr".to_string());
+        assert!(!format!("{:?}", source).contains("SiQOECxwSDCeQt1r0n5kqQCr"));
+        assert!(!source.to_string().contains("SiQOECxwSDCeQt1r0n5kqQCr"));
+    }
+
+    #[test]
+    fn formats_file_paths() {
+        let source = Source::FilePath("/var/run/secrets/token".to_string());
+        assert_str_eq!(
+            format!("{:?}", source),
+            "FilePath(\"/var/run/secrets/token\")"
+        );
+        assert_str_eq!(source.to_string(), "file \"/var/run/secrets/token\"");
+    }
+
     #[test]
     fn returns_none_if_no_path_is_defined() {
         let source = Source::Value("hello".to_string());
@@ -1,19 +1,26 @@
 use crate::configuration::authentication::Credentials;
+use crate::utilities::backoff::Backoff;
+use crate::utilities::heartbeat::Heartbeat;
 use futures::{
     channel::mpsc::{channel, Receiver},
     SinkExt,
 };
 use notify::{
-    event::{AccessKind, AccessMode},
+    event::{AccessKind, AccessMode, ModifyKind, RenameMode},
     Event,
-    EventKind::Access,
+    EventKind::{Access, Modify},
     RecommendedWatcher, RecursiveMode, Watcher,
 };
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::RwLock;
+use tokio::time::Instant;
 use tokio_stream::StreamExt;
 use tonic::async_trait;
-use tracing::info;
+use tracing::{error, info};
 
 pub fn async_watcher() -> notify::Result<(RecommendedWatcher, Receiver<notify::Result<Event>>)> {
     let (mut tx, rx) = channel(1);
@@ -30,64 +37,216 @@ pub fn async_watcher() -> notify::Result<(RecommendedWatcher, Receiver<notify::R
 
 #[async_trait]
 pub trait Refresh {
-    async fn refresh_token(&mut self) -> Result<(), std::io::Error>;
+    async fn refresh_token(&self) -> Result<(), std::io::Error>;
+
+    /// Time until the current token should be refreshed regardless of any file changes.
+    fn refresh_in(&self) -> Option<Duration> {
+        None
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct RefreshStatus {
+    failures: AtomicU64,
+    consecutive_failures: AtomicU64,
+    last_error: std::sync::RwLock<Option<String>>,
+}
+
+impl RefreshStatus {
+    pub fn succeeded(&self) {
+        self.consecutive_failures.store(0, Ordering::Relaxed);
+        *self.last_error.write().unwrap() = None;
+    }
+
+    pub fn failed(&self, error: &str) {
+        self.failures.fetch_add(1, Ordering::Relaxed);
+        self.consecutive_failures.fetch_add(1, Ordering::Relaxed);
+        *self.last_error.write().unwrap() = Some(error.to_string());
+    }
+
+    pub fn failures(&self) -> u64 {
+        self.failures.load(Ordering::Relaxed)
+    }
+
+    pub fn consecutive_failures(&self) -> u64 {
+        self.consecutive_failures.load(Ordering::Relaxed)
+    }
+
+    pub fn is_failing(&self) -> bool {
+        self.consecutive_failures() > 0
+    }
+
+    pub fn last_error(&self) -> Option<String> {
+        self.last_error.read().unwrap().clone()
+    }
+}
+
+async fn refresh<T: Refresh>(
+    client: &Arc<RwLock<T>>,
+    status: &RefreshStatus,
+    backoff: &mut Backoff,
+) -> Option<Instant> {
+    match client.read().await.refresh_token().await {
+        Ok(()) => {
+            if status.is_failing() {
+                info!(
+                    "Token refreshed after {} failed attempt(s)",
+                    status.consecutive_failures()
+                );
+            }
+            status.succeeded();
+            backoff.reset();
+            None
+        }
+        Err(error) => {
+            status.failed(&error.to_string());
+            let delay = backoff.next_delay();
+            error!(
+                "Failed to refresh token (attempt {}), retrying in {:?}: {}",
+                status.consecutive_failures(),
+                delay,
+                error
+            );
+            Some(Instant::now() + delay)
+        }
+    }
+}
+
+/// Resolves a file to an absolute path within its canonical parent directory, matching the
+/// paths reported in events for that directory.
+fn resolve(path: &str) -> Result<PathBuf, std::io::Error> {
+    let path = Path::new(path);
+    let name = path.file_name().ok_or_else(|| {
+        std::io::Error::other(format!(
+            "Unable to watch \"{}\", not a file",
+            path.display()
+        ))
+    })?;
+    let directory = path
+        .parent()
+        .filter(|directory| !directory.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    Ok(directory.canonicalize()?.join(name))
+}
+
+/// Files can be rewritten in place or replaced by renaming a new file over them, as Vault Agent
+/// does with its sinks and templates, either counts as an update. Renames are reported as both
+/// `To` and `Both` events, only the former is used so that each rename refreshes once.
+fn is_update(event: &Event) -> bool {
+    matches!(
+        event.kind,
+        Access(AccessKind::Close(AccessMode::Write))
+            | Modify(ModifyKind::Name(RenameMode::To | RenameMode::Any))
+    )
+}
+
+async fn scheduled_refresh<T: Refresh>(client: &Arc<RwLock<T>>) -> Option<Instant> {
+    client.read().await.refresh_in().map(|delay| {
+        info!("Scheduling token refresh in {:?}", delay);
+        Instant::now() + delay
+    })
 }
 
 pub async fn watch<T: Refresh>(
-    path_to_watch: Option<String>,
+    paths: Vec<String>,
     client: Arc<RwLock<T>>,
+    status: Arc<RefreshStatus>,
+    mut backoff: Backoff,
+    heartbeat: Heartbeat,
 ) -> Result<(), std::io::Error> {
-    if let Some(path) = path_to_watch {
+    let mut refresh_at = scheduled_refresh(&client).await;
+    if paths.is_empty() && refresh_at.is_none() {
+        heartbeat.complete();
+    } else {
         let (mut watcher, mut rx) =
             async_watcher().map_err(|error| std::io::Error::other(error.to_string()))?;
-        watcher
-            .watch(path.as_ref(), RecursiveMode::NonRecursive)
-            .map_err(|error| std::io::Error::other(error.to_string()))?;
-        info!("Watching file at path: \"{}\" for updates", path);
-        while let Some(Ok(event)) = rx.next().await {
-            if event.kind == Access(AccessKind::Close(AccessMode::Write)) {
-                event.paths.iter().for_each(|path| {
-                    info!(
-                        "Refreshing token due to updated JWT at path: {}",
-                        path.to_str().unwrap()
-                    )
-                });
-                client
-                    .write()
-                    .await
-                    .refresh_token()
-                    .await
-                    .map_err(|e| std::io::Error::other(e.to_string()))?;
+        let files = paths
+            .iter()
+            .map(|path| resolve(path))
+            .collect::<Result<HashSet<PathBuf>, std::io::Error>>()?;
+        let directories: HashSet<&Path> = files.iter().filter_map(|file| file.parent()).collect();
+        for directory in directories {
+            watcher
+                .watch(directory, RecursiveMode::NonRecursive)
+                .map_err(|error| std::io::Error::other(error.to_string()))?;
+        }
+        paths
+            .iter()
+            .for_each(|path| info!("Watching file at path: \"{}\" for updates", path));
+        let mut retry_at: Option<Instant> = None;
+        // Scheduled refreshes and retries carry on when file events stop being delivered.
+        let mut watching = true;
+        let mut beat = tokio::time::interval(heartbeat.interval());
+        loop {
+            let refresh_required = tokio::select! {
+                event = rx.next(), if watching => match event {
+                    Some(Ok(event)) => {
+                        let updated: Vec<&PathBuf> = if is_update(&event) {
+                            event.paths.iter().filter(|path| files.contains(*path)).collect()
+                        } else {
+                            vec![]
+                        };
+                        updated.iter().for_each(|path| {
+                            info!(
+                                "Refreshing token due to updated credentials at path: {}",
+                                path.display()
+                            )
+                        });
+                        !updated.is_empty()
+                    }
+                    Some(Err(error)) => {
+                        error!("Failed to watch credentials for updates: {}", error);
+                        false
+                    }
+                    None => {
+                        error!("Stopped watching credentials for updates, file changes no longer refresh the token");
+                        watching = false;
+                        false
+                    }
+                },
+                _ = tokio::time::sleep_until(retry_at.unwrap_or_else(Instant::now)), if retry_at.is_some() => {
+                    info!("Retrying token refresh");
+                    true
+                },
+                _ = tokio::time::sleep_until(refresh_at.unwrap_or_else(Instant::now)), if retry_at.is_none() && refresh_at.is_some() => {
+                    info!("Refreshing token before the credentials expire");
+                    true
+                },
+                _ = beat.tick() => false,
+            };
+            if refresh_required {
+                retry_at = refresh(&client, &status, &mut backoff).await;
+                refresh_at = scheduled_refresh(&client).await;
             }
+            heartbeat.beat();
         }
     }
     Ok(())
 }
 
 pub async fn watch_credentials<T: Refresh>(
-    credentials: Credentials,
+    credentials: Vec<Credentials>,
     client: Arc<RwLock<T>>,
+    status: Arc<RefreshStatus>,
+    heartbeat: Heartbeat,
 ) -> Result<(), std::io::Error> {
-    watch(
-        match credentials {
-            Credentials::Kubernetes(credentials) => credentials.jwt.path(),
-            Credentials::AppRole(role) => role.secret_id.path(),
-            Credentials::Token(token) => token.path(),
-            Credentials::UserPass(credentials) => credentials.password.path(),
-            Credentials::Jwt(credentials) => credentials.jwt.path(),
-            _ => None,
-        },
-        client,
-    )
-    .await
+    let mut paths: Vec<String> = credentials
+        .iter()
+        .flat_map(|credentials| credentials.paths())
+        .collect();
+    paths.sort();
+    paths.dedup();
+    watch(paths, client, status, Backoff::default(), heartbeat).await
 }
 
 #[cfg(test)]
 mod watcher {
     use super::*;
     use crate::configuration::authentication::{AppRole, Jwt, Kubernetes, UserPass};
+    use crate::utilities::heartbeat::{Heartbeats, TaskStatus};
     use crate::utilities::source::Source;
     use std::io::Error;
+    use std::sync::atomic::AtomicU32;
     use std::sync::Arc;
     use std::time::Duration;
     use tokio::sync::RwLock;
@@ -95,29 +254,72 @@ mod watcher {
     use uuid::Uuid;
 
     struct Mock {
-        called: bool,
+        refreshes: AtomicU32,
+        failures: AtomicU32,
+        refresh_in: Option<Duration>,
     }
 
     impl Mock {
         pub fn new() -> Self {
-            Self { called: false }
+            Self::failing(0)
+        }
+
+        pub fn failing(failures: u32) -> Self {
+            Self {
+                refreshes: AtomicU32::new(0),
+                failures: AtomicU32::new(failures),
+                refresh_in: None,
+            }
+        }
+
+        pub fn expiring(refresh_in: Duration) -> Self {
+            Self {
+                refresh_in: Some(refresh_in),
+                ..Self::new()
+            }
+        }
+
+        fn refreshes(&self) -> u32 {
+            self.refreshes.load(Ordering::SeqCst)
+        }
+
+        fn called(&self) -> bool {
+            self.refreshes() > 0
         }
     }
 
     #[async_trait]
     impl Refresh for Mock {
-        async fn refresh_token(&mut self) -> Result<(), Error> {
-            self.called = true;
-            Ok(())
+        async fn refresh_token(&self) -> Result<(), Error> {
+            self.refreshes.fetch_add(1, Ordering::SeqCst);
+            let failing = self
+                .failures
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |failures| {
+                    failures.checked_sub(1)
+                })
+                .is_ok();
+            if failing {
+                Err(Error::other("invalid token"))
+            } else {
+                Ok(())
+            }
+        }
+
+        fn refresh_in(&self) -> Option<Duration> {
+            self.refresh_in
         }
     }
 
+    fn heartbeat() -> Heartbeat {
+        Heartbeats::new(Duration::from_secs(60)).register("credentials-watcher")
+    }
+
     async fn check_credential_path(credentials: Credentials, file_path: &str) {
         let mock_client = Arc::new(RwLock::new(Mock::new()));
         std::fs::write(file_path, "Hello World!").unwrap();
         tokio::select! {
             _ = async {
-                watch_credentials(credentials, mock_client.clone()).await.unwrap();
+                watch_credentials(vec![credentials], mock_client.clone(), Arc::new(RefreshStatus::default()), heartbeat()).await.unwrap();
             } => (),
             _ = async {
                 tokio::time::sleep(Duration::from_millis(100)).await;
@@ -125,7 +327,7 @@ mod watcher {
                 tokio::time::sleep(Duration::from_millis(100)).await;
             } => (),
         }
-        assert!(mock_client.read().await.called);
+        assert!(mock_client.read().await.called());
     }
 
     mod watch_credentials {
@@ -155,18 +357,44 @@ mod watcher {
         async fn watches_app_role_secret_id() {
             let file_path = format!("./test_files/test-watch-file-{}", Uuid::new_v4());
             let credentials = Credentials::AppRole(AppRole::new(
-                "role_id".to_string(),
+                Source::Value("role_id".to_string()),
                 Source::FilePath(file_path.clone()),
                 None,
             ));
             check_credential_path(credentials, &file_path).await;
         }
 
+        #[tokio::test]
+        async fn watches_app_role_role_id() {
+            let file_path = format!("./test_files/test-watch-file-{}", Uuid::new_v4());
+            let secret_id_path = format!("./test_files/test-watch-file-{}", Uuid::new_v4());
+            std::fs::write(&secret_id_path, "secret_id").unwrap();
+            let credentials = Credentials::AppRole(AppRole::new(
+                Source::FilePath(file_path.clone()),
+                Source::FilePath(secret_id_path),
+                None,
+            ));
+            check_credential_path(credentials, &file_path).await;
+        }
+
+        #[tokio::test]
+        async fn watches_kubernetes_role() {
+            let file_path = format!("./test_files/test-watch-file-{}", Uuid::new_v4());
+            let jwt_path = format!("./test_files/test-watch-file-{}", Uuid::new_v4());
+            std::fs::write(&jwt_path, "jwt").unwrap();
+            let credentials = Credentials::Kubernetes(Kubernetes::new(
+                Source::FilePath(jwt_path),
+                Some(Source::FilePath(file_path.clone())),
+                None,
+            ));
+            check_credential_path(credentials, &file_path).await;
+        }
+
         #[tokio::test]
         async fn watches_password() {
             let file_path = format!("./test_files/test-watch-file-{}", Uuid::new_v4());
             let credentials = Credentials::UserPass(UserPass::new(
-                "password".to_string(),
+                Source::Value("password".to_string()),
                 Source::FilePath(file_path.clone()),
                 None,
             ));
@@ -180,12 +408,46 @@ mod watcher {
             check_credential_path(credentials, &file_path).await;
         }
 
+        #[tokio::test]
+        async fn watches_every_method_in_a_chain() {
+            let file_path = format!("./test_files/test-watch-file-{}", Uuid::new_v4());
+            let mock_client = Arc::new(RwLock::new(Mock::new()));
+            let credentials = vec![
+                Credentials::Certificate(Certificate::new(Source::Value("cert".to_string()), None)),
+                Credentials::Token(Source::FilePath(file_path.clone())),
+            ];
+            std::fs::write(&file_path, "Hello World!").unwrap();
+            tokio::select! {
+                _ = async {
+                    watch_credentials(credentials, mock_client.clone(), Arc::new(RefreshStatus::default()), heartbeat()).await.unwrap();
+                } => (),
+                _ = async {
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    std::fs::write(&file_path, "Goodbye Stranger!").unwrap();
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                } => (),
+            }
+            assert!(mock_client.read().await.called());
+        }
+
         #[tokio::test]
         async fn does_not_watch_credentials_with_no_path() {
+            let heartbeats = Heartbeats::new(Duration::from_secs(60));
             let mock_client = Arc::new(RwLock::new(Mock::new()));
-            let credentials = Credentials::Certificate(Certificate::new("cert".to_string(), None));
-            let result = watch_credentials(credentials, mock_client).await;
+            let credentials =
+                Credentials::Certificate(Certificate::new(Source::Value("cert".to_string()), None));
+            let result = watch_credentials(
+                vec![credentials],
+                mock_client,
+                Arc::new(RefreshStatus::default()),
+                heartbeats.register("credentials-watcher"),
+            )
+            .await;
             assert!(result.is_ok());
+            assert_eq!(
+                heartbeats.statuses(),
+                vec![("credentials-watcher", TaskStatus::Alive)]
+            );
         }
     }
 
@@ -199,7 +461,7 @@ mod watcher {
             std::fs::write(&path, "Hello World!").unwrap();
             tokio::select! {
                 _ = async {
-                    watch(Some(path.to_string()), mock_client.clone()).await.unwrap();
+                    watch(vec![path.to_string()], mock_client.clone(), Arc::new(RefreshStatus::default()), Backoff::default(), heartbeat()).await.unwrap();
                     Ok::<(), std::io::Error>
                 } => (),
                 _ = async {
@@ -209,8 +471,108 @@ mod watcher {
                     Ok::<(), std::io::Error>(())
                 } => (),
             }
-            assert!(mock_client.read().await.called);
+            assert!(mock_client.read().await.called());
             Ok(())
         }
+
+        #[tokio::test]
+        async fn retries_failed_refreshes_until_they_succeed() {
+            let path = format!("./test_files/test-watch-file-{}", Uuid::new_v4());
+            let mock_client = Arc::new(RwLock::new(Mock::failing(2)));
+            let status = Arc::new(RefreshStatus::default());
+            let backoff = Backoff::new(Duration::from_millis(10), Duration::from_millis(20));
+            std::fs::write(&path, "Hello World!").unwrap();
+            tokio::select! {
+                _ = async {
+                    watch(vec![path.clone()], mock_client.clone(), status.clone(), backoff, heartbeat()).await.unwrap();
+                } => (),
+                _ = async {
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    std::fs::write(&path, "Goodbye Stranger!").unwrap();
+                    tokio::time::sleep(Duration::from_millis(200)).await;
+                } => (),
+            }
+            assert_eq!(mock_client.read().await.failures.load(Ordering::SeqCst), 0);
+            assert_eq!(status.failures(), 2);
+            assert!(!status.is_failing());
+        }
+
+        #[tokio::test]
+        async fn keeps_watching_while_refreshes_fail() {
+            let path = format!("./test_files/test-watch-file-{}", Uuid::new_v4());
+            let mock_client = Arc::new(RwLock::new(Mock::failing(u32::MAX)));
+            let status = Arc::new(RefreshStatus::default());
+            let backoff = Backoff::new(Duration::from_millis(10), Duration::from_millis(20));
+            std::fs::write(&path, "Hello World!").unwrap();
+            let finished = tokio::select! {
+                _ = async {
+                    watch(vec![path.clone()], mock_client.clone(), status.clone(), backoff, heartbeat()).await.unwrap();
+                } => true,
+                _ = async {
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    std::fs::write(&path, "Goodbye Stranger!").unwrap();
+                    tokio::time::sleep(Duration::from_millis(200)).await;
+                } => false,
+            };
+            assert!(!finished);
+            assert!(status.is_failing());
+            assert!(status.failures() > 1);
+            assert_eq!(status.last_error(), Some("invalid token".to_string()));
+        }
+
+        #[tokio::test]
+        async fn refreshes_token_when_a_file_is_replaced() {
+            let path = format!("./test_files/test-watch-file-{}", Uuid::new_v4());
+            let replacement = format!("./test_files/test-watch-file-{}", Uuid::new_v4());
+            let mock_client = Arc::new(RwLock::new(Mock::new()));
+            std::fs::write(&path, "Hello World!").unwrap();
+            tokio::select! {
+                _ = async {
+                    watch(vec![path.clone()], mock_client.clone(), Arc::new(RefreshStatus::default()), Backoff::default(), heartbeat()).await.unwrap();
+                } => (),
+                _ = async {
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    std::fs::write(&replacement, "Goodbye Stranger!").unwrap();
+                    std::fs::rename(&replacement, &path).unwrap();
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    std::fs::write(&path, "Hello Again!").unwrap();
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                } => (),
+            }
+            assert_eq!(mock_client.read().await.refreshes(), 2);
+        }
+
+        #[tokio::test]
+        async fn ignores_other_files_in_the_same_directory() {
+            let path = format!("./test_files/test-watch-file-{}", Uuid::new_v4());
+            let other = format!("./test_files/test-watch-file-{}", Uuid::new_v4());
+            let mock_client = Arc::new(RwLock::new(Mock::new()));
+            std::fs::write(&path, "Hello World!").unwrap();
+            tokio::select! {
+                _ = async {
+                    watch(vec![path.clone()], mock_client.clone(), Arc::new(RefreshStatus::default()), Backoff::default(), heartbeat()).await.unwrap();
+                } => (),
+                _ = async {
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    std::fs::write(&other, "Goodbye Stranger!").unwrap();
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                } => (),
+            }
+            assert!(!mock_client.read().await.called());
+        }
+
+        #[tokio::test]
+        async fn refreshes_expiring_tokens_without_file_changes() {
+            let mock_client = Arc::new(RwLock::new(Mock::expiring(Duration::from_millis(20))));
+            let status = Arc::new(RefreshStatus::default());
+            tokio::select! {
+                _ = async {
+                    watch(vec![], mock_client.clone(), status.clone(), Backoff::default(), heartbeat()).await.unwrap();
+                } => (),
+                _ = tokio::time::sleep(Duration::from_millis(100)) => (),
+            }
+            assert!(mock_client.read().await.refreshes() > 1);
+            assert!(!status.is_failing());
+        }
     }
 }
@@ -0,0 +1,324 @@
+use crate::utilities::environment::Environment;
+use crate::utilities::source::Source;
+use base64::{prelude::BASE64_STANDARD, Engine};
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use tracing::{debug, instrument};
+use url::{form_urlencoded, Url};
+use vaultrs::error::ClientError;
+use zeroize::Zeroizing;
+
+const ALGORITHM: &str = "AWS4-HMAC-SHA256";
+const SERVICE: &str = "sts";
+const STS_API_VERSION: &str = "2011-06-15";
+const CONTENT_TYPE: &str = "application/x-www-form-urlencoded; charset=utf-8";
+const IAM_SERVER_ID_HEADER: &str = "X-Vault-AWS-IAM-Server-ID";
+const DEFAULT_SESSION_NAME: &str = "vault-kms-provider";
+
+#[derive(Clone, PartialEq)]
+pub struct AwsCredentials {
+    pub access_key_id: String,
+    pub secret_access_key: Zeroizing<String>,
+    pub session_token: Option<Zeroizing<String>>,
+}
+
+/// A signed `sts:GetCallerIdentity` request, encoded the way Vault's `auth/aws/login` expects it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct IamRequest {
+    pub method: String,
+    pub url: String,
+    pub headers: String,
+    pub body: String,
+}
+
+fn sts_error(message: String) -> ClientError {
+    ClientError::APIError {
+        code: 500,
+        errors: vec![message],
+    }
+}
+
+fn parse_url(url: &str) -> Result<Url, ClientError> {
+    Url::parse(url).map_err(|error| sts_error(format!("Invalid STS endpoint {}: {}", url, error)))
+}
+
+fn xml_value(xml: &str, tag: &str) -> Option<String> {
+    let start = xml.find(&format!("<{}>", tag))? + tag.len() + 2;
+    let end = start + xml[start..].find(&format!("</{}>", tag))?;
+    Some(xml[start..end].to_string())
+}
+
+fn hmac(key: &[u8], message: &str) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(message.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn host(url: &Url) -> String {
+    let host = url.host_str().unwrap_or_default();
+    url.port()
+        .map(|port| format!("{}:{}", host, port))
+        .unwrap_or(host.to_string())
+}
+
+impl AwsCredentials {
+    #[instrument(skip_all)]
+    pub async fn from_env(http: &reqwest::Client, sts_endpoint: &str) -> Result<Self, ClientError> {
+        if let Some((access_key_id, secret_access_key)) = Environment::AwsAccessKeyId
+            .get()
+            .zip(Environment::AwsSecretAccessKey.get())
+        {
+            debug!("Using AWS credentials from the environment");
+            Ok(Self {
+                access_key_id,
+                secret_access_key: Zeroizing::new(secret_access_key),
+                session_token: Environment::AwsSessionToken.get().map(Zeroizing::new),
+            })
+        } else if let Some((token_file, role_arn)) = Environment::AwsWebIdentityTokenFile
+            .get()
+            .zip(Environment::AwsRoleArn.get())
+        {
+            debug!("Using AWS web identity credentials for role: {}", role_arn);
+            Self::assume_role_with_web_identity(
+                http,
+                sts_endpoint,
+                &role_arn,
+                &Source::FilePath(token_file).value()?,
+                &Environment::AwsRoleSessionName.silent_or(DEFAULT_SESSION_NAME),
+            )
+            .await
+        } else {
+            Err(sts_error("No AWS credentials found".to_string()))
+        }
+    }
+
+    /// Exchanges a web identity token for temporary credentials. The token is sent in the form encoded body, so it
+    /// does not end up in the access logs of proxies between the provider and STS.
+    #[instrument(skip(http, sts_endpoint, token))]
+    pub async fn assume_role_with_web_identity(
+        http: &reqwest::Client,
+        sts_endpoint: &str,
+        role_arn: &str,
+        token: &str,
+        session_name: &str,
+    ) -> Result<Self, ClientError> {
+        let body = form_urlencoded::Serializer::new(String::new())
+            .append_pair("Action", "AssumeRoleWithWebIdentity")
+            .append_pair("Version", STS_API_VERSION)
+            .append_pair("RoleArn", role_arn)
+            .append_pair("RoleSessionName", session_name)
+            .append_pair("WebIdentityToken", token)
+            .finish();
+        let response = http
+            .post(parse_url(sts_endpoint)?)
+            .header(reqwest::header::CONTENT_TYPE, CONTENT_TYPE)
+            .body(body)
+            .send()
+            .await
+            .map_err(|error| sts_error(format!("Failed to reach STS: {}", error)))?;
+        let status = response.status();
+        let body = response
+            .text()
+            .await
+            .map_err(|error| sts_error(format!("Failed to read STS response: {}", error)))?;
+        if !status.is_success() {
+            return Err(ClientError::APIError {
+                code: status.as_u16(),
+                errors: vec![xml_value(&body, "Message").unwrap_or(body)],
+            });
+        }
+        match (
+            xml_value(&body, "AccessKeyId"),
+            xml_value(&body, "SecretAccessKey"),
+        ) {
+            (Some(access_key_id), Some(secret_access_key)) => Ok(Self {
+                access_key_id,
+                secret_access_key: Zeroizing::new(secret_access_key),
+                session_token: xml_value(&body, "SessionToken").map(Zeroizing::new),
+            }),
+            _ => Err(sts_error(
+                "STS response did not contain credentials".to_string(),
+            )),
+        }
+    }
+
+    fn signing_key(&self, date: &str, region: &str, service: &str) -> Vec<u8> {
+        [date, region, service, "aws4_request"].iter().fold(
+            format!("AWS4{}", *self.secret_access_key).into_bytes(),
+            |key, message| hmac(&key, message),
+        )
+    }
+
+    /// Adds the `X-Amz-Date` and `Authorization` headers for a Signature Version 4 signed request.
+    pub fn sign(
+        &self,
+        method: &str,
+        url: &Url,
+        headers: &mut BTreeMap<String, String>,
+        body: &str,
+        (region, service): (&str, &str),
+        time: DateTime<Utc>,
+    ) {
+        let timestamp = time.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = time.format("%Y%m%d").to_string();
+        headers.insert("X-Amz-Date".to_string(), timestamp.clone());
+        let canonical_headers: BTreeMap<String, String> = headers
+            .iter()
+            .map(|(name, value)| (name.to_lowercase(), value.trim().to_string()))
+            .collect();
+        let signed_headers = canonical_headers
+            .keys()
+            .cloned()
+            .collect::<Vec<String>>()
+            .join(";");
+        let mut query: Vec<&str> = url
+            .query()
+            .map(|query| query.split('&').collect())
+            .unwrap_or_default();
+        query.sort();
+        let canonical_request = [
+            method.to_string(),
+            url.path().to_string(),
+            query.join("&"),
+            canonical_headers
+                .iter()
+                .map(|(name, value)| format!("{}:{}\n", name, value))
+                .collect::<String>(),
+            signed_headers.clone(),
+            hex::encode(Sha256::digest(body.as_bytes())),
+        ]
+        .join("\n");
+        let scope = format!("{}/{}/{}/aws4_request", date, region, service);
+        let string_to_sign = [
+            ALGORITHM.to_string(),
+            timestamp,
+            scope.clone(),
+            hex::encode(Sha256::digest(canonical_request.as_bytes())),
+        ]
+        .join("\n");
+        let signature = hex::encode(hmac(
+            &self.signing_key(&date, region, service),
+            &string_to_sign,
+        ));
+        headers.insert(
+            "Authorization".to_string(),
+            format!(
+                "{} Credential={}/{}, SignedHeaders={}, Signature={}",
+                ALGORITHM, self.access_key_id, scope, signed_headers, signature
+            ),
+        );
+    }
+
+    pub fn get_caller_identity(
+        &self,
+        sts_endpoint: &str,
+        region: &str,
+        header_value: Option<String>,
+        time: DateTime<Utc>,
+    ) -> Result<IamRequest, ClientError> {
+        let url = parse_url(sts_endpoint)?;
+        let body = format!("Action=GetCallerIdentity&Version={}", STS_API_VERSION);
+        let mut headers = BTreeMap::from([
+            ("Host".to_string(), host(&url)),
+            ("Content-Type".to_string(), CONTENT_TYPE.to_string()),
+        ]);
+        if let Some(token) = &self.session_token {
+            headers.insert("X-Amz-Security-Token".to_string(), token.to_string());
+        }
+        if let Some(value) = header_value {
+            headers.insert(IAM_SERVER_ID_HEADER.to_string(), value);
+        }
+        self.sign("POST", &url, &mut headers, &body, (region, SERVICE), time);
+        let headers: BTreeMap<String, Vec<String>> = headers
+            .into_iter()
+            .map(|(name, value)| (name, vec![value]))
+            .collect();
+        Ok(IamRequest {
+            method: "POST".to_string(),
+            url: BASE64_STANDARD.encode(url.as_str()),
+            headers: BASE64_STANDARD.encode(
+                serde_json::to_string(&headers)
+                    .map_err(|source| ClientError::JsonParseError { source })?,
+            ),
+            body: BASE64_STANDARD.encode(body),
+        })
+    }
+}
+
+#[cfg(test)]
+mod aws {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn credentials(session_token: Option<String>) -> AwsCredentials {
+        AwsCredentials {
+            access_key_id: "AKIDEXAMPLE".to_string(),
+            secret_access_key: Zeroizing::new(
+                "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY".to_string(),
+            ),
+            session_token: session_token.map(Zeroizing::new),
+        }
+    }
+
+    fn time() -> DateTime<Utc> {
+        "2015-08-30T12:36:00Z".parse::<DateTime<Utc>>().unwrap()
+    }
+
+    #[test]
+    fn signs_requests_with_signature_version_4() {
+        let url =
+            Url::parse("https://iam.amazonaws.com/?Action=ListUsers&Version=2010-05-08").unwrap();
+        let mut headers = BTreeMap::from([
+            ("Host".to_string(), "iam.amazonaws.com".to_string()),
+            ("Content-Type".to_string(), CONTENT_TYPE.to_string()),
+        ]);
+        credentials(None).sign("GET", &url, &mut headers, "", ("us-east-1", "iam"), time());
+        assert_eq!(
+            headers.get("Authorization").unwrap(),
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/iam/aws4_request, SignedHeaders=content-type;host;x-amz-date, Signature=5d672d79c15b13162d9279b0855cfba6789a8edb4c82c400e06b5924a6f2b5d7"
+        );
+    }
+
+    #[test]
+    fn encodes_get_caller_identity_requests_for_vault() {
+        let request = credentials(Some("session".to_string()))
+            .get_caller_identity(
+                "https://sts.amazonaws.com",
+                "us-east-1",
+                Some("vault.example.com".to_string()),
+                time(),
+            )
+            .unwrap();
+        let headers: BTreeMap<String, Vec<String>> =
+            serde_json::from_slice(&BASE64_STANDARD.decode(request.headers).unwrap()).unwrap();
+        assert_eq!(request.method, "POST");
+        assert_eq!(
+            BASE64_STANDARD.decode(request.url).unwrap(),
+            b"https://sts.amazonaws.com/"
+        );
+        assert_eq!(
+            BASE64_STANDARD.decode(request.body).unwrap(),
+            b"Action=GetCallerIdentity&Version=2011-06-15"
+        );
+        assert_eq!(
+            headers.get(IAM_SERVER_ID_HEADER),
+            Some(&vec!["vault.example.com".to_string()])
+        );
+        assert_eq!(
+            headers.get("X-Amz-Security-Token"),
+            Some(&vec!["session".to_string()])
+        );
+        assert!(headers.get("Authorization").unwrap()[0].contains(
+            "SignedHeaders=content-type;host;x-amz-date;x-amz-security-token;x-vault-aws-iam-server-id"
+        ));
+    }
+
+    #[test]
+    fn extracts_values_from_sts_responses() {
+        let xml = "<Credentials><AccessKeyId>ASIA</AccessKeyId></Credentials>";
+        assert_eq!(xml_value(xml, "AccessKeyId"), Some("ASIA".to_string()));
+        assert_eq!(xml_value(xml, "SessionToken"), None);
+    }
+}
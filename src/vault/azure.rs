@@ -0,0 +1,102 @@
+use crate::vault::metadata;
+use rustify_derive::Endpoint;
+use serde_json::Value;
+use tracing::{debug, instrument};
+use vaultrs::error::ClientError;
+
+const TOKEN_API_VERSION: &str = "2018-02-01";
+const INSTANCE_API_VERSION: &str = "2021-02-01";
+const METADATA_HEADER: (&str, &str) = ("Metadata", "true");
+
+/// ## Login with Azure
+/// Issues a Vault token for a managed identity's access token and the instance it was issued to.
+///
+/// * Path: /auth/{self.mount}/login
+/// * Method: POST
+/// * Reference: <https://developer.hashicorp.com/vault/api-docs/auth/azure#login>
+#[derive(Debug, Endpoint)]
+#[endpoint(path = "/auth/{self.mount}/login", method = "POST")]
+pub struct LoginRequest {
+    #[endpoint(skip)]
+    pub mount: String,
+    pub role: String,
+    pub jwt: String,
+    pub subscription_id: String,
+    pub resource_group_name: String,
+    pub vm_name: Option<String>,
+    pub vmss_name: Option<String>,
+}
+
+fn field(value: &Value, name: &str) -> Option<String> {
+    value[name]
+        .as_str()
+        .filter(|value| !value.is_empty())
+        .map(str::to_string)
+}
+
+fn missing(name: &str) -> ClientError {
+    metadata::metadata_error(format!(
+        "Azure instance metadata did not contain \"{}\"",
+        name
+    ))
+}
+
+#[instrument(skip(http, mount, role))]
+pub async fn login_request(
+    http: &reqwest::Client,
+    metadata_endpoint: &str,
+    resource: &str,
+    mount: String,
+    role: String,
+) -> Result<LoginRequest, ClientError> {
+    let token: Value = serde_json::from_str(
+        &metadata::get(
+            http,
+            metadata_endpoint,
+            "/metadata/identity/oauth2/token",
+            &[("api-version", TOKEN_API_VERSION), ("resource", resource)],
+            METADATA_HEADER,
+        )
+        .await?,
+    )
+    .map_err(|source| ClientError::JsonParseError { source })?;
+    let instance: Value = serde_json::from_str(
+        &metadata::get(
+            http,
+            metadata_endpoint,
+            "/metadata/instance",
+            &[("api-version", INSTANCE_API_VERSION)],
+            METADATA_HEADER,
+        )
+        .await?,
+    )
+    .map_err(|source| ClientError::JsonParseError { source })?;
+    let compute = &instance["compute"];
+    debug!("Retrieved Azure managed identity token and instance metadata");
+    Ok(LoginRequest {
+        mount,
+        role,
+        jwt: field(&token, "access_token").ok_or_else(|| missing("access_token"))?,
+        subscription_id: field(compute, "subscriptionId")
+            .ok_or_else(|| missing("subscriptionId"))?,
+        resource_group_name: field(compute, "resourceGroupName")
+            .ok_or_else(|| missing("resourceGroupName"))?,
+        vm_name: field(compute, "name"),
+        vmss_name: field(compute, "vmScaleSetName"),
+    })
+}
+
+#[cfg(test)]
+mod azure {
+    use super::field;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    #[test]
+    fn treats_empty_metadata_fields_as_missing() {
+        let compute = json!({"name": "vm", "vmScaleSetName": ""});
+        assert_eq!(field(&compute, "name"), Some("vm".to_string()));
+        assert_eq!(field(&compute, "vmScaleSetName"), None);
+        assert_eq!(field(&compute, "subscriptionId"), None);
+    }
+}
@@ -1,21 +1,75 @@
 use crate::configuration::authentication::{
-    AppRole, Certificate, Credentials, Jwt, Kubernetes, UserPass,
+    AppRole, Aws, Azure, Certificate, Credentials, Gcp, Jwt, JwtSource, Kubernetes, UserPass,
 };
 use crate::configuration::vault::VaultConfiguration;
-use crate::utilities::watcher::Refresh;
+use crate::utilities::backoff::Backoff;
+use crate::utilities::redact::Redacted;
+use crate::vault::aws::AwsCredentials;
 use crate::vault::keys::KeyInfo;
+use crate::vault::rate_limit::{TokenBucket, TOO_MANY_REQUESTS};
+use crate::vault::request::{self, ExecuteError};
+use crate::vault::{azure, gcp, jwt, wrapping};
+use chrono::Utc;
+use rustify::endpoint::Endpoint;
 use std::string::ToString;
-use tonic::{async_trait, Code, Status};
-use tracing::{debug, instrument, warn};
+use std::sync::Mutex;
+use std::time::Duration;
+use tonic::{Code, Status};
+use tracing::{debug, info, instrument, warn};
+use vaultrs::api::transit::requests::{DecryptDataRequest, EncryptDataRequest, ReadKeyRequest};
 use vaultrs::client::{Client as ClientTrait, VaultClient};
-use vaultrs::{api::AuthInfo, error::ClientError, transit};
+use vaultrs::{api::AuthInfo, error::ClientError};
+use zeroize::{Zeroize, Zeroizing};
+
+const RATE_LIMIT_INITIAL_DELAY: Duration = Duration::from_millis(250);
+const RATE_LIMIT_MAXIMUM_DELAY: Duration = Duration::from_secs(10);
+/// Standby nodes forward requests to the active node, so they are reported as healthy.
+const HEALTH_PATH: &str = "v1/sys/health?standbyok=true&perfstandbyok=true";
 
 #[derive(Debug)]
 pub struct VaultError(pub ClientError);
 
+fn no_token_found() -> ClientError {
+    ClientError::APIError {
+        code: 500,
+        errors: vec!["No token found".to_string()],
+    }
+}
+
+impl VaultError {
+    /// Whether the request was abandoned because it exceeded the configured connect or request timeout.
+    pub fn is_timeout(&self) -> bool {
+        match &self.0 {
+            ClientError::RestClientError {
+                source: rustify::errors::ClientError::RequestError { source, .. },
+            } => source
+                .downcast_ref::<reqwest::Error>()
+                .is_some_and(reqwest::Error::is_timeout),
+            _ => false,
+        }
+    }
+
+    /// Whether Vault kept rejecting the request because of a rate limit quota.
+    pub fn is_rate_limited(&self) -> bool {
+        match &self.0 {
+            ClientError::APIError { code, .. } => *code == TOO_MANY_REQUESTS,
+            ClientError::RestClientError {
+                source: rustify::errors::ClientError::ServerResponseError { code, .. },
+            } => *code == TOO_MANY_REQUESTS,
+            _ => false,
+        }
+    }
+}
+
 impl From<VaultError> for Status {
     fn from(value: VaultError) -> Self {
-        Status::new(Code::Internal, value.0.to_string())
+        if value.is_timeout() {
+            Status::new(Code::DeadlineExceeded, value.0.to_string())
+        } else if value.is_rate_limited() {
+            Status::new(Code::Unavailable, value.0.to_string())
+        } else {
+            Status::new(Code::Internal, value.0.to_string())
+        }
     }
 }
 
@@ -27,22 +81,40 @@ impl From<ClientError> for VaultError {
 
 pub struct Client {
     key_name: String,
-    auth: Credentials,
+    auth: Vec<Credentials>,
     client: VaultClient,
     mount_path: String,
+    refresh_in: Option<Duration>,
+    rate_limiter: Option<TokenBucket>,
+    rate_limit_retries: u32,
+    /// Client for requests made to log in that are not sent to Vault, ex: to AWS STS or an instance metadata service.
+    http: reqwest::Client,
+    /// Unwrapped AppRole secret_id, kept in memory only and keyed by the wrapping token it came from.
+    unwrapped_secret_id: Mutex<Option<(Zeroizing<String>, Zeroizing<String>)>>,
+}
+
+impl Drop for Client {
+    fn drop(&mut self) {
+        self.clear_token();
+    }
 }
 
-#[async_trait]
-impl Refresh for Client {
+impl Client {
+    /// Logs in and uses the new token for the following requests.
     #[instrument(skip(self))]
-    async fn refresh_token(&mut self) -> Result<(), std::io::Error> {
-        let token = self
-            .get_token()
+    pub async fn refresh_token(&mut self) -> Result<(), std::io::Error> {
+        let (token, refresh_in) = self
+            .login()
             .await
             .map_err(|error| std::io::Error::other(error.to_string()))?;
-        self.client.set_token(&token);
+        self.use_token(&token, refresh_in);
         Ok(())
     }
+
+    /// Time until the current token should be refreshed.
+    pub fn refresh_in(&self) -> Option<Duration> {
+        self.refresh_in
+    }
 }
 
 impl Client {
@@ -54,8 +126,8 @@ impl Client {
         debug!("Logging in with kubernetes auth: {:?}", credentials);
         Ok(vaultrs::auth::kubernetes::login(
             &self.client,
-            &credentials.mount_path,
-            &credentials.role,
+            &credentials.mount_path.trimmed()?,
+            &credentials.role.trimmed()?,
             &credentials.jwt.value()?,
         )
         .await?)
@@ -64,13 +136,24 @@ impl Client {
     #[instrument(skip(self, credentials))]
     async fn jwt_authentication(&self, credentials: &Jwt) -> Result<AuthInfo, ClientError> {
         debug!("Logging in with JWT authentication: {:?}", credentials);
-        Ok(vaultrs::auth::oidc::login(
+        let token = match &credentials.jwt {
+            JwtSource::Token(source) => source.value()?,
+            JwtSource::Signed(signer) => Zeroizing::new(jwt::sign(signer, Utc::now())?),
+        };
+        vaultrs::api::auth(
             &self.client,
-            &credentials.mount_path,
-            &credentials.jwt.value()?,
-            credentials.role.clone(),
+            jwt::LoginRequest {
+                mount: credentials.mount_path.trimmed()?.to_string(),
+                role: credentials
+                    .role
+                    .as_ref()
+                    .map(|role| role.trimmed())
+                    .transpose()?
+                    .map(|role| role.to_string()),
+                jwt: token.to_string(),
+            },
         )
-        .await?)
+        .await
     }
 
     #[instrument(skip(self, credentials))]
@@ -79,37 +162,138 @@ impl Client {
         credentials: &Certificate,
     ) -> Result<AuthInfo, ClientError> {
         debug!("Logging in with JWT authentication: {:?}", credentials);
-        Ok(
-            vaultrs::auth::cert::login(&self.client, &credentials.mount_path, &credentials.name)
-                .await?,
+        Ok(vaultrs::auth::cert::login(
+            &self.client,
+            &credentials.mount_path.trimmed()?,
+            &credentials.name.trimmed()?,
+        )
+        .await?)
+    }
+
+    #[instrument(skip(self, credentials))]
+    async fn aws_authentication(&self, credentials: &Aws) -> Result<AuthInfo, ClientError> {
+        debug!("Logging in with AWS IAM authentication: {:?}", credentials);
+        let request = AwsCredentials::from_env(&self.http, &credentials.sts_endpoint)
+            .await?
+            .get_caller_identity(
+                &credentials.sts_endpoint,
+                &credentials.region,
+                credentials
+                    .header_value
+                    .as_ref()
+                    .map(|value| value.trimmed())
+                    .transpose()?
+                    .map(|value| value.to_string()),
+                Utc::now(),
+            )?;
+        vaultrs::auth::aws::iam_login(
+            &self.client,
+            &credentials.mount_path.trimmed()?,
+            &request.method,
+            &request.url,
+            &request.headers,
+            &request.body,
+            credentials
+                .role
+                .as_ref()
+                .map(|role| role.trimmed())
+                .transpose()?
+                .as_deref()
+                .map(String::as_str),
+        )
+        .await
+    }
+
+    #[instrument(skip(self, credentials))]
+    async fn azure_authentication(&self, credentials: &Azure) -> Result<AuthInfo, ClientError> {
+        debug!("Logging in with Azure authentication: {:?}", credentials);
+        let request = azure::login_request(
+            &self.http,
+            &credentials.metadata_endpoint,
+            &credentials.resource,
+            credentials.mount_path.trimmed()?.to_string(),
+            credentials.role.trimmed()?.to_string(),
+        )
+        .await?;
+        vaultrs::api::auth(&self.client, request).await
+    }
+
+    #[instrument(skip(self, credentials))]
+    async fn gcp_authentication(&self, credentials: &Gcp) -> Result<AuthInfo, ClientError> {
+        debug!("Logging in with GCP authentication: {:?}", credentials);
+        let role = credentials.role.trimmed()?;
+        let jwt = match &credentials.service_account_key {
+            Some(key) => gcp::service_account_jwt(&key.value()?, &role, Utc::now())?,
+            None => gcp::instance_jwt(&self.http, &credentials.metadata_endpoint, &role).await?,
+        };
+        vaultrs::api::auth(
+            &self.client,
+            gcp::LoginRequest {
+                mount: credentials.mount_path.trimmed()?.to_string(),
+                role: role.to_string(),
+                jwt,
+            },
         )
+        .await
     }
 
+    /// Logs in with the credentials, returning the token and its lease, which is zero for tokens that do not expire.
+    #[instrument(skip(self, credentials))]
+    async fn authenticate(
+        &self,
+        credentials: &Credentials,
+    ) -> Result<(Zeroizing<String>, Duration), ClientError> {
+        let auth = match credentials {
+            Credentials::Token(token) => return Ok((token.trimmed()?, Duration::ZERO)),
+            Credentials::Kubernetes(credentials) => {
+                self.kubernetes_authentication(credentials).await?
+            }
+            Credentials::UserPass(credentials) => {
+                self.user_pass_authentication(credentials).await?
+            }
+            Credentials::AppRole(credentials) => self.app_role_authentication(credentials).await?,
+            Credentials::Jwt(jwt) => self.jwt_authentication(jwt).await?,
+            Credentials::Certificate(credentials) => self.cert_authentication(credentials).await?,
+            Credentials::Aws(credentials) => self.aws_authentication(credentials).await?,
+            Credentials::Azure(credentials) => self.azure_authentication(credentials).await?,
+            Credentials::Gcp(credentials) => self.gcp_authentication(credentials).await?,
+            Credentials::Agent => return Ok((Zeroizing::new(String::new()), Duration::ZERO)),
+            Credentials::None => return Err(no_token_found()),
+        };
+        Ok((
+            Zeroizing::new(auth.client_token),
+            Duration::from_secs(auth.lease_duration),
+        ))
+    }
+
+    /// Tries each configured method in turn, returning the first token along with when it should be renewed.
     #[instrument(skip(self))]
-    pub async fn get_token(&self) -> Result<String, ClientError> {
-        match &self.auth {
-            Credentials::Token(token) => Ok(token.value()?),
-            Credentials::Kubernetes(credentials) => Ok(self
-                .kubernetes_authentication(credentials)
-                .await?
-                .client_token),
-            Credentials::UserPass(credentials) => Ok(self
-                .user_pass_authentication(credentials)
-                .await?
-                .client_token),
-            Credentials::AppRole(credentials) => Ok(self
-                .app_role_authentication(credentials)
-                .await?
-                .client_token),
-            Credentials::Jwt(jwt) => Ok(self.jwt_authentication(jwt).await?.client_token),
-            Credentials::Certificate(credentials) => {
-                Ok(self.cert_authentication(credentials).await?.client_token)
+    pub(crate) async fn login(&self) -> Result<(Zeroizing<String>, Option<Duration>), ClientError> {
+        let mut last_error = no_token_found();
+        for credentials in &self.auth {
+            match self.authenticate(credentials).await {
+                Ok((token, lease_duration)) => {
+                    info!(
+                        "Authenticated with Vault using {} authentication",
+                        credentials
+                    );
+                    return Ok((token, credentials.refresh_in(lease_duration)));
+                }
+                Err(error) => {
+                    warn!(
+                        "Failed to authenticate with Vault using {} authentication: {}",
+                        credentials, error
+                    );
+                    last_error = error;
+                }
             }
-            Credentials::None => Err(ClientError::APIError {
-                code: 500,
-                errors: vec!["No token found".to_string()],
-            }),
         }
+        Err(last_error)
+    }
+
+    #[instrument(skip(self))]
+    pub async fn get_token(&self) -> Result<Zeroizing<String>, ClientError> {
+        Ok(self.login().await?.0)
     }
 
     pub fn new(client: VaultClient, config: &VaultConfiguration) -> Self {
@@ -118,6 +302,18 @@ impl Client {
             auth: config.credentials.clone(),
             mount_path: config.mount_path.clone(),
             client,
+            refresh_in: None,
+            rate_limiter: config
+                .rate_limit
+                .rate
+                .map(|rate| TokenBucket::new(rate, config.rate_limit.burst)),
+            rate_limit_retries: config.rate_limit.retries,
+            http: reqwest::Client::builder()
+                .connect_timeout(config.timeouts.connect)
+                .timeout(config.timeouts.request)
+                .build()
+                .expect("Unable to build HTTP client"),
+            unwrapped_secret_id: Mutex::new(None),
         }
     }
 
@@ -129,8 +325,8 @@ impl Client {
         debug!("Logging in with UserPass credentials: {:?}", credentials);
         Ok(vaultrs::auth::userpass::login(
             &self.client,
-            &credentials.mount_path,
-            &credentials.username,
+            &credentials.mount_path.trimmed()?,
+            &credentials.username.trimmed()?,
             &credentials.password.value()?,
         )
         .await?)
@@ -142,48 +338,225 @@ impl Client {
         credentials: &AppRole,
     ) -> Result<AuthInfo, ClientError> {
         debug!("Logging in with AppRole credentials: {:?}", credentials);
+        let secret_id = if credentials.wrapped {
+            self.unwrap_secret_id(&credentials.secret_id.value()?)
+                .await?
+        } else {
+            credentials.secret_id.value()?
+        };
         Ok(vaultrs::auth::approle::login(
             &self.client,
-            &credentials.mount_path,
-            &credentials.role_id,
-            &credentials.secret_id.value()?,
+            &credentials.mount_path.trimmed()?,
+            &credentials.role_id.trimmed()?,
+            &secret_id,
         )
         .await?)
     }
 
+    /// Wrapping tokens can only be used once, so the secret_id is unwrapped when the token
+    /// changes and reused for every login until then.
+    #[instrument(skip_all)]
+    async fn unwrap_secret_id(
+        &self,
+        wrapping_token: &str,
+    ) -> Result<Zeroizing<String>, ClientError> {
+        if let Some((token, secret_id)) = self.unwrapped_secret_id.lock().unwrap().as_ref() {
+            if token.as_str() == wrapping_token {
+                debug!("Using previously unwrapped AppRole secret_id");
+                return Ok(secret_id.clone());
+            }
+        }
+        let secret_id = wrapping::unwrap_secret_id(&self.client, wrapping_token).await?;
+        *self.unwrapped_secret_id.lock().unwrap() = Some((
+            Zeroizing::new(wrapping_token.to_string()),
+            secret_id.clone(),
+        ));
+        Ok(secret_id)
+    }
+
+    /// Sends a transit request, waiting for the rate limiter and retrying requests rejected by a
+    /// Vault rate limit quota after the delay Vault asks for.
+    async fn transit<E: Endpoint>(&self, endpoint: &E) -> Result<E::Response, VaultError> {
+        let mut backoff = Backoff::new(RATE_LIMIT_INITIAL_DELAY, RATE_LIMIT_MAXIMUM_DELAY);
+        let mut retries = 0;
+        loop {
+            if let Some(rate_limiter) = &self.rate_limiter {
+                rate_limiter.acquire().await;
+            }
+            match request::execute(&self.client, endpoint).await {
+                Ok(response) => return Ok(response),
+                Err(ExecuteError::RateLimited { retry_after, .. })
+                    if retries < self.rate_limit_retries =>
+                {
+                    let delay = retry_after
+                        .unwrap_or_else(|| backoff.next_delay())
+                        .min(RATE_LIMIT_MAXIMUM_DELAY);
+                    retries += 1;
+                    warn!(
+                        "Vault rate limit exceeded, retrying in {:?} ({}/{})",
+                        delay, retries, self.rate_limit_retries
+                    );
+                    tokio::time::sleep(delay).await;
+                }
+                Err(ExecuteError::RateLimited { error, .. }) => {
+                    warn!(
+                        "Vault rate limit exceeded, giving up after {} retries",
+                        retries
+                    );
+                    return Err(error.into());
+                }
+                Err(ExecuteError::Failed(error)) => return Err(error.into()),
+            }
+        }
+    }
+
     #[instrument(skip(self))]
     pub async fn request_key(&self) -> Result<KeyInfo, VaultError> {
-        Ok(
-            transit::key::read(&self.client, &self.mount_path, &self.key_name)
-                .await?
-                .keys
-                .into(),
-        )
+        let response = self
+            .transit(&ReadKeyRequest {
+                mount: self.mount_path.clone(),
+                name: self.key_name.clone(),
+            })
+            .await?;
+        Ok(KeyInfo {
+            encryptable: response.supports_encryption,
+            ..response.keys.into()
+        })
     }
 
     #[instrument(skip(self, data))]
     pub async fn request_encryption(&self, data: &str) -> Result<String, VaultError> {
-        debug!("Requesting encryption, data: {}", data);
-        Ok(
-            transit::data::encrypt(&self.client, &self.mount_path, &self.key_name, data, None)
-                .await?
-                .ciphertext,
-        )
+        debug!("Requesting encryption of {}", Redacted::new(data));
+        let mut request = EncryptDataRequest {
+            mount: self.mount_path.clone(),
+            name: self.key_name.clone(),
+            plaintext: data.to_string(),
+            ..Default::default()
+        };
+        let response = self.transit(&request).await;
+        request.plaintext.zeroize();
+        Ok(response?.ciphertext)
     }
 
     #[instrument(skip(self, data))]
-    pub async fn request_decryption(&self, data: &str) -> Result<String, VaultError> {
-        debug!("Requesting decryption, data: {}", data);
-        Ok(
-            transit::data::decrypt(&self.client, &self.mount_path, &self.key_name, data, None)
-                .await?
-                .plaintext,
-        )
+    pub async fn request_decryption(&self, data: &str) -> Result<Zeroizing<String>, VaultError> {
+        debug!("Requesting decryption of {}", Redacted::new(data));
+        Ok(Zeroizing::new(
+            self.transit(&DecryptDataRequest {
+                mount: self.mount_path.clone(),
+                name: self.key_name.clone(),
+                ciphertext: data.to_string(),
+                ..Default::default()
+            })
+            .await?
+            .plaintext,
+        ))
+    }
+
+    /// Probes `sys/health`, treating active, standby and performance standby nodes as able to serve requests.
+    #[instrument(skip(self))]
+    pub async fn is_healthy(&self) -> bool {
+        let url = match self.client.settings.address.join(HEALTH_PATH) {
+            Ok(url) => url,
+            Err(error) => {
+                warn!("Invalid Vault health check url: {}", error);
+                return false;
+            }
+        };
+        match self.client.http.http.get(url).send().await {
+            Ok(response) if response.status().is_success() => true,
+            Ok(response) => {
+                debug!("Vault health check returned status: {}", response.status());
+                false
+            }
+            Err(error) => {
+                debug!("Vault health check failed: {}", error);
+                false
+            }
+        }
     }
 
+    /// Replaces the token, clearing the previous one from memory.
     #[instrument(skip(self, token))]
     pub fn set_token(&mut self, token: &str) -> () {
-        debug!("Setting token: {}", token);
+        debug!("Setting token: {}", Redacted::new(token));
+        self.clear_token();
         self.client.set_token(token);
     }
+
+    /// Replaces the token with one returned by `login`, along with when it should be renewed.
+    pub(crate) fn use_token(&mut self, token: &str, refresh_in: Option<Duration>) {
+        self.set_token(token);
+        self.refresh_in = refresh_in;
+    }
+
+    fn clear_token(&mut self) {
+        self.client.settings.token.zeroize();
+        self.client.middle.token.zeroize();
+    }
+}
+
+#[cfg(test)]
+mod client {
+    use super::Client;
+    use crate::configuration::authentication::Credentials;
+    use crate::configuration::vault::{RateLimit, Timeouts, VaultConfiguration};
+    use crate::utilities::source::Source;
+    use pretty_assertions::assert_eq;
+    use vaultrs::client::{VaultClient, VaultClientSettingsBuilder};
+
+    fn client_with_credentials(credentials: Vec<Credentials>) -> Client {
+        let settings = VaultClientSettingsBuilder::default()
+            .address("http://127.0.0.1:8200")
+            .build()
+            .unwrap();
+        let config = VaultConfiguration {
+            credentials,
+            address: "http://127.0.0.1:8200".to_string(),
+            failover_addresses: vec![],
+            timeouts: Timeouts::default(),
+            rate_limit: RateLimit::default(),
+            transit_key: "vault-kms-provider".to_string(),
+            mount_path: "transit".to_string(),
+            canary_interval: None,
+        };
+        Client::new(VaultClient::new(settings).unwrap(), &config)
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_the_next_method_when_authentication_fails() {
+        let client = client_with_credentials(vec![
+            Credentials::Token(Source::FilePath(
+                "./test_files/non-existent-token".to_string(),
+            )),
+            Credentials::Token(Source::Value("break-glass".to_string())),
+        ]);
+        assert_eq!(
+            *client.get_token().await.unwrap(),
+            "break-glass".to_string()
+        );
+    }
+
+    #[tokio::test]
+    async fn uses_the_first_method_that_succeeds() {
+        let client = client_with_credentials(vec![
+            Credentials::Token(Source::Value("first".to_string())),
+            Credentials::Token(Source::Value("second".to_string())),
+        ]);
+        assert_eq!(*client.get_token().await.unwrap(), "first".to_string());
+    }
+
+    #[tokio::test]
+    async fn returns_an_error_if_every_method_fails() {
+        let client = client_with_credentials(vec![Credentials::Token(Source::FilePath(
+            "./test_files/non-existent-token".to_string(),
+        ))]);
+        assert!(client.get_token().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn returns_an_error_if_no_methods_are_configured() {
+        let client = client_with_credentials(vec![]);
+        assert!(client.get_token().await.is_err());
+    }
 }
@@ -0,0 +1,261 @@
+use crate::configuration::tls::TlsConfiguration;
+use crate::configuration::vault::VaultConfiguration;
+use crate::utilities::watcher::Refresh;
+use crate::vault::client::{Client, VaultError};
+use crate::vault::connection::connect;
+use crate::vault::keys::KeyInfo;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::Duration;
+use tokio::sync::{Mutex, RwLock};
+use tonic::async_trait;
+use tracing::{error, info, instrument, warn};
+use vaultrs::error::ClientError;
+use zeroize::Zeroizing;
+
+/// Status codes returned by nodes that cannot serve requests: sealed or in standby without
+/// forwarding, a DR secondary, or an uninitialized node.
+const UNAVAILABLE_STATUS_CODES: [u16; 4] = [472, 501, 502, 503];
+
+/// Whether a request failed because of the node it was sent to, rather than the request itself.
+fn is_unavailable(error: &ClientError) -> bool {
+    match error {
+        ClientError::APIError { code, .. } => UNAVAILABLE_STATUS_CODES.contains(code),
+        ClientError::RestClientError { source } => match source {
+            rustify::errors::ClientError::RequestError { .. } => true,
+            rustify::errors::ClientError::ServerResponseError { code, .. } => {
+                UNAVAILABLE_STATUS_CODES.contains(code)
+            }
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+struct Node {
+    address: String,
+    client: RwLock<Client>,
+    /// Milliseconds until the token of `client` should be refreshed, zero when it is not refreshed
+    /// on a schedule. Kept outside the client lock so it can be read while logging in.
+    refresh_in: AtomicU64,
+}
+
+/// Routes requests to one of several Vault nodes, failing over to the next healthy node when the
+/// active one becomes unreachable or sealed. Tokens are not assumed to be shared between nodes, so
+/// each node is logged into when it becomes active.
+pub struct Cluster {
+    nodes: Vec<Node>,
+    active: AtomicUsize,
+    failover: Mutex<()>,
+}
+
+impl Cluster {
+    pub fn new(nodes: Vec<(String, Client)>) -> Self {
+        Self {
+            nodes: nodes
+                .into_iter()
+                .map(|(address, client)| Node {
+                    address,
+                    client: RwLock::new(client),
+                    refresh_in: AtomicU64::new(0),
+                })
+                .collect(),
+            active: AtomicUsize::new(0),
+            failover: Mutex::new(()),
+        }
+    }
+
+    /// Creates a client for every configured address, the primary address is active initially.
+    pub fn connect(
+        config: &VaultConfiguration,
+        tls: &TlsConfiguration,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(Self::new(
+            config
+                .addresses()
+                .into_iter()
+                .map(|address| {
+                    let client = Client::new(connect(&address, tls, &config.timeouts)?, config);
+                    Ok((address, client))
+                })
+                .collect::<Result<_, Box<dyn std::error::Error>>>()?,
+        ))
+    }
+
+    /// Address of the node requests are currently sent to.
+    pub fn active_address(&self) -> &str {
+        &self.nodes[self.active.load(Ordering::SeqCst)].address
+    }
+
+    /// Logs into the active node.
+    pub async fn authenticate(&self) -> Result<(), std::io::Error> {
+        Self::log_in(&self.nodes[self.active.load(Ordering::SeqCst)]).await
+    }
+
+    /// Logs into `node`, only locking its client to swap the new token in, so requests are served
+    /// with the previous token while logging in.
+    async fn log_in(node: &Node) -> Result<(), std::io::Error> {
+        let login = node.client.read().await.login().await;
+        let (token, refresh_in) =
+            login.map_err(|error| std::io::Error::other(error.to_string()))?;
+        node.client.write().await.use_token(&token, refresh_in);
+        node.refresh_in.store(
+            refresh_in.map_or(0, |refresh_in| refresh_in.as_millis() as u64),
+            Ordering::SeqCst,
+        );
+        Ok(())
+    }
+
+    /// Logs into the next healthy node after `failed` and makes it active, returning whether
+    /// requests should be retried.
+    #[instrument(skip(self))]
+    async fn fail_over(&self, failed: usize) -> bool {
+        let _guard = self.failover.lock().await;
+        if self.active.load(Ordering::SeqCst) != failed {
+            return true;
+        }
+        let candidates = (1..self.nodes.len()).map(|offset| (failed + offset) % self.nodes.len());
+        for index in candidates {
+            let node = &self.nodes[index];
+            if !node.client.read().await.is_healthy().await {
+                warn!("Vault node at {} is not healthy, skipping", node.address);
+                continue;
+            }
+            // Boxed as logging in nests deeply enough to overflow the layout of the request futures.
+            match Box::pin(Self::log_in(node)).await {
+                Ok(()) => {
+                    info!(
+                        "Failed over from Vault node at {} to {}",
+                        self.nodes[failed].address, node.address
+                    );
+                    self.active.store(index, Ordering::SeqCst);
+                    return true;
+                }
+                Err(error) => warn!(
+                    "Failed to authenticate with Vault node at {}: {}",
+                    node.address, error
+                ),
+            }
+        }
+        error!("No healthy Vault node is available to fail over to");
+        false
+    }
+
+    /// Decides whether a failed request should be retried against another node.
+    async fn should_retry(&self, index: usize, error: &VaultError, attempt: usize) -> bool {
+        if attempt >= self.nodes.len() || !is_unavailable(&error.0) {
+            return false;
+        }
+        warn!(
+            "Vault node at {} is unavailable: {}",
+            self.nodes[index].address, error.0
+        );
+        self.fail_over(index).await
+    }
+
+    #[instrument(skip(self))]
+    pub async fn request_key(&self) -> Result<KeyInfo, VaultError> {
+        let mut attempt = 1;
+        loop {
+            let index = self.active.load(Ordering::SeqCst);
+            let result = self.nodes[index].client.read().await.request_key().await;
+            match result {
+                Err(error) if self.should_retry(index, &error, attempt).await => attempt += 1,
+                result => return result,
+            }
+        }
+    }
+
+    #[instrument(skip(self, data))]
+    pub async fn request_encryption(&self, data: &str) -> Result<String, VaultError> {
+        let mut attempt = 1;
+        loop {
+            let index = self.active.load(Ordering::SeqCst);
+            let result = self.nodes[index]
+                .client
+                .read()
+                .await
+                .request_encryption(data)
+                .await;
+            match result {
+                Err(error) if self.should_retry(index, &error, attempt).await => attempt += 1,
+                result => return result,
+            }
+        }
+    }
+
+    #[instrument(skip(self, data))]
+    pub async fn request_decryption(&self, data: &str) -> Result<Zeroizing<String>, VaultError> {
+        let mut attempt = 1;
+        loop {
+            let index = self.active.load(Ordering::SeqCst);
+            let result = self.nodes[index]
+                .client
+                .read()
+                .await
+                .request_decryption(data)
+                .await;
+            match result {
+                Err(error) if self.should_retry(index, &error, attempt).await => attempt += 1,
+                result => return result,
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Refresh for Cluster {
+    /// Renews the token of the active node, moving to a healthy node first if it has become unavailable.
+    #[instrument(skip(self))]
+    async fn refresh_token(&self) -> Result<(), std::io::Error> {
+        let index = self.active.load(Ordering::SeqCst);
+        let node = &self.nodes[index];
+        if self.nodes.len() > 1
+            && !node.client.read().await.is_healthy().await
+            && self.fail_over(index).await
+        {
+            return Ok(());
+        }
+        Self::log_in(node).await
+    }
+
+    fn refresh_in(&self) -> Option<Duration> {
+        match self.nodes[self.active.load(Ordering::SeqCst)]
+            .refresh_in
+            .load(Ordering::SeqCst)
+        {
+            0 => None,
+            millis => Some(Duration::from_millis(millis)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod cluster {
+    use super::*;
+
+    #[test]
+    fn treats_sealed_nodes_as_unavailable() {
+        assert!(is_unavailable(&ClientError::APIError {
+            code: 503,
+            errors: vec!["Vault is sealed".to_string()],
+        }));
+    }
+
+    #[test]
+    fn treats_server_errors_without_a_body_as_unavailable() {
+        assert!(is_unavailable(&ClientError::RestClientError {
+            source: rustify::errors::ClientError::ServerResponseError {
+                code: 503,
+                content: None,
+            },
+        }));
+    }
+
+    #[test]
+    fn does_not_fail_over_on_request_errors() {
+        assert!(!is_unavailable(&ClientError::APIError {
+            code: 403,
+            errors: vec!["permission denied".to_string()],
+        }));
+    }
+}
@@ -0,0 +1,196 @@
+use crate::configuration::tls::TlsConfiguration;
+use crate::configuration::vault::Timeouts;
+use tracing::{debug, info, instrument};
+use vaultrs::client::{VaultClient, VaultClientSettings, VaultClientSettingsBuilder};
+use vaultrs::error::ClientError;
+
+const UNIX_SCHEME: &str = "unix://";
+/// Requests sent over a unix socket still need a URL, only its path is meaningful to the listener.
+const UNIX_SOCKET_ADDRESS: &str = "http://localhost";
+
+/// Splits a `unix:///path/to/socket` address into the URL used for requests and the socket path.
+fn parse_address(address: &str) -> (&str, Option<&str>) {
+    match address.strip_prefix(UNIX_SCHEME) {
+        Some(path) => (UNIX_SOCKET_ADDRESS, Some(path)),
+        None => (address, None),
+    }
+}
+
+/// Builds the HTTP client used for Vault requests. `vaultrs` only supports an overall request timeout,
+/// so the client it creates is replaced with one that also bounds the time spent connecting, keeping the
+/// rest of its settings (`VAULT_SKIP_VERIFY`, the proxy, CA certificates and the client identity).
+fn http_client(
+    settings: &VaultClientSettings,
+    socket: Option<&str>,
+    timeouts: &Timeouts,
+) -> Result<reqwest::Client, ClientError> {
+    let mut builder = reqwest::Client::builder()
+        .tls_backend_rustls()
+        .connect_timeout(timeouts.connect)
+        .timeout(timeouts.request)
+        .tls_danger_accept_invalid_certs(!settings.verify);
+    if let Some(path) = socket {
+        info!("Connecting to Vault over unix socket: {}", path);
+        builder = builder.unix_socket(path);
+    }
+    if let Some(proxy) = &settings.proxy {
+        debug!("Connecting to Vault through proxy: {}", proxy);
+        builder = builder.proxy(
+            reqwest::Proxy::all(proxy.as_str())
+                .map_err(|source| ClientError::RestClientBuildError { source })?,
+        );
+    }
+    if settings.address.scheme() == "https" {
+        for path in &settings.ca_certs {
+            let content = std::fs::read(path).map_err(|source| ClientError::FileReadError {
+                source,
+                path: path.clone(),
+            })?;
+            let certs = reqwest::Certificate::from_pem_bundle(&content).map_err(|source| {
+                ClientError::ParseCertificateError {
+                    source,
+                    path: path.clone(),
+                }
+            })?;
+            debug!("Importing CA certificate from {}", path);
+            builder = builder.tls_certs_merge(certs);
+        }
+    } else {
+        builder = builder.tls_certs_only(Vec::new());
+    }
+    if let Some(identity) = &settings.identity {
+        builder = builder.identity(identity.clone());
+    }
+    builder
+        .build()
+        .map_err(|source| ClientError::RestClientBuildError { source })
+}
+
+/// Creates a client for the Vault server at `address`, either an `http(s)://` URL or a `unix://` socket path.
+#[instrument(skip(tls))]
+pub fn connect(
+    address: &str,
+    tls: &TlsConfiguration,
+    timeouts: &Timeouts,
+) -> Result<VaultClient, Box<dyn std::error::Error>> {
+    let (url, socket) = parse_address(address);
+    let settings = VaultClientSettingsBuilder::default()
+        .address(url)
+        .identity(tls.identity())
+        .ca_certs(tls.certs())
+        .timeout(Some(timeouts.request))
+        .build()?;
+    let mut client = VaultClient::new(settings)?;
+    client.http.http = http_client(&client.settings, socket, timeouts)?;
+    Ok(client)
+}
+
+#[cfg(test)]
+mod connection {
+    use super::*;
+    use crate::checks::tls;
+    use pretty_assertions::assert_eq;
+    use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Responds to a single request with `200`, returning its request line.
+    async fn respond<S: AsyncRead + AsyncWrite + Unpin>(mut stream: S) -> String {
+        let mut request = vec![];
+        let mut buffer = [0; 1024];
+        while !request.ends_with(b"\r\n\r\n") {
+            match stream.read(&mut buffer).await {
+                Ok(0) | Err(_) => break,
+                Ok(read) => request.extend_from_slice(&buffer[..read]),
+            }
+        }
+        let _ = stream
+            .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\nconnection: close\r\n\r\n")
+            .await;
+        let _ = stream.shutdown().await;
+        String::from_utf8_lossy(&request)
+            .lines()
+            .next()
+            .unwrap_or_default()
+            .to_string()
+    }
+
+    fn settings(address: &str, verify: bool, proxy: Option<&str>) -> VaultClientSettings {
+        let mut builder = VaultClientSettingsBuilder::default();
+        builder.address(address).verify(verify).ca_certs(vec![]);
+        if let Some(proxy) = proxy {
+            builder.proxy(proxy);
+        }
+        builder.build().unwrap()
+    }
+
+    /// Serves HTTPS with a certificate that is not signed by a trusted CA.
+    async fn untrusted_server() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = format!("https://{}", listener.local_addr().unwrap());
+        let acceptor =
+            tls::acceptor("test_files/certs/tls.crt", "test_files/certs/tls.key", None).unwrap();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                if let Ok(stream) = acceptor.accept(stream).await {
+                    respond(stream).await;
+                }
+            }
+        });
+        address
+    }
+
+    #[test]
+    fn parses_unix_socket_addresses() {
+        assert_eq!(
+            parse_address("unix:///run/vault/agent.sock"),
+            (UNIX_SOCKET_ADDRESS, Some("/run/vault/agent.sock"))
+        );
+    }
+
+    #[test]
+    fn leaves_http_addresses_unchanged() {
+        assert_eq!(
+            parse_address("https://vault:8200"),
+            ("https://vault:8200", None)
+        );
+    }
+
+    #[tokio::test]
+    async fn accepts_invalid_certificates_when_verification_is_skipped() {
+        let address = untrusted_server().await;
+        let client =
+            http_client(&settings(&address, false, None), None, &Timeouts::default()).unwrap();
+        assert!(client.get(&address).send().await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn rejects_invalid_certificates_by_default() {
+        let address = untrusted_server().await;
+        let client =
+            http_client(&settings(&address, true, None), None, &Timeouts::default()).unwrap();
+        assert!(client.get(&address).send().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn sends_requests_through_the_configured_proxy() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let proxy = format!("http://{}", listener.local_addr().unwrap());
+        let request =
+            tokio::spawn(async move { respond(listener.accept().await.unwrap().0).await });
+        let client = http_client(
+            &settings("http://vault.invalid:8200", true, Some(&proxy)),
+            None,
+            &Timeouts::default(),
+        )
+        .unwrap();
+        client
+            .get("http://vault.invalid:8200/v1/sys/health")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(
+            request.await.unwrap(),
+            "GET http://vault.invalid:8200/v1/sys/health HTTP/1.1"
+        );
+    }
+}
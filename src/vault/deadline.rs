@@ -0,0 +1,106 @@
+use std::future::Future;
+use std::time::Duration;
+use tonic::metadata::MetadataMap;
+use tonic::{Request, Status};
+use tracing::warn;
+
+const GRPC_TIMEOUT_HEADER: &str = "grpc-timeout";
+/// Vault calls are abandoned this long before the caller's deadline so the caller receives
+/// `DeadlineExceeded`, instead of the transport cancelling the call once the deadline passes.
+const DEADLINE_MARGIN: Duration = Duration::from_millis(20);
+
+/// Parses a `grpc-timeout` value, an integer of at most 8 digits followed by a unit.
+fn parse_timeout(value: &str) -> Option<Duration> {
+    if value.len() < 2 || value.len() > 9 || !value.is_ascii() {
+        return None;
+    }
+    let (amount, unit) = value.split_at(value.len() - 1);
+    let amount: u64 = amount.parse().ok()?;
+    match unit {
+        "H" => Some(Duration::from_secs(amount * 60 * 60)),
+        "M" => Some(Duration::from_secs(amount * 60)),
+        "S" => Some(Duration::from_secs(amount)),
+        "m" => Some(Duration::from_millis(amount)),
+        "u" => Some(Duration::from_micros(amount)),
+        "n" => Some(Duration::from_nanos(amount)),
+        _ => None,
+    }
+}
+
+fn timeout_from_metadata(metadata: &MetadataMap) -> Option<Duration> {
+    let value = metadata.get(GRPC_TIMEOUT_HEADER)?.to_str().ok()?;
+    let timeout = parse_timeout(value);
+    if timeout.is_none() {
+        warn!("Ignoring invalid grpc-timeout: {}", value);
+    }
+    timeout
+}
+
+/// Time remaining for Vault calls made on behalf of `request`, if the caller set a deadline.
+pub fn remaining<T>(request: &Request<T>) -> Option<Duration> {
+    timeout_from_metadata(request.metadata()).map(|timeout| timeout.saturating_sub(DEADLINE_MARGIN))
+}
+
+/// Runs `future` until it completes or `timeout` elapses, in which case `DeadlineExceeded` is returned.
+pub async fn within<T, F>(timeout: Option<Duration>, future: F) -> Result<T, Status>
+where
+    F: Future<Output = Result<T, Status>>,
+{
+    match timeout {
+        Some(timeout) => tokio::time::timeout(timeout, future)
+            .await
+            .unwrap_or_else(|_| {
+                warn!("Vault did not respond before the request deadline");
+                Err(Status::deadline_exceeded(
+                    "Vault did not respond before the request deadline",
+                ))
+            }),
+        None => future.await,
+    }
+}
+
+#[cfg(test)]
+mod deadline {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tonic::Code;
+
+    #[test]
+    fn parses_timeouts_in_each_unit() {
+        assert_eq!(parse_timeout("2H"), Some(Duration::from_secs(7200)));
+        assert_eq!(parse_timeout("3M"), Some(Duration::from_secs(180)));
+        assert_eq!(parse_timeout("4S"), Some(Duration::from_secs(4)));
+        assert_eq!(parse_timeout("500m"), Some(Duration::from_millis(500)));
+        assert_eq!(parse_timeout("600u"), Some(Duration::from_micros(600)));
+        assert_eq!(parse_timeout("700n"), Some(Duration::from_nanos(700)));
+    }
+
+    #[test]
+    fn rejects_invalid_timeouts() {
+        assert_eq!(parse_timeout("S"), None);
+        assert_eq!(parse_timeout("123456789S"), None);
+        assert_eq!(parse_timeout("10x"), None);
+    }
+
+    #[test]
+    fn leaves_a_margin_before_the_callers_deadline() {
+        let mut request = Request::new(());
+        request
+            .metadata_mut()
+            .insert(GRPC_TIMEOUT_HEADER, "1S".parse().unwrap());
+        assert_eq!(
+            remaining(&request),
+            Some(Duration::from_secs(1) - DEADLINE_MARGIN)
+        );
+    }
+
+    #[tokio::test]
+    async fn returns_deadline_exceeded_when_the_timeout_elapses() {
+        let result: Result<(), Status> = within(
+            Some(Duration::from_millis(10)),
+            std::future::pending::<Result<(), Status>>(),
+        )
+        .await;
+        assert_eq!(result.unwrap_err().code(), Code::DeadlineExceeded);
+    }
+}
@@ -0,0 +1,114 @@
+use crate::vault::metadata::{self, metadata_error};
+use chrono::{DateTime, Duration, Utc};
+use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
+use rustify_derive::Endpoint;
+use serde_json::{json, Value};
+use tracing::{debug, instrument};
+use vaultrs::error::ClientError;
+
+const IDENTITY_PATH: &str = "/computeMetadata/v1/instance/service-accounts/default/identity";
+const METADATA_HEADER: (&str, &str) = ("Metadata-Flavor", "Google");
+const JWT_TTL: Duration = Duration::minutes(10);
+
+/// ## Login with GCP
+/// Issues a Vault token for a JWT signed by a service account (iam) or issued to an instance (gce).
+///
+/// * Path: /auth/{self.mount}/login
+/// * Method: POST
+/// * Reference: <https://developer.hashicorp.com/vault/api-docs/auth/gcp#login>
+#[derive(Debug, Endpoint)]
+#[endpoint(path = "/auth/{self.mount}/login", method = "POST")]
+pub struct LoginRequest {
+    #[endpoint(skip)]
+    pub mount: String,
+    pub role: String,
+    pub jwt: String,
+}
+
+fn key_field<'a>(key: &'a Value, name: &str) -> Result<&'a str, ClientError> {
+    key[name]
+        .as_str()
+        .ok_or_else(|| metadata_error(format!("Service account key did not contain \"{}\"", name)))
+}
+
+/// Signs a JWT for the `iam` login type using a service account key file.
+pub fn service_account_jwt(
+    key: &str,
+    role: &str,
+    time: DateTime<Utc>,
+) -> Result<String, ClientError> {
+    let key: Value =
+        serde_json::from_str(key).map_err(|source| ClientError::JsonParseError { source })?;
+    let mut header = Header::new(Algorithm::RS256);
+    header.kid = Some(key_field(&key, "private_key_id")?.to_string());
+    let claims = json!({
+        "sub": key_field(&key, "client_email")?,
+        "aud": format!("vault/{}", role),
+        "iat": time.timestamp(),
+        "exp": (time + JWT_TTL).timestamp(),
+    });
+    let signing_key = EncodingKey::from_rsa_pem(key_field(&key, "private_key")?.as_bytes())
+        .map_err(|error| metadata_error(format!("Invalid service account key: {}", error)))?;
+    encode(&header, &claims, &signing_key)
+        .map_err(|error| metadata_error(format!("Failed to sign JWT: {}", error)))
+}
+
+/// Requests an identity token for the `gce` login type from the metadata server.
+#[instrument(skip(http))]
+pub async fn instance_jwt(
+    http: &reqwest::Client,
+    metadata_endpoint: &str,
+    role: &str,
+) -> Result<String, ClientError> {
+    debug!("Requesting instance identity token from the GCP metadata server");
+    metadata::get(
+        http,
+        metadata_endpoint,
+        IDENTITY_PATH,
+        &[
+            ("audience", &format!("http://vault/{}", role)),
+            ("format", "full"),
+        ],
+        METADATA_HEADER,
+    )
+    .await
+}
+
+#[cfg(test)]
+mod gcp {
+    use super::*;
+    use jsonwebtoken::{decode, decode_header, DecodingKey, Validation};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn signs_service_account_jwts_for_the_vault_role() {
+        let private_key = std::fs::read_to_string("./test_files/jwt/private_key.pem").unwrap();
+        let public_key = std::fs::read("./test_files/jwt/public_key.pem").unwrap();
+        let key = json!({
+            "type": "service_account",
+            "private_key_id": "key-id",
+            "private_key": private_key,
+            "client_email": "kms@project.iam.gserviceaccount.com",
+        })
+        .to_string();
+
+        let jwt = service_account_jwt(&key, "vault-kms-provider", Utc::now()).unwrap();
+
+        let mut validation = Validation::new(Algorithm::RS256);
+        validation.set_audience(&["vault/vault-kms-provider"]);
+        let claims = decode::<Value>(
+            &jwt,
+            &DecodingKey::from_rsa_pem(&public_key).unwrap(),
+            &validation,
+        )
+        .unwrap()
+        .claims;
+        assert_eq!(decode_header(&jwt).unwrap().kid, Some("key-id".to_string()));
+        assert_eq!(claims["sub"], "kms@project.iam.gserviceaccount.com");
+    }
+
+    #[test]
+    fn rejects_incomplete_service_account_keys() {
+        assert!(service_account_jwt("{}", "vault-kms-provider", Utc::now()).is_err());
+    }
+}
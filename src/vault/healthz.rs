@@ -0,0 +1,162 @@
+use crate::vault::client::VaultError;
+use std::fmt::{Display, Formatter};
+use vaultrs::error::ClientError;
+
+const FORBIDDEN: u16 = 403;
+const NOT_FOUND: u16 = 404;
+const SERVICE_UNAVAILABLE: u16 = 503;
+
+/// Why the provider cannot serve requests, reported as the `healthz` of `Status` which the API server includes in
+/// its own healthz output. Reasons start with a stable code, ex: `vault_sealed: Vault is sealed`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Unhealthy {
+    /// The token was rejected by Vault, it has expired or been revoked.
+    AuthExpired,
+    /// Vault is sealed and cannot encrypt or decrypt until it is unsealed.
+    Sealed,
+    /// The transit key does not exist.
+    KeyDeleted,
+    /// The transit key exists but cannot be used to encrypt, ex: an `ed25519` signing key.
+    KeyNotEncryptable,
+    /// The token's policies do not allow the operation on the transit key.
+    PolicyDenied(&'static str),
+    /// Vault could not be reached.
+    Unreachable(String),
+    Failed(String),
+}
+
+/// Status code and error messages of a response from Vault.
+fn response(error: &ClientError) -> Option<(u16, String)> {
+    match error {
+        ClientError::APIError { code, errors } => Some((*code, errors.join(", "))),
+        ClientError::RestClientError {
+            source: rustify::errors::ClientError::ServerResponseError { code, content },
+        } => Some((*code, content.clone().unwrap_or_default())),
+        _ => None,
+    }
+}
+
+impl Unhealthy {
+    /// Classifies a failed request to Vault, `operation` describes the request, ex: `encrypting with`.
+    pub fn from_error(error: &VaultError, operation: &'static str) -> Self {
+        if let ClientError::RestClientError {
+            source: rustify::errors::ClientError::RequestError { .. },
+        } = &error.0
+        {
+            return Self::Unreachable(error.0.to_string());
+        }
+        match response(&error.0) {
+            Some((_, message))
+                if message.contains("invalid token") || message.contains("No token found") =>
+            {
+                Self::AuthExpired
+            }
+            Some((FORBIDDEN, _)) => Self::PolicyDenied(operation),
+            Some((SERVICE_UNAVAILABLE, message)) if message.contains("sealed") => Self::Sealed,
+            Some((NOT_FOUND, _)) => Self::KeyDeleted,
+            Some((_, message)) if message.contains("key not found") => Self::KeyDeleted,
+            _ => Self::Failed(error.0.to_string()),
+        }
+    }
+}
+
+impl Display for Unhealthy {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::AuthExpired => f.write_str(
+                "auth_expired: Vault rejected the token, it has expired or been revoked",
+            ),
+            Self::Sealed => f.write_str("vault_sealed: Vault is sealed"),
+            Self::KeyDeleted => f.write_str("key_deleted: The transit key does not exist"),
+            Self::KeyNotEncryptable => {
+                f.write_str("key_not_encryptable: The transit key does not support encryption")
+            }
+            Self::PolicyDenied(operation) => write!(
+                f,
+                "policy_denied: The token's policies do not allow {} the transit key",
+                operation
+            ),
+            Self::Unreachable(error) => write!(f, "vault_unreachable: {}", error),
+            Self::Failed(error) => write!(f, "vault_error: {}", error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod healthz {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn api_error(code: u16, error: &str) -> VaultError {
+        VaultError(ClientError::APIError {
+            code,
+            errors: vec![error.to_string()],
+        })
+    }
+
+    #[test]
+    fn classifies_rejected_tokens_as_expired_authentication() {
+        assert_eq!(
+            Unhealthy::from_error(
+                &api_error(
+                    403,
+                    "2 errors occurred:\n\t* permission denied\n\t* invalid token\n\n"
+                ),
+                "encrypting with"
+            ),
+            Unhealthy::AuthExpired
+        );
+        assert_eq!(
+            Unhealthy::from_error(&api_error(500, "No token found"), "encrypting with"),
+            Unhealthy::AuthExpired
+        );
+    }
+
+    #[test]
+    fn classifies_other_denials_as_policy_denials() {
+        let unhealthy =
+            Unhealthy::from_error(&api_error(403, "permission denied"), "encrypting with");
+        assert_eq!(unhealthy, Unhealthy::PolicyDenied("encrypting with"));
+        assert_eq!(
+            unhealthy.to_string(),
+            "policy_denied: The token's policies do not allow encrypting with the transit key"
+        );
+    }
+
+    #[test]
+    fn classifies_sealed_vaults() {
+        assert_eq!(
+            Unhealthy::from_error(&api_error(503, "Vault is sealed"), "encrypting with"),
+            Unhealthy::Sealed
+        );
+    }
+
+    #[test]
+    fn classifies_missing_keys_as_deleted() {
+        assert_eq!(
+            Unhealthy::from_error(
+                &VaultError(ClientError::APIError {
+                    code: 404,
+                    errors: vec![]
+                }),
+                "reading"
+            ),
+            Unhealthy::KeyDeleted
+        );
+        assert_eq!(
+            Unhealthy::from_error(
+                &api_error(400, "encryption key not found"),
+                "decrypting with"
+            ),
+            Unhealthy::KeyDeleted
+        );
+    }
+
+    #[test]
+    fn reports_other_errors_as_they_are() {
+        assert_eq!(
+            Unhealthy::from_error(&api_error(500, "internal error"), "encrypting with").to_string(),
+            format!("vault_error: {}", api_error(500, "internal error").0)
+        );
+    }
+}
@@ -0,0 +1,116 @@
+use crate::configuration::authentication::JwtSigner;
+use chrono::{DateTime, Utc};
+use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
+use rustify_derive::Endpoint;
+use serde_json::json;
+use tracing::{debug, instrument};
+use vaultrs::error::ClientError;
+
+/// ## Login with JWT
+/// Issues a Vault token for a JWT, validated against the keys or OIDC discovery configured on the mount.
+///
+/// * Path: /auth/{self.mount}/login
+/// * Method: POST
+/// * Reference: <https://developer.hashicorp.com/vault/api-docs/auth/jwt#jwt-login>
+#[derive(Debug, Endpoint)]
+#[endpoint(path = "/auth/{self.mount}/login", method = "POST")]
+pub struct LoginRequest {
+    #[endpoint(skip)]
+    pub mount: String,
+    pub role: Option<String>,
+    pub jwt: String,
+}
+
+fn signing_error(message: String) -> ClientError {
+    ClientError::APIError {
+        code: 500,
+        errors: vec![message],
+    }
+}
+
+fn encoding_key(algorithm: Algorithm, pem: &[u8]) -> Result<EncodingKey, ClientError> {
+    match algorithm {
+        Algorithm::RS256
+        | Algorithm::RS384
+        | Algorithm::RS512
+        | Algorithm::PS256
+        | Algorithm::PS384
+        | Algorithm::PS512 => EncodingKey::from_rsa_pem(pem),
+        Algorithm::ES256 | Algorithm::ES384 => EncodingKey::from_ec_pem(pem),
+        Algorithm::EdDSA => EncodingKey::from_ed_pem(pem),
+        Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512 => Ok(EncodingKey::from_secret(pem)),
+    }
+    .map_err(|error| signing_error(format!("Invalid JWT signing key: {}", error)))
+}
+
+/// Mints a JWT from the signer's private key, valid from `time` until its TTL has passed.
+#[instrument(skip_all)]
+pub fn sign(signer: &JwtSigner, time: DateTime<Utc>) -> Result<String, ClientError> {
+    let key = encoding_key(signer.algorithm, signer.private_key.value()?.as_bytes())?;
+    let claims = json!({
+        "iss": signer.issuer,
+        "sub": signer.subject,
+        "aud": signer.audience,
+        "iat": time.timestamp(),
+        "nbf": time.timestamp(),
+        "exp": time.timestamp() + signer.ttl.as_secs() as i64,
+    });
+    debug!(
+        "Signing JWT for subject: {}, expiring in {:?}",
+        signer.subject, signer.ttl
+    );
+    encode(&Header::new(signer.algorithm), &claims, &key)
+        .map_err(|error| signing_error(format!("Failed to sign JWT: {}", error)))
+}
+
+#[cfg(test)]
+mod jwt {
+    use super::*;
+    use crate::utilities::source::Source;
+    use jsonwebtoken::{decode, DecodingKey, Validation};
+    use pretty_assertions::assert_eq;
+    use serde_json::Value;
+    use std::time::Duration;
+
+    fn signer(private_key: Source) -> JwtSigner {
+        JwtSigner::new(
+            private_key,
+            None,
+            Some("issuer".to_string()),
+            Some("subject".to_string()),
+            Some("audience".to_string()),
+            Some(Duration::from_secs(60)),
+        )
+    }
+
+    #[test]
+    fn signs_jwts_with_the_configured_claims() {
+        let time = Utc::now();
+        let jwt = sign(
+            &signer(Source::FilePath(
+                "./test_files/jwt/private_key.pem".to_string(),
+            )),
+            time,
+        )
+        .unwrap();
+        let mut validation = Validation::new(Algorithm::RS256);
+        validation.set_audience(&["audience"]);
+        validation.set_issuer(&["issuer"]);
+        let claims = decode::<Value>(
+            &jwt,
+            &DecodingKey::from_rsa_pem(&std::fs::read("./test_files/jwt/public_key.pem").unwrap())
+                .unwrap(),
+            &validation,
+        )
+        .unwrap()
+        .claims;
+        assert_eq!(claims["sub"], "subject");
+        assert_eq!(claims["exp"], time.timestamp() + 60);
+    }
+
+    #[test]
+    fn returns_an_error_for_an_invalid_private_key() {
+        let result = sign(&signer(Source::Value("not a key".to_string())), Utc::now());
+        assert!(result.is_err());
+    }
+}
@@ -6,6 +6,8 @@ use vaultrs::api::transit::responses::{ReadKeyData, ReadPublicKeyEntry};
 pub struct KeyInfo {
     pub id: String,
     pub version: String,
+    /// Whether the key type supports encryption, set from the key's properties when read from Vault.
+    pub encryptable: bool,
 }
 
 impl From<&ReadKeyData> for KeyInfo {
@@ -28,6 +30,7 @@ impl From<&HashMap<String, u64>> for KeyInfo {
         KeyInfo {
             version: version.to_string(),
             id: id.to_string(),
+            encryptable: true,
         }
     }
 }
@@ -50,6 +53,7 @@ impl From<&HashMap<String, ReadPublicKeyEntry>> for KeyInfo {
         KeyInfo {
             version: version.to_string(),
             id: id.to_string(),
+            encryptable: true,
         }
     }
 }
@@ -113,7 +117,8 @@ mod key_info {
             KeyInfo::from(data),
             KeyInfo {
                 id: latest_id,
-                version: "9".to_string()
+                version: "9".to_string(),
+                encryptable: true,
             }
         );
     }
@@ -133,7 +138,8 @@ mod key_info {
             KeyInfo::from(map),
             KeyInfo {
                 id: latest_id,
-                version: "9".to_string()
+                version: "9".to_string(),
+                encryptable: true,
             }
         );
     }
@@ -0,0 +1,47 @@
+use tracing::{debug, instrument};
+use url::Url;
+use vaultrs::error::ClientError;
+
+pub fn metadata_error(message: String) -> ClientError {
+    ClientError::APIError {
+        code: 500,
+        errors: vec![message],
+    }
+}
+
+/// Requests a document from a cloud provider's instance metadata service.
+#[instrument(skip(http, query, header))]
+pub async fn get(
+    http: &reqwest::Client,
+    endpoint: &str,
+    path: &str,
+    query: &[(&str, &str)],
+    header: (&str, &str),
+) -> Result<String, ClientError> {
+    let mut url = Url::parse(endpoint)
+        .and_then(|url| url.join(path))
+        .map_err(|error| {
+            metadata_error(format!("Invalid metadata endpoint {}: {}", endpoint, error))
+        })?;
+    url.query_pairs_mut().extend_pairs(query);
+    debug!("Requesting instance metadata from: {}", url.path());
+    let response = http
+        .get(url)
+        .header(header.0, header.1)
+        .send()
+        .await
+        .map_err(|error| metadata_error(format!("Failed to reach metadata endpoint: {}", error)))?;
+    let status = response.status();
+    let body = response
+        .text()
+        .await
+        .map_err(|error| metadata_error(format!("Failed to read metadata response: {}", error)))?;
+    if status.is_success() {
+        Ok(body)
+    } else {
+        Err(ClientError::APIError {
+            code: status.as_u16(),
+            errors: vec![body],
+        })
+    }
+}
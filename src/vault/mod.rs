@@ -1,7 +1,22 @@
+mod aws;
+mod azure;
 mod client;
+mod cluster;
+mod connection;
+mod deadline;
+mod gcp;
+mod healthz;
+mod jwt;
 mod keys;
+mod metadata;
+mod rate_limit;
+mod request;
 mod service;
+mod wrapping;
 
 pub use client::Client;
+pub use cluster::Cluster;
+pub use connection::connect;
+pub use healthz::Unhealthy;
 
-pub use service::VaultKmsServer;
+pub use service::{Initialization, VaultKmsServer, LIVENESS_PROBE};
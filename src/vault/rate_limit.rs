@@ -0,0 +1,95 @@
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+use tracing::debug;
+
+pub const TOO_MANY_REQUESTS: u16 = 429;
+const RETRY_AFTER_HEADER: &str = "retry-after";
+
+/// Limits requests to `rate` per second, allowing up to `burst` requests at once.
+pub struct TokenBucket {
+    rate: f64,
+    burst: f64,
+    state: Mutex<(f64, Instant)>,
+}
+
+impl TokenBucket {
+    pub fn new(rate: f64, burst: u32) -> Self {
+        Self {
+            rate,
+            burst: burst as f64,
+            state: Mutex::new((burst as f64, Instant::now())),
+        }
+    }
+
+    /// Waits until a request can be sent. Waiting requests are served in the order they arrived.
+    pub async fn acquire(&self) {
+        let mut state = self.state.lock().await;
+        let (tokens, updated) = *state;
+        let now = Instant::now();
+        let tokens =
+            (tokens + now.duration_since(updated).as_secs_f64() * self.rate).min(self.burst);
+        if tokens >= 1.0 {
+            *state = (tokens - 1.0, now);
+            return;
+        }
+        let wait = Duration::from_secs_f64((1.0 - tokens) / self.rate);
+        debug!("Rate limiting Vault request for {:?}", wait);
+        tokio::time::sleep(wait).await;
+        *state = (0.0, now + wait);
+    }
+}
+
+/// Seconds to wait before retrying, HTTP dates are not used by Vault and are ignored.
+pub fn retry_after(headers: &http::HeaderMap) -> Option<Duration> {
+    headers
+        .get(RETRY_AFTER_HEADER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+        .map(Duration::from_secs)
+}
+
+#[cfg(test)]
+mod rate_limit {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[tokio::test]
+    async fn allows_a_burst_of_requests_without_waiting() {
+        let bucket = TokenBucket::new(1.0, 3);
+        let start = Instant::now();
+        for _ in 0..3 {
+            bucket.acquire().await;
+        }
+        assert!(start.elapsed() < Duration::from_millis(100));
+    }
+
+    #[tokio::test]
+    async fn waits_for_a_token_once_the_burst_is_used() {
+        let bucket = TokenBucket::new(20.0, 1);
+        bucket.acquire().await;
+        let start = Instant::now();
+        bucket.acquire().await;
+        assert!(start.elapsed() >= Duration::from_millis(45));
+    }
+
+    #[test]
+    fn reads_the_retry_after_header_in_seconds() {
+        let mut headers = http::HeaderMap::new();
+        headers.insert(RETRY_AFTER_HEADER, "2".parse().unwrap());
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(2)));
+    }
+
+    #[test]
+    fn ignores_retry_after_dates() {
+        let mut headers = http::HeaderMap::new();
+        headers.insert(
+            RETRY_AFTER_HEADER,
+            "Wed, 21 Oct 2015 07:28:00 GMT".parse().unwrap(),
+        );
+        assert_eq!(retry_after(&headers), None);
+    }
+}
@@ -0,0 +1,104 @@
+use crate::audit;
+use crate::telemetry;
+use crate::vault::rate_limit::{retry_after, TOO_MANY_REQUESTS};
+use rustify::client::{Client as _, HTTP_SUCCESS_CODES};
+use rustify::endpoint::{Endpoint, MiddleWare};
+use std::time::Duration;
+use vaultrs::api::{EndpointError, EndpointResult};
+use vaultrs::client::VaultClient;
+use vaultrs::error::ClientError;
+
+/// Failure of a request executed by [execute].
+pub enum ExecuteError {
+    /// Vault rejected the request with `429 Too Many Requests`, optionally saying when to retry.
+    RateLimited {
+        error: ClientError,
+        retry_after: Option<Duration>,
+    },
+    Failed(ClientError),
+}
+
+impl From<ClientError> for ExecuteError {
+    fn from(value: ClientError) -> Self {
+        Self::Failed(value)
+    }
+}
+
+impl From<rustify::errors::ClientError> for ExecuteError {
+    fn from(value: rustify::errors::ClientError) -> Self {
+        Self::Failed(ClientError::from(value))
+    }
+}
+
+/// Same conversion `vaultrs` applies to error responses, the errors listed in the body become an `APIError`.
+fn api_error(code: u16, content: Vec<u8>) -> ClientError {
+    match serde_json::from_slice::<EndpointError>(&content) {
+        Ok(error) => ClientError::APIError {
+            code,
+            errors: error.errors,
+        },
+        Err(_) => ClientError::RestClientError {
+            source: rustify::errors::ClientError::ServerResponseError {
+                code,
+                content: String::from_utf8(content).ok(),
+            },
+        },
+    }
+}
+
+/// Executes a Vault endpoint like `vaultrs::api::exec_with_result`, which discards the response headers
+/// of failed requests, keeping the `Retry-After` header of rate limited responses. The Vault request
+/// id of successful requests is passed on to the audit log, see [audit::vault_request], and the
+/// current trace is passed on to Vault in the `traceparent` header.
+pub async fn execute<E>(client: &VaultClient, endpoint: &E) -> Result<E::Response, ExecuteError>
+where
+    E: Endpoint,
+{
+    let mut request = endpoint.request(client.http.base())?;
+    client.middle.request(endpoint, &mut request)?;
+    if let Some(traceparent) = telemetry::traceparent().and_then(|value| value.parse().ok()) {
+        request
+            .headers_mut()
+            .insert(telemetry::TRACEPARENT, traceparent);
+    }
+    let mut response = client.http.send(request).await?;
+    let code = response.status().as_u16();
+    if code == TOO_MANY_REQUESTS {
+        let retry_after = retry_after(response.headers());
+        return Err(ExecuteError::RateLimited {
+            error: api_error(code, response.into_body()),
+            retry_after,
+        });
+    }
+    if !HTTP_SUCCESS_CODES.contains(&code) {
+        return Err(api_error(code, response.into_body()).into());
+    }
+    client.middle.response(endpoint, &mut response)?;
+    let result =
+        rustify::endpoint::EndpointResult::<E::Response>::new(response, E::RESPONSE_BODY_TYPE)
+            .wrap::<EndpointResult<_>>()?;
+    audit::vault_request(&result.request_id);
+    result
+        .data
+        .ok_or(ClientError::ResponseDataEmptyError.into())
+}
+
+#[cfg(test)]
+mod request {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parses_errors_from_the_response_body() {
+        match api_error(
+            429,
+            br#"{"errors": ["rate limit quota exceeded"]}"#.to_vec(),
+        ) {
+            ClientError::APIError { code, errors } => {
+                assert_eq!(code, 429);
+                assert_eq!(errors, vec!["rate limit quota exceeded".to_string()]);
+            }
+            error => panic!("Unexpected error: {:?}", error),
+        }
+    }
+}
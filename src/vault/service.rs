@@ -1,93 +1,350 @@
+use crate::audit::{self, AuditRecord, Auditor, Operation};
 use crate::kms::{
     key_management_service_server::KeyManagementService, DecryptRequest, DecryptResponse,
     EncryptRequest, EncryptResponse, StatusRequest, StatusResponse,
 };
-use crate::utilities::watcher::Refresh;
-use crate::vault::client;
+use crate::telemetry;
+use crate::utilities::backoff::Backoff;
+use crate::utilities::heartbeat::Heartbeat;
+use crate::vault::cluster::Cluster;
+use crate::vault::deadline;
+use crate::vault::healthz::Unhealthy;
 use base64::{prelude::BASE64_STANDARD, Engine};
-use std::sync::Arc;
-use std::{collections::HashMap, io::ErrorKind, string::ToString};
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use std::{collections::HashMap, string::ToString};
 use tokio::sync::RwLock;
+use tokio::time::Instant;
 use tonic::{Code, Request, Response, Status};
-use tracing::{debug, error, info, instrument};
+use tracing::{debug, error, info, info_span, warn, Instrument};
+use zeroize::Zeroizing;
 
 const OKAY_RESPONSE: &str = "ok";
+const INITIALIZING_RESPONSE: &str = "Vault encryption has not been initialized yet";
 const API_VERSION: &str = "v2";
+/// Metadata marking the `Status` requests the server sends itself to check liveness.
+pub const LIVENESS_PROBE: &str = "x-liveness-probe";
+
+/// Whether Vault encryption has been initialized, with the reason the last attempt failed until it has.
+#[derive(Debug)]
+pub struct Initialization {
+    failure: Mutex<Option<String>>,
+}
+
+impl Default for Initialization {
+    fn default() -> Self {
+        Self {
+            failure: Mutex::new(Some(INITIALIZING_RESPONSE.to_string())),
+        }
+    }
+}
+
+impl Initialization {
+    pub fn is_initialized(&self) -> bool {
+        self.failure().is_none()
+    }
+
+    /// Why Vault encryption is not initialized yet, if it is not.
+    pub fn failure(&self) -> Option<String> {
+        self.failure
+            .lock()
+            .expect("Initialization lock poisoned")
+            .clone()
+    }
+
+    pub fn succeeded(&self) {
+        *self.failure.lock().expect("Initialization lock poisoned") = None;
+    }
+
+    pub fn failed(&self, reason: &str) {
+        *self.failure.lock().expect("Initialization lock poisoned") =
+            Some(format!("Vault encryption is not initialized: {}", reason));
+    }
+}
 
 pub struct VaultKmsServer {
-    client: Arc<RwLock<client::Client>>,
+    client: Arc<RwLock<Cluster>>,
+    auditor: Auditor,
+    initialization: Arc<Initialization>,
+    /// Id of the key last read from Vault, reported by `Status` while the key cannot be read.
+    key_id: Mutex<Option<String>>,
+    /// Why the last canary encryption failed, if it did.
+    canary: Arc<Mutex<Option<Unhealthy>>>,
+}
+
+/// Authenticates, then checks that the transit key can be used to encrypt. Only the shared lock is held, so
+/// requests and `Status` are served while Vault is slow to respond.
+async fn initialize(client: &RwLock<Cluster>) -> Result<(), String> {
+    let client = client.read().await;
+    client
+        .authenticate()
+        .await
+        .map_err(|error| format!("Failed to authenticate: {}", error))?;
+    client
+        .request_encryption(&BASE64_STANDARD.encode("initialize".as_bytes()))
+        .await
+        .map_err(|error| format!("Failed to encrypt: {}", error.0))?;
+    Ok(())
 }
 
 impl VaultKmsServer {
-    pub fn new(client: Arc<RwLock<client::Client>>) -> Self {
-        Self { client }
-    }
-
-    #[instrument(skip(self))]
-    pub async fn initialize(&self) -> Result<(), std::io::Error> {
-        let mut client = self.client.write().await;
-        client.refresh_token().await.map_err(|e| {
-            error!("Failed to authenticate during initialization: {:?}", e);
-            std::io::Error::other(e.to_string())
-        })?;
-        client
-            .request_encryption(&BASE64_STANDARD.encode("initialize".as_bytes()))
-            .await
-            .map_err(|error| {
-                let error = format!("Failed to initialize: {}", error.0.to_string());
-                error!("{}", error);
-                std::io::Error::new(ErrorKind::Other, error.as_str())
-            })?;
-        info!("Vault encryption has been initialized");
-        Ok(())
+    pub fn new(client: Arc<RwLock<Cluster>>, auditor: Auditor) -> Self {
+        Self {
+            client,
+            auditor,
+            initialization: Arc::new(Initialization::default()),
+            key_id: Mutex::new(None),
+            canary: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    pub fn initialization(&self) -> Arc<Initialization> {
+        self.initialization.clone()
+    }
+
+    /// Encrypts every `interval` once initialized, so `Status` reports when the token can no longer encrypt, ex: after
+    /// a policy change, before the API server next needs to. Beats after each encryption, and while waiting for the
+    /// next one.
+    pub fn canary(
+        &self,
+        interval: Duration,
+        heartbeat: Heartbeat,
+    ) -> impl Future<Output = ()> + Send + 'static {
+        let (client, initialization, canary) = (
+            self.client.clone(),
+            self.initialization.clone(),
+            self.canary.clone(),
+        );
+        async move {
+            loop {
+                heartbeat.idle(interval).await;
+                if !initialization.is_initialized() {
+                    continue;
+                }
+                let failure = client
+                    .read()
+                    .await
+                    .request_encryption(&BASE64_STANDARD.encode("canary".as_bytes()))
+                    .await
+                    .err()
+                    .map(|error| Unhealthy::from_error(&error, "encrypting with"));
+                match &failure {
+                    Some(unhealthy) => warn!("Canary encryption failed: {}", unhealthy),
+                    None => debug!("Canary encryption succeeded"),
+                }
+                *canary.lock().expect("Canary lock poisoned") = failure;
+            }
+        }
+    }
+
+    /// Remembers the id of the current key, so it can still be reported when Vault fails.
+    fn key_seen(&self, key_id: &str) {
+        *self.key_id.lock().expect("Key id lock poisoned") = Some(key_id.to_string());
+    }
+
+    /// Initializes Vault encryption, retrying with backoff until Vault is reachable. Requests are served in the
+    /// meantime, with `Status` reporting why the provider is not initialized yet. Beats before each attempt and
+    /// while backing off, completing `heartbeat` once initialized.
+    pub fn initialize(
+        &self,
+        mut backoff: Backoff,
+        heartbeat: Heartbeat,
+    ) -> impl Future<Output = ()> + Send + 'static {
+        let (client, initialization) = (self.client.clone(), self.initialization.clone());
+        async move {
+            let mut attempts: u32 = 0;
+            loop {
+                attempts += 1;
+                heartbeat.beat();
+                match initialize(&client).await {
+                    Ok(()) => {
+                        initialization.succeeded();
+                        heartbeat.complete();
+                        info!(
+                            "Vault encryption has been initialized after {} attempt(s)",
+                            attempts
+                        );
+                        return;
+                    }
+                    Err(error) => {
+                        let delay = backoff.next_delay();
+                        error!(
+                            "Failed to initialize (attempt {}), retrying in {:?}: {}",
+                            attempts, delay, error
+                        );
+                        initialization.failed(&error);
+                        heartbeat.idle(delay).await;
+                    }
+                }
+            }
+        }
     }
 }
 
-#[tonic::async_trait]
-impl KeyManagementService for VaultKmsServer {
-    #[instrument(skip(self, _request))]
-    async fn status(
+/// Trace context sent by the API server, continued by the span serving the request.
+fn traceparent<T>(request: &Request<T>) -> Option<&str> {
+    request
+        .metadata()
+        .get(telemetry::TRACEPARENT)
+        .and_then(|value| value.to_str().ok())
+}
+
+impl VaultKmsServer {
+    /// Answers a liveness probe with the key seen last instead of asking Vault, only waiting on the client lock so a
+    /// deadlocked client fails the probe.
+    async fn probe_status(
         &self,
-        _request: Request<StatusRequest>,
+        request: Request<StatusRequest>,
+    ) -> Result<Response<StatusResponse>, Status> {
+        deadline::within(deadline::remaining(&request), async {
+            drop(self.client.read().await);
+            Ok(())
+        })
+        .await?;
+        Ok(Response::new(StatusResponse {
+            version: API_VERSION.to_string(),
+            key_id: self
+                .key_id
+                .lock()
+                .expect("Key id lock poisoned")
+                .clone()
+                .unwrap_or_default(),
+            healthz: self
+                .initialization
+                .failure()
+                .unwrap_or_else(|| OKAY_RESPONSE.to_string()),
+        }))
+    }
+
+    async fn report_status(
+        &self,
+        request: Request<StatusRequest>,
     ) -> Result<Response<StatusResponse>, Status> {
         debug!("Status request");
-        let client = self.client.read().await;
-        Ok(client.request_key().await.map(|key| {
-            Response::new(StatusResponse {
-                version: API_VERSION.to_string(),
-                key_id: key.id,
-                healthz: OKAY_RESPONSE.to_string(),
-            })
-        })?)
+        let start = Instant::now();
+        let mut record = AuditRecord::new(Operation::Status, None);
+        let mut unhealthy = None;
+        // Vault is only asked once initialized, so the failure is reported without waiting on the client.
+        let failure = self.initialization.failure();
+        let (key, vault_requests) = match &failure {
+            Some(failure) => (Err(Status::unavailable(failure)), vec![]),
+            None => {
+                audit::collect_vault_requests(deadline::within(
+                    deadline::remaining(&request),
+                    async {
+                        let client = self.client.read().await;
+                        client.request_key().await.map_err(|error| {
+                            unhealthy = Some(Unhealthy::from_error(&error, "reading"));
+                            Status::from(error)
+                        })
+                    },
+                ))
+                .await
+            }
+        };
+        if let Ok(key) = &key {
+            record.key_id = Some(key.id.clone());
+            record.key_version = Some(key.version.clone());
+            self.key_seen(&key.id);
+        }
+        record.finish(&key, start.elapsed(), vault_requests);
+        self.auditor.record(&record);
+        let healthz = match (failure, &key) {
+            (Some(failure), _) => failure,
+            (None, Ok(key)) if !key.encryptable => Unhealthy::KeyNotEncryptable.to_string(),
+            (None, Ok(_)) => self
+                .canary
+                .lock()
+                .expect("Canary lock poisoned")
+                .as_ref()
+                .map_or(OKAY_RESPONSE.to_string(), Unhealthy::to_string),
+            (None, Err(status)) => unhealthy
+                .unwrap_or_else(|| Unhealthy::Failed(status.message().to_string()))
+                .to_string(),
+        };
+        if healthz != OKAY_RESPONSE {
+            warn!("Reporting unhealthy status: {}", healthz);
+        }
+        let key_id = match key {
+            Ok(key) => key.id,
+            Err(_) => self
+                .key_id
+                .lock()
+                .expect("Key id lock poisoned")
+                .clone()
+                .unwrap_or_default(),
+        };
+        Ok(Response::new(StatusResponse {
+            version: API_VERSION.to_string(),
+            key_id,
+            healthz,
+        }))
     }
 
-    #[instrument(skip(self, request))]
-    async fn decrypt(
+    async fn decrypt_ciphertext(
         &self,
         request: Request<DecryptRequest>,
     ) -> Result<Response<DecryptResponse>, Status> {
         debug!("Decryption request");
-        let client = self.client.read().await;
-        let encrypted = String::from_utf8(request.get_ref().ciphertext.to_vec())
-            .map_err(|error| Status::new(Code::Internal, error.to_string()))?;
-        let plaintext = client.request_decryption(&encrypted).await?;
-        Ok(Response::new(DecryptResponse {
-            plaintext: BASE64_STANDARD
-                .decode(plaintext.as_bytes())
-                .map_err(|error| Status::new(Code::Internal, error.to_string()))?,
-        }))
+        let start = Instant::now();
+        let mut record = AuditRecord::new(Operation::Decrypt, Some(request.get_ref().uid.clone()));
+        record.key_id = Some(request.get_ref().key_id.clone()).filter(|id| !id.is_empty());
+        record.key_version = audit::ciphertext_key_version(&request.get_ref().ciphertext);
+        record.ciphertext_size = Some(request.get_ref().ciphertext.len());
+        let (response, vault_requests) = audit::collect_vault_requests(async {
+            let encrypted = String::from_utf8(request.get_ref().ciphertext.to_vec())
+                .map_err(|error| Status::new(Code::Internal, error.to_string()))?;
+            let plaintext = deadline::within(deadline::remaining(&request), async {
+                let client = self.client.read().await;
+                Ok(client.request_decryption(&encrypted).await?)
+            })
+            .await?;
+            Ok(Response::new(DecryptResponse {
+                plaintext: BASE64_STANDARD
+                    .decode(plaintext.as_bytes())
+                    .map_err(|error| Status::new(Code::Internal, error.to_string()))?,
+            }))
+        })
+        .await;
+        if let Ok(response) = &response {
+            record.plaintext_size = Some(response.get_ref().plaintext.len());
+        }
+        record.finish(&response, start.elapsed(), vault_requests);
+        self.auditor.record(&record);
+        response
     }
 
-    #[instrument(skip(self, request))]
-    async fn encrypt(
+    async fn encrypt_plaintext(
         &self,
         request: Request<EncryptRequest>,
     ) -> Result<Response<EncryptResponse>, Status> {
         debug!("Encryption request");
-        let client = self.client.read().await;
-        let encoded = BASE64_STANDARD.encode(&request.get_ref().plaintext);
-        let ciphertext = client.request_encryption(&encoded).await?;
-        let key = client.request_key().await?;
+        let start = Instant::now();
+        let remaining = deadline::remaining(&request);
+        let request = request.into_inner();
+        let plaintext = Zeroizing::new(request.plaintext);
+        let mut record = AuditRecord::new(Operation::Encrypt, Some(request.uid));
+        record.plaintext_size = Some(plaintext.len());
+        let encoded = Zeroizing::new(BASE64_STANDARD.encode(&*plaintext));
+        let (result, vault_requests) =
+            audit::collect_vault_requests(deadline::within(remaining, async {
+                let client = self.client.read().await;
+                Ok((
+                    client.request_encryption(&encoded).await?,
+                    client.request_key().await?,
+                ))
+            }))
+            .await;
+        if let Ok((ciphertext, key)) = &result {
+            self.key_seen(&key.id);
+            record.key_id = Some(key.id.clone());
+            record.key_version = audit::ciphertext_key_version(ciphertext.as_bytes());
+            record.ciphertext_size = Some(ciphertext.len());
+        }
+        record.finish(&result, start.elapsed(), vault_requests);
+        self.auditor.record(&record);
+        let (ciphertext, key) = result?;
         Ok(Response::new(EncryptResponse {
             key_id: key.id,
             ciphertext: ciphertext.as_bytes().to_vec(),
@@ -95,3 +352,36 @@ impl KeyManagementService for VaultKmsServer {
         }))
     }
 }
+
+#[tonic::async_trait]
+impl KeyManagementService for VaultKmsServer {
+    async fn status(
+        &self,
+        request: Request<StatusRequest>,
+    ) -> Result<Response<StatusResponse>, Status> {
+        if request.metadata().contains_key(LIVENESS_PROBE) {
+            return self.probe_status(request).await;
+        }
+        let span = info_span!("status", otel.kind = "server");
+        telemetry::continue_trace(&span, traceparent(&request));
+        self.report_status(request).instrument(span).await
+    }
+
+    async fn decrypt(
+        &self,
+        request: Request<DecryptRequest>,
+    ) -> Result<Response<DecryptResponse>, Status> {
+        let span = info_span!("decrypt", uid = request.get_ref().uid, otel.kind = "server");
+        telemetry::continue_trace(&span, traceparent(&request));
+        self.decrypt_ciphertext(request).instrument(span).await
+    }
+
+    async fn encrypt(
+        &self,
+        request: Request<EncryptRequest>,
+    ) -> Result<Response<EncryptResponse>, Status> {
+        let span = info_span!("encrypt", uid = request.get_ref().uid, otel.kind = "server");
+        telemetry::continue_trace(&span, traceparent(&request));
+        self.encrypt_plaintext(request).instrument(span).await
+    }
+}
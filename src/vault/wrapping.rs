@@ -0,0 +1,73 @@
+use serde_json::Value;
+use tonic::async_trait;
+use tracing::{debug, error, instrument};
+use vaultrs::api::EndpointMiddleware;
+use vaultrs::client::{Client, VaultClient, VaultClientSettings};
+use vaultrs::error::ClientError;
+use zeroize::{Zeroize, Zeroizing};
+
+const INVALID_WRAPPING_TOKEN: &str = "wrapping token is not valid or does not exist";
+
+/// Borrows a client's connection to make requests authenticated with a wrapping token,
+/// leaving the client's own token untouched.
+struct WrappingClient<'a> {
+    client: &'a VaultClient,
+    middle: EndpointMiddleware,
+}
+
+#[async_trait]
+impl Client for WrappingClient<'_> {
+    fn http(&self) -> &rustify::clients::reqwest::Client {
+        &self.client.http
+    }
+
+    fn middle(&self) -> &EndpointMiddleware {
+        &self.middle
+    }
+
+    fn settings(&self) -> &VaultClientSettings {
+        &self.client.settings
+    }
+
+    fn set_token(&mut self, token: &str) {
+        self.middle.token.zeroize();
+        self.middle.token = token.to_string();
+    }
+}
+
+impl Drop for WrappingClient<'_> {
+    fn drop(&mut self) {
+        self.middle.token.zeroize();
+    }
+}
+
+/// Unwraps a response-wrapped AppRole `secret_id` via `sys/wrapping/unwrap`.
+#[instrument(skip_all)]
+pub async fn unwrap_secret_id(
+    client: &VaultClient,
+    wrapping_token: &str,
+) -> Result<Zeroizing<String>, ClientError> {
+    let mut wrapping_client = WrappingClient {
+        client,
+        middle: client.middle.clone(),
+    };
+    wrapping_client.set_token(wrapping_token);
+    let mut response: Value = vaultrs::sys::wrapping::unwrap(&wrapping_client, None)
+        .await
+        .map_err(|error| match error {
+            ClientError::APIError { errors, .. }
+                if errors
+                    .iter()
+                    .any(|error| error.contains(INVALID_WRAPPING_TOKEN)) =>
+            {
+                error!("The AppRole secret_id wrapping token has already been used or has expired, a new wrapping token is required");
+                ClientError::WrapInvalidError
+            }
+            error => error,
+        })?;
+    debug!("Unwrapped AppRole secret_id");
+    match response["secret_id"].take() {
+        Value::String(secret_id) => Ok(Zeroizing::new(secret_id)),
+        _ => Err(ClientError::ResponseDataEmptyError),
+    }
+}
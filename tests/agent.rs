@@ -0,0 +1,67 @@
+mod common;
+
+#[cfg(test)]
+mod agent {
+    use super::common;
+    use base64::{prelude::BASE64_STANDARD, Engine};
+    use lib::configuration::agent::AgentConfiguration;
+    use lib::configuration::vault::VaultConfiguration;
+    use lib::utilities::source::Source;
+    use lib::vault::Client;
+    use pretty_assertions::assert_eq;
+    use std::sync::{Arc, Mutex};
+    use uuid::Uuid;
+    use vaultrs::client::{VaultClient, VaultClientSettingsBuilder};
+
+    async fn encrypt_through_agent(sink: Option<Source>) -> common::StandInRequest {
+        let requests: Arc<Mutex<Vec<common::StandInRequest>>> = Arc::new(Mutex::new(vec![]));
+        let recorded = requests.clone();
+        let address = common::stand_in(move |request| {
+            recorded.lock().unwrap().push(request.clone());
+            if request.uri == "/v1/transit/encrypt/vault-kms-provider" {
+                (
+                    200,
+                    common::data_response(r#"{"ciphertext": "vault:v1:ciphertext"}"#),
+                )
+            } else {
+                (404, "{}".to_string())
+            }
+        })
+        .await;
+        let agent = AgentConfiguration::new(format!("http://{}", address), sink);
+        let config: VaultConfiguration = common::server_config().vault.with_agent(&agent);
+        let settings = VaultClientSettingsBuilder::default()
+            .address(&config.address)
+            .build()
+            .unwrap();
+        let mut client = Client::new(VaultClient::new(settings).unwrap(), &config);
+        client.refresh_token().await.unwrap();
+
+        let ciphertext = client
+            .request_encryption(&BASE64_STANDARD.encode("secret"))
+            .await
+            .unwrap();
+
+        assert_eq!(ciphertext, "vault:v1:ciphertext");
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 1);
+        requests[0].clone()
+    }
+
+    #[tokio::test]
+    async fn uses_the_token_written_to_the_agent_sink() {
+        let sink = format!("./test_files/agent-sink-{}", Uuid::new_v4());
+        std::fs::write(&sink, "hvs.agent-token\n").unwrap();
+
+        let request = encrypt_through_agent(Some(Source::FilePath(sink))).await;
+
+        assert_eq!(request.headers["x-vault-token"], "hvs.agent-token");
+    }
+
+    #[tokio::test]
+    async fn leaves_the_token_to_the_agent_cache_without_a_sink() {
+        let request = encrypt_through_agent(None).await;
+
+        assert!(!request.headers.contains_key("x-vault-token"));
+    }
+}
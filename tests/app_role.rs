@@ -0,0 +1,131 @@
+mod common;
+
+#[cfg(test)]
+mod app_role {
+    use super::common;
+    use lib::configuration::authentication::{AppRole, Credentials};
+    use lib::utilities::source::Source;
+    use lib::vault::Client;
+    use pretty_assertions::assert_eq;
+    use std::net::SocketAddr;
+    use std::sync::{Arc, Mutex};
+    use vaultrs::client::{VaultClient, VaultClientSettingsBuilder};
+    use vaultrs::error::ClientError;
+
+    const WRAPPING_TOKEN: &str = "hvs.wrapping-token";
+
+    async fn stand_in_vault(requests: Arc<Mutex<Vec<common::StandInRequest>>>) -> SocketAddr {
+        common::stand_in(move |request| {
+            let mut requests = requests.lock().unwrap();
+            requests.push(request.clone());
+            let unwrapped = requests
+                .iter()
+                .filter(|request| request.uri == "/v1/sys/wrapping/unwrap")
+                .count();
+            match request.uri.as_str() {
+                "/v1/sys/wrapping/unwrap" if unwrapped == 1 => (
+                    200,
+                    common::data_response(
+                        r#"{"secret_id": "unwrapped-secret-id", "secret_id_accessor": "accessor", "secret_id_ttl": 0}"#,
+                    ),
+                ),
+                "/v1/sys/wrapping/unwrap" => (
+                    400,
+                    r#"{"errors": ["wrapping token is not valid or does not exist"]}"#.to_string(),
+                ),
+                "/v1/auth/approle/login" => (200, common::auth_response("app-role-token")),
+                _ => (404, "{}".to_string()),
+            }
+        })
+        .await
+    }
+
+    fn client(address: SocketAddr) -> Client {
+        let mut config = common::server_config();
+        config.vault.address = format!("http://{}", address);
+        config.vault.credentials = vec![Credentials::AppRole(AppRole::with_wrapped_secret_id(
+            Source::Value("role-id".to_string()),
+            Source::Value(WRAPPING_TOKEN.to_string()),
+            None,
+        ))];
+        let settings = VaultClientSettingsBuilder::default()
+            .address(&config.vault.address)
+            .token("")
+            .build()
+            .unwrap();
+        Client::new(VaultClient::new(settings).unwrap(), &config.vault)
+    }
+
+    #[tokio::test]
+    async fn login_with_a_wrapped_secret_id() {
+        let requests: Arc<Mutex<Vec<common::StandInRequest>>> = Arc::new(Mutex::new(vec![]));
+        let client = client(stand_in_vault(requests.clone()).await);
+
+        assert_eq!(client.get_token().await.unwrap().as_str(), "app-role-token");
+        assert_eq!(client.get_token().await.unwrap().as_str(), "app-role-token");
+
+        let requests = requests.lock().unwrap();
+        let unwraps: Vec<&common::StandInRequest> = requests
+            .iter()
+            .filter(|request| request.uri == "/v1/sys/wrapping/unwrap")
+            .collect();
+        assert_eq!(unwraps.len(), 1);
+        assert_eq!(unwraps[0].headers["x-vault-token"], WRAPPING_TOKEN);
+        let login: serde_json::Value = serde_json::from_str(
+            &requests
+                .iter()
+                .find(|request| request.uri == "/v1/auth/approle/login")
+                .unwrap()
+                .body,
+        )
+        .unwrap();
+        assert_eq!(login["secret_id"], "unwrapped-secret-id");
+        assert_eq!(login["role_id"], "role-id");
+    }
+
+    #[tokio::test]
+    async fn fails_clearly_when_the_wrapping_token_was_already_used() {
+        let requests: Arc<Mutex<Vec<common::StandInRequest>>> = Arc::new(Mutex::new(vec![]));
+        requests.lock().unwrap().push(common::StandInRequest {
+            method: "POST".to_string(),
+            uri: "/v1/sys/wrapping/unwrap".to_string(),
+            headers: Default::default(),
+            body: String::new(),
+        });
+        let client = client(stand_in_vault(requests.clone()).await);
+
+        let error = client.get_token().await.unwrap_err();
+
+        assert!(
+            matches!(error, ClientError::WrapInvalidError),
+            "{:?}",
+            error
+        );
+        assert!(!requests
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|request| request.uri == "/v1/auth/approle/login"));
+    }
+
+    #[tokio::test]
+    async fn passes_other_unwrap_errors_through() {
+        let address = common::stand_in(|request| match request.uri.as_str() {
+            "/v1/sys/wrapping/unwrap" => (
+                403,
+                r#"{"errors": ["1 error occurred:\n\t* permission denied\n\n"]}"#.to_string(),
+            ),
+            _ => (404, "{}".to_string()),
+        })
+        .await;
+        let client = client(address);
+
+        let error = client.get_token().await.unwrap_err();
+
+        assert!(
+            matches!(error, ClientError::APIError { code: 403, .. }),
+            "{:?}",
+            error
+        );
+    }
+}
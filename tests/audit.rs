@@ -0,0 +1,179 @@
+mod common;
+
+#[cfg(test)]
+mod audit {
+    use super::common;
+    use base64::{prelude::BASE64_STANDARD, Engine};
+    use lib::audit::Auditor;
+    use lib::configuration::audit::{AuditConfiguration, AuditSink};
+    use lib::kms::key_management_service_server::KeyManagementService;
+    use lib::kms::{DecryptRequest, EncryptRequest, StatusRequest};
+    use lib::utilities::backoff::Backoff;
+    use lib::utilities::heartbeat::{Heartbeat, Heartbeats};
+    use lib::vault::{Cluster, VaultKmsServer};
+    use pretty_assertions::assert_eq;
+    use serde_json::Value;
+    use std::path::{Path, PathBuf};
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::sync::RwLock;
+    use tonic::Request;
+
+    const KEY: &str = r#"{"type": "aes256-gcm96", "deletion_allowed": false, "derived": false, "exportable": false, "allow_plaintext_backup": false, "keys": {"1": 1733119759, "2": 1733119760}, "min_decryption_version": 1, "min_encryption_version": 0, "name": "vault-kms-provider", "supports_encryption": true, "supports_decryption": true, "supports_derivation": true, "supports_signing": false}"#;
+
+    fn vault_response(request_id: &str, data: &str) -> String {
+        format!(
+            r#"{{"request_id": "{}", "lease_id": "", "renewable": false, "lease_duration": 0, "data": {}, "wrap_info": null, "warnings": null, "auth": null}}"#,
+            request_id, data
+        )
+    }
+
+    fn heartbeat() -> Heartbeat {
+        Heartbeats::new(Duration::from_secs(60)).register("task")
+    }
+
+    async fn server(path: &Path) -> VaultKmsServer {
+        let address = common::stand_in(|request| match request.uri.as_str() {
+            "/v1/transit/encrypt/vault-kms-provider" => (
+                200,
+                vault_response(
+                    "encrypt-id",
+                    r#"{"ciphertext": "vault:v2:Y2lwaGVydGV4dA=="}"#,
+                ),
+            ),
+            "/v1/transit/decrypt/vault-kms-provider" => (
+                200,
+                vault_response("decrypt-id", r#"{"plaintext": "c2VjcmV0"}"#),
+            ),
+            "/v1/transit/keys/vault-kms-provider" => (200, vault_response("key-id", KEY)),
+            _ => (400, r#"{"errors": ["invalid ciphertext"]}"#.to_string()),
+        })
+        .await;
+        let mut config = common::server_config();
+        config.vault.address = format!("http://{}", address);
+        let server = VaultKmsServer::new(
+            Arc::new(RwLock::new(
+                Cluster::connect(&config.vault, &config.tls).unwrap(),
+            )),
+            Auditor::new(&AuditConfiguration::new(
+                Some(AuditSink::File(path.to_path_buf())),
+                None,
+                None,
+            ))
+            .unwrap(),
+        );
+        tokio::time::timeout(
+            Duration::from_secs(5),
+            server.initialize(Backoff::default(), heartbeat()),
+        )
+        .await
+        .expect("initialization did not complete");
+        server
+    }
+
+    fn audit_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "vault-kms-provider-{}-{}.log",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    async fn records(path: &Path, expected: usize) -> Vec<Value> {
+        for _ in 0..100 {
+            let records = std::fs::read_to_string(path)
+                .unwrap_or_default()
+                .lines()
+                .map(|line| serde_json::from_str(line).unwrap())
+                .collect::<Vec<Value>>();
+            if records.len() >= expected {
+                return records;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("{} audit record(s) were not written", expected);
+    }
+
+    #[tokio::test]
+    async fn records_each_request_without_payloads() {
+        let path = audit_path("audit");
+        let server = server(&path).await;
+
+        server
+            .encrypt(Request::new(EncryptRequest {
+                plaintext: b"secret".to_vec(),
+                uid: "encrypt-uid".to_string(),
+            }))
+            .await
+            .unwrap();
+        server
+            .decrypt(Request::new(DecryptRequest {
+                ciphertext: b"vault:v1:Y2lwaGVydGV4dA==".to_vec(),
+                uid: "decrypt-uid".to_string(),
+                key_id: "1733119760".to_string(),
+                annotations: Default::default(),
+            }))
+            .await
+            .unwrap();
+        server.status(Request::new(StatusRequest {})).await.unwrap();
+
+        let records = records(&path, 3).await;
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert!(!contents.contains("secret"));
+        assert!(!contents.contains(&BASE64_STANDARD.encode("secret")));
+        assert!(!contents.contains("Y2lwaGVydGV4dA=="));
+
+        assert_eq!(records[0]["operation"], "encrypt");
+        assert_eq!(records[0]["uid"], "encrypt-uid");
+        assert_eq!(records[0]["key_id"], "1733119760");
+        assert_eq!(records[0]["key_version"], "2");
+        assert_eq!(records[0]["plaintext_size"], 6);
+        assert_eq!(records[0]["ciphertext_size"], 25);
+        assert_eq!(records[0]["outcome"], "success");
+        assert_eq!(
+            records[0]["vault_request_ids"],
+            serde_json::json!(["encrypt-id", "key-id"])
+        );
+
+        assert_eq!(records[1]["operation"], "decrypt");
+        assert_eq!(records[1]["uid"], "decrypt-uid");
+        assert_eq!(records[1]["key_id"], "1733119760");
+        assert_eq!(records[1]["key_version"], "1");
+        assert_eq!(records[1]["plaintext_size"], 6);
+        assert_eq!(records[1]["ciphertext_size"], 25);
+        assert_eq!(
+            records[1]["vault_request_ids"],
+            serde_json::json!(["decrypt-id"])
+        );
+
+        assert_eq!(records[2]["operation"], "status");
+        assert_eq!(records[2]["key_version"], "2");
+        assert_eq!(records[2]["code"], "Ok");
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn records_failed_requests() {
+        let path = audit_path("audit-failure");
+        let server = server(&path).await;
+
+        server
+            .decrypt(Request::new(DecryptRequest {
+                ciphertext: vec![0xff],
+                uid: "invalid".to_string(),
+                key_id: String::new(),
+                annotations: Default::default(),
+            }))
+            .await
+            .unwrap_err();
+
+        let records = records(&path, 1).await;
+        assert_eq!(records[0]["outcome"], "failure");
+        assert_eq!(records[0]["code"], "Internal");
+        assert_eq!(records[0]["key_id"], Value::Null);
+        assert_eq!(records[0]["plaintext_size"], Value::Null);
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -3,7 +3,9 @@ mod common;
 #[cfg(test)]
 mod authentication {
     use super::common;
-    use lib::configuration::authentication::{AppRole, Certificate, Credentials, Jwt, UserPass};
+    use lib::configuration::authentication::{
+        AppRole, Certificate, Credentials, Jwt, JwtSigner, UserPass,
+    };
     use lib::utilities::logging;
     use lib::utilities::source::Source;
     use lib::vault::Client;
@@ -12,7 +14,7 @@ mod authentication {
 
     async fn test_login_with_credentials(credentials: Credentials) {
         let mut client_config = common::server_config();
-        client_config.vault.credentials = credentials;
+        client_config.vault.credentials = vec![credentials];
         let settings = VaultClientSettingsBuilder::default()
             .address(client_config.vault.address.clone())
             .identity(client_config.tls.identity())
@@ -30,16 +32,16 @@ mod authentication {
     #[tokio::test]
     async fn login_with_username_and_password() {
         test_login_with_credentials(Credentials::UserPass(UserPass {
-            username: "vault-kms-provider".to_string(),
+            username: Source::Value("vault-kms-provider".to_string()),
             password: Source::Value("password".to_string()),
-            mount_path: "userpass".to_string(),
+            mount_path: Source::Value("userpass".to_string()),
         }))
         .await;
     }
 
     #[tokio::test]
     async fn login_with_app_role() {
-        logging::initialize();
+        logging::initialize().unwrap();
         let role_id = fs::read_to_string("./test_files/role_id")
             .unwrap()
             .trim()
@@ -49,7 +51,7 @@ mod authentication {
             .trim()
             .to_string();
         test_login_with_credentials(Credentials::AppRole(AppRole::new(
-            role_id,
+            Source::Value(role_id),
             Source::Value(secret_id),
             None,
         )))
@@ -61,7 +63,24 @@ mod authentication {
         let jwt = fs::read_to_string("./test_files/jwt/token").unwrap();
         test_login_with_credentials(Credentials::Jwt(Jwt::new(
             Source::Value(jwt.to_string()),
-            Some("vault-kms-provider".to_string()),
+            Some(Source::Value("vault-kms-provider".to_string())),
+            None,
+        )))
+        .await;
+    }
+
+    #[tokio::test]
+    async fn login_with_signed_jwt() {
+        test_login_with_credentials(Credentials::Jwt(Jwt::signed(
+            JwtSigner::new(
+                Source::FilePath("./test_files/jwt/private_key.pem".to_string()),
+                None,
+                None,
+                None,
+                None,
+                None,
+            ),
+            Some(Source::Value("vault-kms-provider".to_string())),
             None,
         )))
         .await;
@@ -70,7 +89,7 @@ mod authentication {
     #[tokio::test]
     async fn login_with_certificate() {
         test_login_with_credentials(Credentials::Certificate(Certificate::new(
-            "vault-kms-provider".to_string(),
+            Source::Value("vault-kms-provider".to_string()),
             None,
         )))
         .await;
@@ -0,0 +1,152 @@
+mod common;
+
+#[cfg(test)]
+mod aws {
+    use super::common;
+    use base64::{prelude::BASE64_STANDARD, Engine};
+    use lib::configuration::authentication::{Aws, Credentials};
+    use lib::configuration::vault::Timeouts;
+    use lib::utilities::source::Source;
+    use lib::vault::Client;
+    use pretty_assertions::assert_eq;
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use uuid::Uuid;
+    use vaultrs::client::{VaultClient, VaultClientSettingsBuilder};
+
+    const STS_RESPONSE: &str = r#"<AssumeRoleWithWebIdentityResponse>
+  <AssumeRoleWithWebIdentityResult>
+    <Credentials>
+      <AccessKeyId>ASIAFAKEACCESSKEY</AccessKeyId>
+      <SecretAccessKey>fake-secret-access-key</SecretAccessKey>
+      <SessionToken>fake-session-token</SessionToken>
+      <Expiration>2030-01-01T00:00:00Z</Expiration>
+    </Credentials>
+  </AssumeRoleWithWebIdentityResult>
+</AssumeRoleWithWebIdentityResponse>"#;
+
+    fn decode(value: &serde_json::Value) -> String {
+        String::from_utf8(BASE64_STANDARD.decode(value.as_str().unwrap()).unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn login_with_aws_web_identity() {
+        let requests: Arc<Mutex<Vec<common::StandInRequest>>> = Arc::new(Mutex::new(vec![]));
+        let recorded = requests.clone();
+        let address = common::stand_in(move |request| {
+            recorded.lock().unwrap().push(request.clone());
+            if request.body.contains("Action=AssumeRoleWithWebIdentity") {
+                (200, STS_RESPONSE.to_string())
+            } else if request.uri == "/v1/auth/aws/login" {
+                (200, common::auth_response("aws-token"))
+            } else {
+                (404, "{}".to_string())
+            }
+        })
+        .await;
+        let token_file = format!("./test_files/aws-web-identity-{}", Uuid::new_v4());
+        std::fs::write(&token_file, "web-identity-token").unwrap();
+        unsafe {
+            std::env::set_var("AWS_WEB_IDENTITY_TOKEN_FILE", &token_file);
+            std::env::set_var("AWS_ROLE_ARN", "arn:aws:iam::123456789012:role/kms");
+        }
+
+        let mut config = common::server_config();
+        config.vault.address = format!("http://{}", address);
+        config.vault.credentials = vec![Credentials::Aws(Aws::new(
+            Some(Source::Value("vault-kms-provider".to_string())),
+            Some(Source::Value("vault.example.com".to_string())),
+            None,
+            None,
+            Some(format!("http://{}", address)),
+        ))];
+        let settings = VaultClientSettingsBuilder::default()
+            .address(&config.vault.address)
+            .build()
+            .unwrap();
+        let client = Client::new(VaultClient::new(settings).unwrap(), &config.vault);
+
+        assert_eq!(*client.get_token().await.unwrap(), "aws-token".to_string());
+
+        let requests = requests.lock().unwrap();
+        let sts_request = requests
+            .iter()
+            .find(|request| request.body.contains("Action=AssumeRoleWithWebIdentity"))
+            .unwrap();
+        assert_eq!(sts_request.method, "POST");
+        assert_eq!(sts_request.uri, "/");
+        assert!(sts_request
+            .body
+            .contains("WebIdentityToken=web-identity-token"));
+        let login = requests
+            .iter()
+            .find(|request| request.uri == "/v1/auth/aws/login")
+            .unwrap();
+        assert_eq!(login.method, "POST");
+        assert_eq!(login.headers["x-vault-request"], "true");
+        let body: serde_json::Value = serde_json::from_str(&login.body).unwrap();
+        let headers: HashMap<String, Vec<String>> =
+            serde_json::from_str(&decode(&body["iam_request_headers"])).unwrap();
+        assert_eq!(body["role"], "vault-kms-provider");
+        assert_eq!(body["iam_http_request_method"], "POST");
+        assert_eq!(
+            decode(&body["iam_request_url"]),
+            format!("http://{}/", address)
+        );
+        assert_eq!(
+            decode(&body["iam_request_body"]),
+            "Action=GetCallerIdentity&Version=2011-06-15"
+        );
+        assert_eq!(
+            headers["X-Vault-AWS-IAM-Server-ID"],
+            vec!["vault.example.com".to_string()]
+        );
+        assert_eq!(
+            headers["X-Amz-Security-Token"],
+            vec!["fake-session-token".to_string()]
+        );
+        assert!(headers["Authorization"][0]
+            .starts_with("AWS4-HMAC-SHA256 Credential=ASIAFAKEACCESSKEY/"));
+        assert!(headers["Authorization"][0].contains("/us-east-1/sts/aws4_request"));
+    }
+
+    #[tokio::test]
+    async fn gives_up_on_an_unresponsive_sts_endpoint() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut connections = vec![];
+            while let Ok((connection, _)) = listener.accept().await {
+                connections.push(connection);
+            }
+        });
+        let token_file = format!("./test_files/aws-web-identity-{}", Uuid::new_v4());
+        std::fs::write(&token_file, "web-identity-token").unwrap();
+        unsafe {
+            std::env::set_var("AWS_WEB_IDENTITY_TOKEN_FILE", &token_file);
+            std::env::set_var("AWS_ROLE_ARN", "arn:aws:iam::123456789012:role/kms");
+        }
+
+        let mut config = common::server_config();
+        config.vault.address = format!("http://{}", address);
+        config.vault.timeouts = Timeouts::new(None, Some(Duration::from_millis(200)));
+        config.vault.credentials = vec![Credentials::Aws(Aws::new(
+            None,
+            None,
+            None,
+            None,
+            Some(format!("http://{}", address)),
+        ))];
+        let settings = VaultClientSettingsBuilder::default()
+            .address(&config.vault.address)
+            .build()
+            .unwrap();
+        let client = Client::new(VaultClient::new(settings).unwrap(), &config.vault);
+
+        let result = tokio::time::timeout(Duration::from_secs(5), client.get_token())
+            .await
+            .expect("login did not time out");
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,112 @@
+mod common;
+
+#[cfg(test)]
+mod azure {
+    use super::common;
+    use lib::configuration::authentication::{Azure, Credentials};
+    use lib::configuration::vault::Timeouts;
+    use lib::utilities::source::Source;
+    use lib::vault::Client;
+    use pretty_assertions::assert_eq;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use vaultrs::client::{VaultClient, VaultClientSettingsBuilder};
+
+    const TOKEN_RESPONSE: &str = r#"{"access_token": "managed-identity-token", "expires_in": "3599", "resource": "https://management.azure.com/", "token_type": "Bearer"}"#;
+    const INSTANCE_RESPONSE: &str = r#"{"compute": {"name": "control-plane-0", "resourceGroupName": "kubernetes", "subscriptionId": "00000000-0000-0000-0000-000000000000", "vmScaleSetName": ""}}"#;
+
+    #[tokio::test]
+    async fn login_with_azure_managed_identity() {
+        let requests: Arc<Mutex<Vec<common::StandInRequest>>> = Arc::new(Mutex::new(vec![]));
+        let recorded = requests.clone();
+        let address = common::stand_in(move |request| {
+            recorded.lock().unwrap().push(request.clone());
+            if request.uri.starts_with("/metadata/identity/oauth2/token") {
+                (200, TOKEN_RESPONSE.to_string())
+            } else if request.uri.starts_with("/metadata/instance") {
+                (200, INSTANCE_RESPONSE.to_string())
+            } else if request.uri == "/v1/auth/azure/login" {
+                (200, common::auth_response("azure-token"))
+            } else {
+                (404, "{}".to_string())
+            }
+        })
+        .await;
+
+        let mut config = common::server_config();
+        config.vault.address = format!("http://{}", address);
+        config.vault.credentials = vec![Credentials::Azure(Azure::new(
+            Source::Value("vault-kms-provider".to_string()),
+            None,
+            Some("https://vault.example.com".to_string()),
+            Some(format!("http://{}", address)),
+        ))];
+        let settings = VaultClientSettingsBuilder::default()
+            .address(&config.vault.address)
+            .build()
+            .unwrap();
+        let client = Client::new(VaultClient::new(settings).unwrap(), &config.vault);
+
+        assert_eq!(
+            *client.get_token().await.unwrap(),
+            "azure-token".to_string()
+        );
+
+        let requests = requests.lock().unwrap();
+        let token_request = requests
+            .iter()
+            .find(|request| request.uri.starts_with("/metadata/identity"))
+            .unwrap();
+        assert_eq!(token_request.headers["metadata"], "true");
+        assert!(token_request
+            .uri
+            .contains("resource=https%3A%2F%2Fvault.example.com"));
+        let login = requests
+            .iter()
+            .find(|request| request.uri == "/v1/auth/azure/login")
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_str(&login.body).unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "role": "vault-kms-provider",
+                "jwt": "managed-identity-token",
+                "subscription_id": "00000000-0000-0000-0000-000000000000",
+                "resource_group_name": "kubernetes",
+                "vm_name": "control-plane-0",
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn gives_up_on_an_unresponsive_metadata_service() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut connections = vec![];
+            while let Ok((connection, _)) = listener.accept().await {
+                connections.push(connection);
+            }
+        });
+
+        let mut config = common::server_config();
+        config.vault.address = format!("http://{}", address);
+        config.vault.timeouts = Timeouts::new(None, Some(Duration::from_millis(200)));
+        config.vault.credentials = vec![Credentials::Azure(Azure::new(
+            Source::Value("vault-kms-provider".to_string()),
+            None,
+            None,
+            Some(format!("http://{}", address)),
+        ))];
+        let settings = VaultClientSettingsBuilder::default()
+            .address(&config.vault.address)
+            .build()
+            .unwrap();
+        let client = Client::new(VaultClient::new(settings).unwrap(), &config.vault);
+
+        let result = tokio::time::timeout(Duration::from_secs(5), client.get_token())
+            .await
+            .expect("login did not time out");
+        assert!(result.is_err());
+    }
+}
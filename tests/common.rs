@@ -1,21 +1,37 @@
+// Shared by every test binary, each of which only uses some of the helpers.
+#![allow(dead_code)]
+
+use bytes::Bytes;
+use http::Response;
+use http_body_util::{BodyExt, Full};
+use hyper::body::Incoming;
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper_util::rt::TokioIo;
+use lib::configuration::audit::AuditConfiguration;
 use lib::configuration::authentication::Credentials;
 use lib::configuration::health::HealthCheckConfiguration;
+use lib::configuration::limits::LimitsConfiguration;
+use lib::configuration::memory::MemoryConfiguration;
 use lib::configuration::socket::SocketConfiguration;
 use lib::configuration::tls::TlsConfiguration;
-use lib::configuration::vault::VaultConfiguration;
-use lib::configuration::{tls, ServerConfiguration};
-use lib::kms::{
-    key_management_service_client::KeyManagementServiceClient,
-    key_management_service_server::KeyManagementServiceServer,
-};
+use lib::configuration::vault::{RateLimit, Timeouts, VaultConfiguration};
+use lib::configuration::ServerConfiguration;
+use lib::kms::key_management_service_client::KeyManagementServiceClient;
 use lib::server;
 use lib::utilities::socket::Socket;
 use lib::utilities::source::Source;
 use log::debug;
+use std::collections::HashMap;
+use std::convert::Infallible;
 use std::ffi::OsString;
 use std::future::Future;
+use std::net::SocketAddr;
 use std::sync::atomic::{AtomicU16, Ordering};
-use std::sync::OnceLock;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::{TcpListener, UnixListener};
 use tokio::select;
 use tonic::transport::Channel;
 use uuid::Uuid;
@@ -32,6 +48,12 @@ pub fn server_config() -> ServerConfiguration {
     ServerConfiguration {
         health: HealthCheckConfiguration {
             endpoint: format!("127.0.0.1:808{}", num),
+            admin_token: None,
+            cert: None,
+            key: None,
+            client_ca: None,
+            liveness_threshold: Duration::from_secs(60),
+            selftest_on_startup: false,
         },
         socket: SocketConfiguration {
             socket_path: format!("@test_files/kms-{}.sock", id),
@@ -39,9 +61,15 @@ pub fn server_config() -> ServerConfiguration {
         },
         vault: VaultConfiguration {
             address: "https://localhost:8400".to_string(),
+            failover_addresses: vec![],
+            timeouts: Timeouts::default(),
+            rate_limit: RateLimit::default(),
             transit_key: "vault-kms-provider".to_string(),
             mount_path: "transit".to_string(),
-            credentials: Credentials::Token(Source::Value("SiQOECxwSDCeQt1r0n5kqQCr".to_string())),
+            canary_interval: None,
+            credentials: vec![Credentials::Token(Source::Value(
+                "SiQOECxwSDCeQt1r0n5kqQCr".to_string(),
+            ))],
         },
         tls: TlsConfiguration {
             cert: Some("./test_files/certs/tls.crt".to_string()),
@@ -49,6 +77,9 @@ pub fn server_config() -> ServerConfiguration {
             ca: Some("./test_files/certs/ca.crt".to_string()),
             directory: None,
         },
+        limits: LimitsConfiguration::default(),
+        audit: AuditConfiguration::new(None, None, None),
+        memory: MemoryConfiguration::new(None, None),
     }
 }
 
@@ -68,9 +99,8 @@ where
 {
     select! {
         r = async {
-            server(config).await.map_err(|e| {
-                debug!("Server Error!: {}", e.to_string());
-                e
+            server(config).await.inspect_err(|e| {
+                debug!("Server Error!: {}", e);
             }).unwrap();
         } => r,
         r = async {
@@ -79,3 +109,123 @@ where
         } => r,
     }
 }
+
+#[derive(Clone, Debug)]
+pub struct StandInRequest {
+    pub method: String,
+    pub uri: String,
+    pub headers: HashMap<String, String>,
+    pub body: String,
+}
+
+#[derive(Clone, Debug)]
+pub struct StandInResponse {
+    pub status: u16,
+    pub body: String,
+    pub headers: Vec<(&'static str, String)>,
+}
+
+impl From<(u16, String)> for StandInResponse {
+    fn from((status, body): (u16, String)) -> Self {
+        Self {
+            status,
+            body,
+            headers: vec![],
+        }
+    }
+}
+
+fn serve<F, R, S>(stream: S, respond: Arc<F>)
+where
+    F: Fn(&StandInRequest) -> R + Send + Sync + 'static,
+    R: Into<StandInResponse>,
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    tokio::spawn(async move {
+        let _ = http1::Builder::new()
+            .serve_connection(
+                TokioIo::new(stream),
+                service_fn(move |request: http::Request<Incoming>| {
+                    let respond = respond.clone();
+                    async move {
+                        let (parts, body) = request.into_parts();
+                        let body = body.collect().await.unwrap().to_bytes();
+                        let request = StandInRequest {
+                            method: parts.method.to_string(),
+                            uri: parts.uri.to_string(),
+                            headers: parts
+                                .headers
+                                .iter()
+                                .map(|(name, value)| {
+                                    (
+                                        name.to_string(),
+                                        value.to_str().unwrap_or_default().to_string(),
+                                    )
+                                })
+                                .collect(),
+                            body: String::from_utf8_lossy(&body).to_string(),
+                        };
+                        let response: StandInResponse = respond(&request).into();
+                        let builder = response.headers.iter().fold(
+                            Response::builder()
+                                .status(response.status)
+                                .header("Content-Type", "application/json"),
+                            |builder, (name, value)| builder.header(*name, value),
+                        );
+                        Ok::<_, Infallible>(
+                            builder.body(Full::new(Bytes::from(response.body))).unwrap(),
+                        )
+                    }
+                }),
+            )
+            .await;
+    });
+}
+
+/// Starts a local HTTP server standing in for an external service (Vault, STS, metadata endpoints, etc.)
+pub async fn stand_in<F, R>(respond: F) -> SocketAddr
+where
+    F: Fn(&StandInRequest) -> R + Send + Sync + 'static,
+    R: Into<StandInResponse>,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    let respond = Arc::new(respond);
+    tokio::spawn(async move {
+        loop {
+            let (stream, _) = listener.accept().await.unwrap();
+            serve(stream, respond.clone());
+        }
+    });
+    address
+}
+
+/// Starts a local HTTP server standing in for an external service, listening on a unix socket.
+pub fn stand_in_unix<F, R>(path: &str, respond: F)
+where
+    F: Fn(&StandInRequest) -> R + Send + Sync + 'static,
+    R: Into<StandInResponse>,
+{
+    let listener = UnixListener::bind(path).unwrap();
+    let respond = Arc::new(respond);
+    tokio::spawn(async move {
+        loop {
+            let (stream, _) = listener.accept().await.unwrap();
+            serve(stream, respond.clone());
+        }
+    });
+}
+
+pub fn auth_response(token: &str) -> String {
+    format!(
+        r#"{{"request_id": "request", "lease_id": "", "renewable": false, "lease_duration": 0, "data": null, "wrap_info": null, "warnings": null, "auth": {{"client_token": "{}", "accessor": "accessor", "policies": ["default"], "token_policies": ["default"], "metadata": null, "lease_duration": 3600, "renewable": true, "entity_id": "entity", "token_type": "service", "orphan": true}}}}"#,
+        token
+    )
+}
+
+pub fn data_response(data: &str) -> String {
+    format!(
+        r#"{{"request_id": "request", "lease_id": "", "renewable": false, "lease_duration": 0, "data": {}, "wrap_info": null, "warnings": null, "auth": null}}"#,
+        data
+    )
+}
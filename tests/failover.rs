@@ -0,0 +1,161 @@
+mod common;
+
+#[cfg(test)]
+mod failover {
+    use super::common;
+    use base64::{prelude::BASE64_STANDARD, Engine};
+    use lib::configuration::authentication::{Credentials, UserPass};
+    use lib::configuration::ServerConfiguration;
+    use lib::utilities::source::Source;
+    use lib::utilities::watcher::Refresh;
+    use lib::vault::Cluster;
+    use pretty_assertions::assert_eq;
+    use std::net::SocketAddr;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    type Requests = Arc<Mutex<Vec<common::StandInRequest>>>;
+
+    async fn stand_in_vault(
+        token: &'static str,
+        sealed: Arc<AtomicBool>,
+        requests: Requests,
+    ) -> SocketAddr {
+        common::stand_in(move |request| {
+            requests.lock().unwrap().push(request.clone());
+            if sealed.load(Ordering::SeqCst) {
+                return (503, r#"{"errors": ["Vault is sealed"]}"#.to_string());
+            }
+            match request.uri.split('?').next().unwrap() {
+                "/v1/sys/health" => (200, r#"{"initialized": true, "sealed": false}"#.to_string()),
+                "/v1/auth/userpass/login/vault-kms-provider" => (200, common::auth_response(token)),
+                "/v1/transit/encrypt/vault-kms-provider" => (
+                    200,
+                    common::data_response(&format!(r#"{{"ciphertext": "vault:v1:{}"}}"#, token)),
+                ),
+                "/v1/transit/keys/vault-kms-provider" => {
+                    (403, r#"{"errors": ["permission denied"]}"#.to_string())
+                }
+                _ => (404, "{}".to_string()),
+            }
+        })
+        .await
+    }
+
+    fn config(addresses: Vec<String>) -> ServerConfiguration {
+        let mut config = common::server_config();
+        config.vault.address = addresses[0].clone();
+        config.vault.failover_addresses = addresses[1..].to_vec();
+        config.vault.credentials = vec![Credentials::UserPass(UserPass::new(
+            Source::Value("vault-kms-provider".to_string()),
+            Source::Value("password".to_string()),
+            None,
+        ))];
+        config
+    }
+
+    fn tokens_sent_to(requests: &Requests, uri: &str) -> Vec<String> {
+        requests
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|request| request.uri == uri)
+            .map(|request| request.headers["x-vault-token"].clone())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn fails_over_and_logs_into_a_healthy_node_when_the_active_node_is_sealed() {
+        let primary_sealed = Arc::new(AtomicBool::new(false));
+        let primary_requests: Requests = Arc::new(Mutex::new(vec![]));
+        let standby_requests: Requests = Arc::new(Mutex::new(vec![]));
+        let primary = stand_in_vault(
+            "primary-token",
+            primary_sealed.clone(),
+            primary_requests.clone(),
+        )
+        .await;
+        let standby = stand_in_vault(
+            "standby-token",
+            Arc::new(AtomicBool::new(false)),
+            standby_requests.clone(),
+        )
+        .await;
+        let config = config(vec![
+            format!("http://{}", primary),
+            format!("http://{}", standby),
+        ]);
+        let cluster = Cluster::connect(&config.vault, &config.tls).unwrap();
+        cluster.refresh_token().await.unwrap();
+        primary_sealed.store(true, Ordering::SeqCst);
+
+        let ciphertext = cluster
+            .request_encryption(&BASE64_STANDARD.encode("secret"))
+            .await
+            .unwrap();
+
+        assert_eq!(ciphertext, "vault:v1:standby-token");
+        assert_eq!(cluster.active_address(), format!("http://{}", standby));
+        assert_eq!(
+            tokens_sent_to(&standby_requests, "/v1/transit/encrypt/vault-kms-provider"),
+            vec!["standby-token".to_string()]
+        );
+    }
+
+    #[tokio::test]
+    async fn fails_over_when_the_active_node_is_unreachable() {
+        let unreachable = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let standby_requests: Requests = Arc::new(Mutex::new(vec![]));
+        let standby = stand_in_vault(
+            "standby-token",
+            Arc::new(AtomicBool::new(false)),
+            standby_requests.clone(),
+        )
+        .await;
+        let config = config(vec![
+            format!("http://{}", unreachable),
+            format!("http://{}", standby),
+        ]);
+        let cluster = Cluster::connect(&config.vault, &config.tls).unwrap();
+        cluster.refresh_token().await.unwrap();
+
+        let ciphertext = cluster
+            .request_encryption(&BASE64_STANDARD.encode("secret"))
+            .await
+            .unwrap();
+
+        assert_eq!(ciphertext, "vault:v1:standby-token");
+        assert_eq!(cluster.active_address(), format!("http://{}", standby));
+    }
+
+    #[tokio::test]
+    async fn does_not_fail_over_when_a_request_is_rejected() {
+        let primary_requests: Requests = Arc::new(Mutex::new(vec![]));
+        let standby_requests: Requests = Arc::new(Mutex::new(vec![]));
+        let primary = stand_in_vault(
+            "primary-token",
+            Arc::new(AtomicBool::new(false)),
+            primary_requests.clone(),
+        )
+        .await;
+        let standby = stand_in_vault(
+            "standby-token",
+            Arc::new(AtomicBool::new(false)),
+            standby_requests.clone(),
+        )
+        .await;
+        let config = config(vec![
+            format!("http://{}", primary),
+            format!("http://{}", standby),
+        ]);
+        let cluster = Cluster::connect(&config.vault, &config.tls).unwrap();
+        cluster.refresh_token().await.unwrap();
+
+        assert!(cluster.request_key().await.is_err());
+        assert_eq!(cluster.active_address(), format!("http://{}", primary));
+        assert_eq!(standby_requests.lock().unwrap().len(), 0);
+    }
+}
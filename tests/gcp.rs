@@ -0,0 +1,109 @@
+mod common;
+
+#[cfg(test)]
+mod gcp {
+    use super::common;
+    use lib::configuration::authentication::{Credentials, Gcp};
+    use lib::utilities::source::Source;
+    use lib::vault::Client;
+    use pretty_assertions::assert_eq;
+    use std::sync::{Arc, Mutex};
+    use vaultrs::client::{VaultClient, VaultClientSettingsBuilder};
+
+    fn client(address: &str, gcp: Gcp) -> Client {
+        let mut config = common::server_config();
+        config.vault.address = address.to_string();
+        config.vault.credentials = vec![Credentials::Gcp(gcp)];
+        let settings = VaultClientSettingsBuilder::default()
+            .address(&config.vault.address)
+            .build()
+            .unwrap();
+        Client::new(VaultClient::new(settings).unwrap(), &config.vault)
+    }
+
+    #[tokio::test]
+    async fn login_with_gcp_instance_identity() {
+        let requests: Arc<Mutex<Vec<common::StandInRequest>>> = Arc::new(Mutex::new(vec![]));
+        let recorded = requests.clone();
+        let address = common::stand_in(move |request| {
+            recorded.lock().unwrap().push(request.clone());
+            if request.uri.starts_with("/computeMetadata/v1/instance") {
+                (200, "instance-identity-token".to_string())
+            } else if request.uri == "/v1/auth/gcp/login" {
+                (200, common::auth_response("gcp-token"))
+            } else {
+                (404, "{}".to_string())
+            }
+        })
+        .await;
+        let address = format!("http://{}", address);
+        let client = client(
+            &address,
+            Gcp::new(
+                Source::Value("vault-kms-provider".to_string()),
+                None,
+                None,
+                Some(address.clone()),
+            ),
+        );
+
+        assert_eq!(*client.get_token().await.unwrap(), "gcp-token".to_string());
+
+        let requests = requests.lock().unwrap();
+        let identity = requests
+            .iter()
+            .find(|request| request.uri.starts_with("/computeMetadata"))
+            .unwrap();
+        assert_eq!(identity.headers["metadata-flavor"], "Google");
+        assert!(identity
+            .uri
+            .contains("audience=http%3A%2F%2Fvault%2Fvault-kms-provider&format=full"));
+        let login = requests
+            .iter()
+            .find(|request| request.uri == "/v1/auth/gcp/login")
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_str(&login.body).unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!({"role": "vault-kms-provider", "jwt": "instance-identity-token"})
+        );
+    }
+
+    #[tokio::test]
+    async fn login_with_a_gcp_service_account_key() {
+        let requests: Arc<Mutex<Vec<common::StandInRequest>>> = Arc::new(Mutex::new(vec![]));
+        let recorded = requests.clone();
+        let address = common::stand_in(move |request| {
+            recorded.lock().unwrap().push(request.clone());
+            if request.uri == "/v1/auth/custom-gcp/login" {
+                (200, common::auth_response("gcp-token"))
+            } else {
+                (404, "{}".to_string())
+            }
+        })
+        .await;
+        let key = serde_json::json!({
+            "type": "service_account",
+            "private_key_id": "key-id",
+            "private_key": std::fs::read_to_string("./test_files/jwt/private_key.pem").unwrap(),
+            "client_email": "kms@project.iam.gserviceaccount.com",
+        });
+        let client = client(
+            &format!("http://{}", address),
+            Gcp::new(
+                Source::Value("vault-kms-provider".to_string()),
+                Some(Source::Value(key.to_string())),
+                Some(Source::Value("custom-gcp".to_string())),
+                None,
+            ),
+        );
+
+        assert_eq!(*client.get_token().await.unwrap(), "gcp-token".to_string());
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 1);
+        let body: serde_json::Value = serde_json::from_str(&requests[0].body).unwrap();
+        assert_eq!(body["role"], "vault-kms-provider");
+        assert_eq!(body["jwt"].as_str().unwrap().split('.').count(), 3);
+    }
+}
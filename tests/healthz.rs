@@ -0,0 +1,163 @@
+mod common;
+
+#[cfg(test)]
+mod healthz {
+    use super::common;
+    use lib::audit::Auditor;
+    use lib::configuration::audit::AuditConfiguration;
+    use lib::kms::key_management_service_server::KeyManagementService;
+    use lib::kms::StatusRequest;
+    use lib::utilities::backoff::Backoff;
+    use lib::utilities::heartbeat::{Heartbeat, Heartbeats};
+    use lib::vault::{Cluster, VaultKmsServer};
+    use pretty_assertions::assert_eq;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use tokio::sync::RwLock;
+    use tonic::Request;
+
+    const KEY_ID: &str = "1733119760";
+
+    fn key(supports_encryption: bool) -> String {
+        format!(
+            r#"{{"type": "aes256-gcm96", "deletion_allowed": false, "derived": false, "exportable": false, "allow_plaintext_backup": false, "keys": {{"1": 1733119759, "2": {}}}, "min_decryption_version": 1, "min_encryption_version": 0, "name": "vault-kms-provider", "supports_encryption": {}, "supports_decryption": true, "supports_derivation": true, "supports_signing": false}}"#,
+            KEY_ID, supports_encryption
+        )
+    }
+
+    #[derive(Clone, Copy, Debug)]
+    enum Vault {
+        Available,
+        Sealed,
+        KeyDeleted,
+        KeyNotEncryptable,
+        DeniesEncryption,
+    }
+
+    fn heartbeat() -> Heartbeat {
+        Heartbeats::new(Duration::from_secs(60)).register("task")
+    }
+
+    async fn server(vault: Arc<Mutex<Vault>>) -> VaultKmsServer {
+        let address = common::stand_in(move |request| {
+            let vault = *vault.lock().unwrap();
+            match (vault, request.uri.as_str()) {
+                (Vault::Sealed, _) => (503, r#"{"errors": ["Vault is sealed"]}"#.to_string()),
+                (Vault::KeyDeleted, _) => (404, r#"{"errors": []}"#.to_string()),
+                (Vault::DeniesEncryption, "/v1/transit/encrypt/vault-kms-provider") => (
+                    403,
+                    r#"{"errors": ["1 error occurred:\n\t* permission denied\n\n"]}"#.to_string(),
+                ),
+                (_, "/v1/transit/encrypt/vault-kms-provider") => (
+                    200,
+                    common::data_response(r#"{"ciphertext": "vault:v2:Y2lwaGVydGV4dA=="}"#),
+                ),
+                (vault, "/v1/transit/keys/vault-kms-provider") => (
+                    200,
+                    common::data_response(&key(!matches!(vault, Vault::KeyNotEncryptable))),
+                ),
+                _ => (404, r#"{"errors": []}"#.to_string()),
+            }
+        })
+        .await;
+        let mut config = common::server_config();
+        config.vault.address = format!("http://{}", address);
+        let server = VaultKmsServer::new(
+            Arc::new(RwLock::new(
+                Cluster::connect(&config.vault, &config.tls).unwrap(),
+            )),
+            Auditor::new(&AuditConfiguration::new(None, None, None)).unwrap(),
+        );
+        tokio::time::timeout(
+            Duration::from_secs(5),
+            server.initialize(Backoff::default(), heartbeat()),
+        )
+        .await
+        .expect("initialization did not complete");
+        server
+    }
+
+    async fn status(server: &VaultKmsServer) -> (String, String) {
+        let status = server
+            .status(Request::new(StatusRequest {}))
+            .await
+            .unwrap()
+            .into_inner();
+        (status.healthz, status.key_id)
+    }
+
+    #[tokio::test]
+    async fn reports_ok_when_the_key_can_be_used() {
+        let server = server(Arc::new(Mutex::new(Vault::Available))).await;
+        assert_eq!(
+            status(&server).await,
+            ("ok".to_string(), KEY_ID.to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn reports_the_last_known_key_id_when_vault_fails() {
+        let vault = Arc::new(Mutex::new(Vault::Available));
+        let server = server(vault.clone()).await;
+        status(&server).await;
+        for (state, healthz) in [
+            (Vault::Sealed, "vault_sealed: Vault is sealed"),
+            (
+                Vault::KeyDeleted,
+                "key_deleted: The transit key does not exist",
+            ),
+        ] {
+            *vault.lock().unwrap() = state;
+            assert_eq!(
+                status(&server).await,
+                (healthz.to_string(), KEY_ID.to_string()),
+                "{:?}",
+                state
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn reports_keys_that_do_not_support_encryption() {
+        let vault = Arc::new(Mutex::new(Vault::Available));
+        let server = server(vault.clone()).await;
+        *vault.lock().unwrap() = Vault::KeyNotEncryptable;
+        assert_eq!(
+            status(&server).await,
+            (
+                "key_not_encryptable: The transit key does not support encryption".to_string(),
+                KEY_ID.to_string()
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn reports_policies_denying_the_canary_encryption() {
+        let vault = Arc::new(Mutex::new(Vault::Available));
+        let server = server(vault.clone()).await;
+        *vault.lock().unwrap() = Vault::DeniesEncryption;
+        let _ = tokio::time::timeout(
+            Duration::from_millis(100),
+            server.canary(Duration::from_millis(10), heartbeat()),
+        )
+        .await;
+        assert_eq!(
+            status(&server).await,
+            (
+                "policy_denied: The token's policies do not allow encrypting with the transit key"
+                    .to_string(),
+                KEY_ID.to_string()
+            )
+        );
+        *vault.lock().unwrap() = Vault::Available;
+        let _ = tokio::time::timeout(
+            Duration::from_millis(100),
+            server.canary(Duration::from_millis(10), heartbeat()),
+        )
+        .await;
+        assert_eq!(
+            status(&server).await,
+            ("ok".to_string(), KEY_ID.to_string())
+        );
+    }
+}
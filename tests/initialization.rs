@@ -0,0 +1,169 @@
+mod common;
+
+#[cfg(test)]
+mod initialization {
+    use super::common;
+    use lib::audit::Auditor;
+    use lib::configuration::audit::AuditConfiguration;
+    use lib::kms::key_management_service_server::KeyManagementService;
+    use lib::kms::StatusRequest;
+    use lib::utilities::backoff::Backoff;
+    use lib::utilities::heartbeat::{Heartbeat, Heartbeats, TaskStatus};
+    use lib::vault::{Cluster, VaultKmsServer, LIVENESS_PROBE};
+    use pretty_assertions::assert_eq;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::sync::RwLock;
+    use tonic::{Code, Request, Status};
+
+    const KEY: &str = r#"{"type": "aes256-gcm96", "deletion_allowed": false, "derived": false, "exportable": false, "allow_plaintext_backup": false, "keys": {"1": 1733119759, "2": 1733119760}, "min_decryption_version": 1, "min_encryption_version": 0, "name": "vault-kms-provider", "supports_encryption": true, "supports_decryption": true, "supports_derivation": true, "supports_signing": false}"#;
+
+    /// Heartbeat for tests that do not check liveness.
+    fn heartbeat() -> Heartbeat {
+        Heartbeats::new(Duration::from_secs(60)).register("task")
+    }
+
+    /// Vault rejecting the first `unavailable` encryptions, as if it was still starting.
+    async fn server(unavailable: u32) -> (VaultKmsServer, Arc<RwLock<Cluster>>) {
+        let attempts = Arc::new(AtomicU32::new(0));
+        let address = common::stand_in(move |request| match request.uri.as_str() {
+            "/v1/transit/encrypt/vault-kms-provider" => {
+                if attempts.fetch_add(1, Ordering::Relaxed) < unavailable {
+                    (503, r#"{"errors": ["Vault is sealed"]}"#.to_string())
+                } else {
+                    (
+                        200,
+                        common::data_response(r#"{"ciphertext": "vault:v1:Y2lwaGVydGV4dA=="}"#),
+                    )
+                }
+            }
+            "/v1/transit/keys/vault-kms-provider" => (200, common::data_response(KEY)),
+            _ => (404, r#"{"errors": []}"#.to_string()),
+        })
+        .await;
+        let mut config = common::server_config();
+        config.vault.address = format!("http://{}", address);
+        let client = Arc::new(RwLock::new(
+            Cluster::connect(&config.vault, &config.tls).unwrap(),
+        ));
+        let server = VaultKmsServer::new(
+            client.clone(),
+            Auditor::new(&AuditConfiguration::new(None, None, None)).unwrap(),
+        );
+        (server, client)
+    }
+
+    fn backoff() -> Backoff {
+        Backoff::new(Duration::from_millis(10), Duration::from_millis(20))
+    }
+
+    async fn healthz(server: &VaultKmsServer) -> (String, String) {
+        let status = server
+            .status(Request::new(StatusRequest {}))
+            .await
+            .unwrap()
+            .into_inner();
+        (status.healthz, status.key_id)
+    }
+
+    async fn probe(server: &VaultKmsServer) -> Result<(String, String), Status> {
+        let mut request = Request::new(StatusRequest {});
+        request
+            .metadata_mut()
+            .insert(LIVENESS_PROBE, "1".parse().unwrap());
+        request
+            .metadata_mut()
+            .insert("grpc-timeout", "100m".parse().unwrap());
+        let status = server.status(request).await?.into_inner();
+        Ok((status.healthz, status.key_id))
+    }
+
+    #[tokio::test]
+    async fn reports_that_initialization_is_pending_before_it_starts() {
+        let (server, _) = server(0).await;
+        assert_eq!(
+            healthz(&server).await,
+            (
+                "Vault encryption has not been initialized yet".to_string(),
+                "".to_string()
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn reports_status_without_waiting_on_the_vault_client() {
+        let (server, client) = server(0).await;
+        let _locked = client.write().await;
+        let (healthz, _) = tokio::time::timeout(Duration::from_secs(1), healthz(&server))
+            .await
+            .expect("status waited on the Vault client");
+        assert_eq!(healthz, "Vault encryption has not been initialized yet");
+    }
+
+    #[tokio::test]
+    async fn retries_until_vault_is_available() {
+        let (server, _) = server(2).await;
+        let heartbeats = Heartbeats::new(Duration::from_millis(50));
+        tokio::time::timeout(
+            Duration::from_secs(5),
+            server.initialize(backoff(), heartbeats.register("initialization")),
+        )
+        .await
+        .expect("initialization did not complete");
+        assert!(server.initialization().is_initialized());
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(
+            heartbeats.statuses(),
+            vec![("initialization", TaskStatus::Alive)]
+        );
+        assert_eq!(
+            healthz(&server).await,
+            ("ok".to_string(), "1733119760".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn reports_why_initialization_is_failing() {
+        let (server, _) = server(u32::MAX).await;
+        let _ = tokio::time::timeout(
+            Duration::from_millis(200),
+            server.initialize(backoff(), heartbeat()),
+        )
+        .await;
+        assert!(!server.initialization().is_initialized());
+        let (healthz, key_id) = healthz(&server).await;
+        assert!(
+            healthz.starts_with("Vault encryption is not initialized: Failed to encrypt:"),
+            "{}",
+            healthz
+        );
+        assert_eq!(key_id, "");
+    }
+
+    #[tokio::test]
+    async fn answers_liveness_probes_without_calling_vault() {
+        let (server, _) = server(0).await;
+        server.initialize(backoff(), heartbeat()).await;
+        assert_eq!(
+            probe(&server).await.unwrap(),
+            ("ok".to_string(), "".to_string())
+        );
+        healthz(&server).await;
+        assert_eq!(
+            probe(&server).await.unwrap(),
+            ("ok".to_string(), "1733119760".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn fails_liveness_probes_while_the_vault_client_is_locked() {
+        let (server, client) = server(0).await;
+        server.initialize(backoff(), heartbeat()).await;
+        let _locked = client.write().await;
+        assert_eq!(
+            probe(&server).await.unwrap_err().code(),
+            Code::DeadlineExceeded
+        );
+    }
+}
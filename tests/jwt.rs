@@ -0,0 +1,91 @@
+mod common;
+
+#[cfg(test)]
+mod jwt {
+    use super::common;
+    use lib::configuration::authentication::{Credentials, Jwt, JwtSigner};
+    use lib::configuration::ServerConfiguration;
+    use lib::utilities::source::Source;
+    use lib::utilities::watcher::Refresh;
+    use lib::vault::{Client, Cluster};
+    use pretty_assertions::assert_eq;
+    use std::net::SocketAddr;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use vaultrs::client::{VaultClient, VaultClientSettingsBuilder};
+
+    fn config(address: SocketAddr) -> ServerConfiguration {
+        let mut config = common::server_config();
+        config.vault.address = format!("http://{}", address);
+        config.vault.credentials = vec![Credentials::Jwt(Jwt::signed(
+            JwtSigner::new(
+                Source::FilePath("./test_files/jwt/private_key.pem".to_string()),
+                None,
+                None,
+                None,
+                None,
+                Some(Duration::from_secs(60)),
+            ),
+            Some(Source::Value("vault-kms-provider".to_string())),
+            None,
+        ))];
+        config
+    }
+
+    #[tokio::test]
+    async fn login_with_a_signed_jwt_and_refresh_before_the_token_lease_expires() {
+        let requests: Arc<Mutex<Vec<common::StandInRequest>>> = Arc::new(Mutex::new(vec![]));
+        let recorded = requests.clone();
+        let address = common::stand_in(move |request| {
+            recorded.lock().unwrap().push(request.clone());
+            if request.uri == "/v1/auth/jwt/login" {
+                (200, common::auth_response("jwt-token"))
+            } else {
+                (404, "{}".to_string())
+            }
+        })
+        .await;
+
+        let config = config(address);
+        let settings = VaultClientSettingsBuilder::default()
+            .address(&config.vault.address)
+            .build()
+            .unwrap();
+        let mut client = Client::new(VaultClient::new(settings).unwrap(), &config.vault);
+
+        client.refresh_token().await.unwrap();
+        client.refresh_token().await.unwrap();
+
+        assert_eq!(client.refresh_in(), Some(Duration::from_secs(2400)));
+        let requests = requests.lock().unwrap();
+        let jwts: Vec<String> = requests
+            .iter()
+            .map(|request| {
+                assert_eq!(request.method, "POST");
+                let body: serde_json::Value = serde_json::from_str(&request.body).unwrap();
+                assert_eq!(body["role"], "vault-kms-provider");
+                body["jwt"].as_str().unwrap().to_string()
+            })
+            .collect();
+        assert_eq!(jwts.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn schedules_the_refresh_of_the_active_node() {
+        let address = common::stand_in(|request| {
+            if request.uri == "/v1/auth/jwt/login" {
+                (200, common::auth_response("jwt-token"))
+            } else {
+                (404, "{}".to_string())
+            }
+        })
+        .await;
+        let config = config(address);
+        let cluster = Cluster::connect(&config.vault, &config.tls).unwrap();
+        assert_eq!(cluster.refresh_in(), None);
+
+        cluster.refresh_token().await.unwrap();
+
+        assert_eq!(cluster.refresh_in(), Some(Duration::from_secs(2400)));
+    }
+}
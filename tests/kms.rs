@@ -3,11 +3,9 @@ mod common;
 #[cfg(test)]
 mod encryption_and_decryption {
     use super::common;
-    use lib;
     use lib::kms::{DecryptRequest, EncryptRequest};
     use std::ffi::OsString;
     use std::sync::OnceLock;
-    use tokio;
     use tonic::Request;
 
     static UNIX_SOCKET_PATH: OnceLock<OsString> = OnceLock::new();
@@ -59,7 +57,7 @@ mod status {
 
     #[tokio::test]
     async fn returns_ok_status_when_queried() {
-        logging::initialize();
+        logging::initialize().unwrap();
         let config = common::server_config();
         let socket_path = config.socket.socket_path.clone();
         common::run_against_server(config, || async {
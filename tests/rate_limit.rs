@@ -0,0 +1,92 @@
+mod common;
+
+#[cfg(test)]
+mod rate_limit {
+    use super::common;
+    use lib::configuration::vault::RateLimit;
+    use lib::vault::{connect, Client};
+    use pretty_assertions::assert_eq;
+    use std::net::SocketAddr;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+    use tonic::{Code, Status};
+
+    /// Rejects the first `limited` encryption requests as Vault does when a rate limit quota is exceeded.
+    async fn rate_limited_vault(limited: usize, requests: Arc<AtomicUsize>) -> SocketAddr {
+        common::stand_in(move |request| {
+            let count = requests.fetch_add(1, Ordering::SeqCst);
+            match request.uri.as_str() {
+                "/v1/transit/encrypt/vault-kms-provider" if count < limited => common::StandInResponse {
+                    status: 429,
+                    body: r#"{"errors": ["request path \"transit/encrypt/vault-kms-provider\": rate limit quota exceeded"]}"#.to_string(),
+                    headers: vec![("Retry-After", "1".to_string())],
+                },
+                "/v1/transit/encrypt/vault-kms-provider" => (
+                    200,
+                    common::data_response(r#"{"ciphertext": "vault:v1:ciphertext"}"#),
+                )
+                    .into(),
+                _ => (404, "{}".to_string()).into(),
+            }
+        })
+        .await
+    }
+
+    fn client(address: SocketAddr, rate_limit: RateLimit) -> Client {
+        let mut config = common::server_config();
+        config.vault.address = format!("http://{}", address);
+        config.vault.rate_limit = rate_limit;
+        Client::new(
+            connect(&config.vault.address, &config.tls, &config.vault.timeouts).unwrap(),
+            &config.vault,
+        )
+    }
+
+    #[tokio::test]
+    async fn retries_after_the_delay_requested_by_vault() {
+        let requests = Arc::new(AtomicUsize::new(0));
+        let client = client(
+            rate_limited_vault(1, requests.clone()).await,
+            RateLimit::new(None, None, Some(1)),
+        );
+        let start = Instant::now();
+
+        let ciphertext = client.request_encryption("c2VjcmV0").await.unwrap();
+
+        assert_eq!(ciphertext, "vault:v1:ciphertext");
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+        assert!(start.elapsed() >= Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn reports_unavailable_once_retries_are_exhausted() {
+        let requests = Arc::new(AtomicUsize::new(0));
+        let client = client(
+            rate_limited_vault(usize::MAX, requests.clone()).await,
+            RateLimit::new(None, None, Some(0)),
+        );
+
+        let error = client.request_encryption("c2VjcmV0").await.unwrap_err();
+
+        assert!(error.is_rate_limited());
+        assert_eq!(Status::from(error).code(), Code::Unavailable);
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn limits_the_rate_of_requests_sent_to_vault() {
+        let requests = Arc::new(AtomicUsize::new(0));
+        let client = client(
+            rate_limited_vault(0, requests.clone()).await,
+            RateLimit::new(Some(10.0), Some(1), None),
+        );
+        let start = Instant::now();
+
+        for _ in 0..3 {
+            client.request_encryption("c2VjcmV0").await.unwrap();
+        }
+
+        assert!(start.elapsed() >= Duration::from_millis(190));
+    }
+}
@@ -0,0 +1,93 @@
+mod common;
+
+#[cfg(test)]
+mod redaction {
+    use super::common;
+    use lib::configuration::authentication::{Credentials, UserPass};
+    use lib::utilities::source::Source;
+    use lib::vault::{connect, Client};
+    use std::io::Write;
+    use std::sync::{Arc, Mutex};
+    use tracing::Level;
+
+    const PASSWORD: &str = "correct-horse-battery-staple";
+    const TOKEN: &str = "hvs.CAESIJlU4w7IyXGx6Kvn0sUzOpWB";
+    const PLAINTEXT: &str = "c3VwZXItc2VjcmV0LXBhc3N3b3Jk";
+    const CIPHERTEXT: &str =
+        "vault:v1:8SDd3WHDOjf7mq69CyCqYjBXAiQQAVZRkFM13ok481zoCmHnSeDX9vyf7w==";
+
+    #[derive(Clone, Default)]
+    struct LogOutput(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for LogOutput {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl LogOutput {
+        fn contents(&self) -> String {
+            String::from_utf8_lossy(&self.0.lock().unwrap()).to_string()
+        }
+    }
+
+    #[tokio::test]
+    async fn does_not_log_secrets() {
+        let output = LogOutput::default();
+        let writer = output.clone();
+        let _subscriber = tracing::subscriber::set_default(
+            tracing_subscriber::fmt()
+                .with_max_level(Level::TRACE)
+                .with_ansi(false)
+                .with_writer(move || writer.clone())
+                .finish(),
+        );
+        let address = common::stand_in(|request| match request.uri.as_str() {
+            "/v1/auth/userpass/login/vault-kms-provider" => (200, common::auth_response(TOKEN)),
+            "/v1/transit/encrypt/vault-kms-provider" => (
+                200,
+                common::data_response(&format!(r#"{{"ciphertext": "{}"}}"#, CIPHERTEXT)),
+            ),
+            "/v1/transit/decrypt/vault-kms-provider" => (
+                200,
+                common::data_response(&format!(r#"{{"plaintext": "{}"}}"#, PLAINTEXT)),
+            ),
+            _ => (404, "{}".to_string()),
+        })
+        .await;
+        let mut config = common::server_config();
+        config.vault.address = format!("http://{}", address);
+        config.vault.credentials = vec![Credentials::UserPass(UserPass::new(
+            Source::Value("vault-kms-provider".to_string()),
+            Source::Value(PASSWORD.to_string()),
+            None,
+        ))];
+        tracing::debug!("Configuration: {:?}", config);
+        let mut client = Client::new(
+            connect(&config.vault.address, &config.tls, &config.vault.timeouts).unwrap(),
+            &config.vault,
+        );
+
+        let token = client.get_token().await.unwrap();
+        client.set_token(&token);
+        let ciphertext = client.request_encryption(PLAINTEXT).await.unwrap();
+        let plaintext = client.request_decryption(&ciphertext).await.unwrap();
+
+        assert_eq!(plaintext.as_str(), PLAINTEXT);
+        let logs = output.contents();
+        assert!(logs.contains("<redacted"), "nothing was logged:\n{}", logs);
+        for secret in [PASSWORD, TOKEN, PLAINTEXT, CIPHERTEXT] {
+            assert!(
+                !logs.contains(secret),
+                "\"{}\" was logged:\n{}",
+                secret,
+                logs
+            );
+        }
+    }
+}
@@ -4,36 +4,53 @@ mod common;
 mod server {
     use super::common;
     use lib::configuration::authentication::Credentials;
+    use lib::kms::StatusRequest;
     use lib::server;
     use lib::utilities::logging;
     use lib::utilities::source::Source;
     use pretty_assertions::assert_eq;
+    use std::ffi::OsString;
+    use std::sync::OnceLock;
     use std::time::Duration;
-    use tokio;
+    use tonic::Request;
 
     extern crate lib;
 
+    static UNIX_SOCKET_PATH: OnceLock<OsString> = OnceLock::new();
+
     #[tokio::test]
-    async fn throws_an_error_if_initialization_fails() -> Result<(), Box<dyn std::error::Error>> {
+    async fn reports_why_initialization_fails() {
         let mut config = common::server_config();
-        config.vault.credentials = Credentials::Token(Source::Value("invalid".to_string()));
-        let success = "success!".to_string();
-        let result = tokio::select! {
-            r = async {
-                server(config).await.map(|_| "failure".to_string())
-            } => r,
-            r = async {
+        config.vault.credentials = vec![Credentials::Token(Source::Value("invalid".to_string()))];
+        let socket_path = config.socket.socket_path.clone();
+        common::run_against_server(config, || async {
+            let mut client = common::client(&socket_path, &UNIX_SOCKET_PATH)
+                .await
+                .unwrap();
+            let mut healthz = String::new();
+            for _ in 0..50 {
+                healthz = client
+                    .status(Request::new(StatusRequest {}))
+                    .await
+                    .unwrap()
+                    .into_inner()
+                    .healthz;
+                if healthz.starts_with("Vault encryption is not initialized: ") {
+                    return;
+                }
                 tokio::time::sleep(Duration::from_millis(100)).await;
-                Ok(success.clone())
-            } => r,
-        };
-        assert!(result.is_err());
-        Ok(())
+            }
+            panic!(
+                "Status did not report the initialization failure: {}",
+                healthz
+            );
+        })
+        .await;
     }
 
     #[tokio::test]
     async fn connects_and_runs_without_error() -> Result<(), Box<dyn std::error::Error>> {
-        logging::initialize();
+        logging::initialize().unwrap();
         let config = common::server_config();
         let success = "success!".to_string();
         let result = tokio::select! {
@@ -0,0 +1,82 @@
+mod common;
+
+#[cfg(test)]
+mod telemetry {
+    use super::common;
+    use lib::configuration::telemetry::TelemetryConfiguration;
+    use lib::telemetry::{self, TRACEPARENT};
+    use lib::vault::{connect, Client};
+    use opentelemetry_sdk::trace::InMemorySpanExporter;
+    use pretty_assertions::assert_eq;
+    use std::sync::{Arc, Mutex};
+    use tracing::info_span;
+    use tracing::Instrument;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    const CALLER_TRACEPARENT: &str = "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01";
+
+    async fn traceparents_sent_to_vault(span: tracing::Span) -> Vec<Option<String>> {
+        let traceparents = Arc::new(Mutex::new(vec![]));
+        let captured = traceparents.clone();
+        let address = common::stand_in(move |request| {
+            captured
+                .lock()
+                .unwrap()
+                .push(request.headers.get(TRACEPARENT).cloned());
+            (
+                200,
+                common::data_response(r#"{"ciphertext": "vault:v1:c2VjcmV0"}"#),
+            )
+        })
+        .await;
+        let config = common::server_config();
+        let client = Client::new(
+            connect(
+                &format!("http://{}", address),
+                &config.tls,
+                &config.vault.timeouts,
+            )
+            .unwrap(),
+            &config.vault,
+        );
+        client
+            .request_encryption("c2VjcmV0")
+            .instrument(span)
+            .await
+            .unwrap();
+        let traceparents = traceparents.lock().unwrap().clone();
+        traceparents
+    }
+
+    #[tokio::test]
+    async fn continues_the_callers_trace_in_requests_to_vault() {
+        let exporter = InMemorySpanExporter::default();
+        let provider = telemetry::tracer_provider(&TelemetryConfiguration::new(None, None, None))
+            .with_simple_exporter(exporter.clone())
+            .build();
+        let _subscriber = tracing::subscriber::set_default(
+            tracing_subscriber::registry().with(telemetry::tracing_layer(&provider)),
+        );
+        let span = info_span!("encrypt");
+        telemetry::continue_trace(&span, Some(CALLER_TRACEPARENT));
+
+        let traceparents = traceparents_sent_to_vault(span).await;
+
+        assert_eq!(traceparents.len(), 1);
+        let traceparent = traceparents[0].clone().unwrap();
+        assert!(traceparent.starts_with("00-0af7651916cd43dd8448eb211c80319c-"));
+        assert!(traceparent.ends_with("-01"));
+        assert_ne!(traceparent, CALLER_TRACEPARENT);
+        let exported = exporter.get_finished_spans().unwrap();
+        assert!(exported
+            .iter()
+            .any(|span| traceparent.contains(&format!("-{}-", span.span_context.span_id()))));
+    }
+
+    #[tokio::test]
+    async fn sends_no_trace_context_without_tracing() {
+        let traceparents = traceparents_sent_to_vault(info_span!("encrypt")).await;
+
+        assert_eq!(traceparents, vec![None]);
+    }
+}
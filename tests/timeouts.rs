@@ -0,0 +1,48 @@
+mod common;
+
+#[cfg(test)]
+mod timeouts {
+    use super::common;
+    use lib::configuration::vault::Timeouts;
+    use lib::vault::{connect, Client};
+    use pretty_assertions::assert_eq;
+    use std::time::Duration;
+    use tokio::net::TcpListener;
+    use tonic::{Code, Status};
+
+    /// Accepts connections without ever responding, like a hung Vault node.
+    async fn unresponsive_vault() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut connections = vec![];
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                connections.push(stream);
+            }
+        });
+        format!("http://{}", address)
+    }
+
+    #[tokio::test]
+    async fn gives_up_on_requests_that_exceed_the_request_timeout() {
+        let mut config = common::server_config();
+        config.vault.address = unresponsive_vault().await;
+        config.vault.timeouts = Timeouts::new(None, Some(Duration::from_millis(100)));
+        let client = Client::new(
+            connect(&config.vault.address, &config.tls, &config.vault.timeouts).unwrap(),
+            &config.vault,
+        );
+
+        let error = tokio::time::timeout(
+            Duration::from_secs(5),
+            client.request_encryption("c2VjcmV0"),
+        )
+        .await
+        .expect("the request timeout was not applied")
+        .unwrap_err();
+
+        assert!(error.is_timeout());
+        assert_eq!(Status::from(error).code(), Code::DeadlineExceeded);
+    }
+}
@@ -0,0 +1,56 @@
+mod common;
+
+#[cfg(test)]
+mod unix_socket {
+    use super::common;
+    use base64::{prelude::BASE64_STANDARD, Engine};
+    use lib::configuration::authentication::{Credentials, UserPass};
+    use lib::utilities::source::Source;
+    use lib::vault::{connect, Client};
+    use pretty_assertions::assert_eq;
+    use std::sync::{Arc, Mutex};
+    use uuid::Uuid;
+
+    #[tokio::test]
+    async fn authenticates_and_encrypts_over_a_unix_socket() {
+        let requests: Arc<Mutex<Vec<common::StandInRequest>>> = Arc::new(Mutex::new(vec![]));
+        let recorded = requests.clone();
+        let socket_path = format!("./test_files/vault-{}.sock", Uuid::new_v4());
+        common::stand_in_unix(&socket_path, move |request| {
+            recorded.lock().unwrap().push(request.clone());
+            match request.uri.as_str() {
+                "/v1/auth/userpass/login/vault-kms-provider" => {
+                    (200, common::auth_response("unix-socket-token"))
+                }
+                "/v1/transit/encrypt/vault-kms-provider" => (
+                    200,
+                    common::data_response(r#"{"ciphertext": "vault:v1:ciphertext"}"#),
+                ),
+                _ => (404, "{}".to_string()),
+            }
+        });
+
+        let mut config = common::server_config();
+        config.vault.address = format!("unix://{}", socket_path);
+        config.vault.credentials = vec![Credentials::UserPass(UserPass::new(
+            Source::Value("vault-kms-provider".to_string()),
+            Source::Value("password".to_string()),
+            None,
+        ))];
+        let mut client = Client::new(
+            connect(&config.vault.address, &config.tls, &config.vault.timeouts).unwrap(),
+            &config.vault,
+        );
+        client.refresh_token().await.unwrap();
+        let ciphertext = client
+            .request_encryption(&BASE64_STANDARD.encode("secret"))
+            .await
+            .unwrap();
+
+        assert_eq!(ciphertext, "vault:v1:ciphertext");
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[1].headers["x-vault-token"], "unix-socket-token");
+        std::fs::remove_file(&socket_path).unwrap();
+    }
+}